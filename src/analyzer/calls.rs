@@ -0,0 +1,354 @@
+use anyhow::{Result, bail};
+use std::collections::HashSet;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+
+use crate::analyzer::hierarchy::HierarchyNode;
+use crate::analyzer::protocol::{CallHierarchyItem, Location, Range};
+
+pub const DEFAULT_MAX_DEPTH: usize = 3;
+pub const DEFAULT_MAX_NODES: usize = 200;
+
+/// Which edges of the call graph to follow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallDirection {
+    /// Callers of the item.
+    Incoming,
+    /// Callees of the item.
+    Outgoing,
+    Both,
+}
+
+impl CallDirection {
+    pub fn includes_incoming(self) -> bool {
+        matches!(self, CallDirection::Incoming | CallDirection::Both)
+    }
+
+    pub fn includes_outgoing(self) -> bool {
+        matches!(self, CallDirection::Outgoing | CallDirection::Both)
+    }
+}
+
+impl FromStr for CallDirection {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "incoming" | "callers" => Ok(CallDirection::Incoming),
+            "outgoing" | "callees" => Ok(CallDirection::Outgoing),
+            "both" => Ok(CallDirection::Both),
+            other => bail!("Unknown direction '{other}'; expected incoming, outgoing or both"),
+        }
+    }
+}
+
+impl fmt::Display for CallDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallDirection::Incoming => write!(f, "incoming"),
+            CallDirection::Outgoing => write!(f, "outgoing"),
+            CallDirection::Both => write!(f, "both"),
+        }
+    }
+}
+
+/// Depth and size limits shared by one call hierarchy walk.
+#[derive(Debug)]
+pub struct CallBudget {
+    pub max_depth: usize,
+    remaining: usize,
+    truncated: bool,
+}
+
+impl CallBudget {
+    pub fn new(max_depth: usize, max_nodes: usize) -> Self {
+        Self {
+            max_depth,
+            remaining: max_nodes,
+            truncated: false,
+        }
+    }
+
+    /// Reserve room for one more node, recording truncation when exhausted.
+    pub fn take_node(&mut self) -> bool {
+        if self.remaining == 0 {
+            self.truncated = true;
+            return false;
+        }
+        self.remaining -= 1;
+        true
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+/// Stable identity of a call hierarchy item used for cycle detection.
+pub fn item_key(item: &CallHierarchyItem) -> String {
+    format!(
+        "{}:{}:{}",
+        item.uri, item.selection_range.start.line, item.selection_range.start.character
+    )
+}
+
+/// Where a call hierarchy walk gets its edges and labels from; rust-analyzer
+/// outside of tests.
+pub trait CallSource: Send {
+    /// Callers or callees of `item`, each with its call sites.
+    fn calls(
+        &mut self,
+        direction: CallDirection,
+        item: &CallHierarchyItem,
+    ) -> impl Future<Output = Result<Vec<(CallHierarchyItem, Vec<Range>)>>> + Send;
+
+    /// `crate::module::item` path to label `item` with.
+    fn item_path(&mut self, item: &CallHierarchyItem) -> impl Future<Output = String> + Send;
+}
+
+/// Call tree of `item`, with one group per followed direction. Each group
+/// tracks its own expanded nodes, so a function seen among the callers is
+/// still expanded among the callees; only the node limit is shared.
+pub async fn call_tree<S: CallSource>(
+    source: &mut S,
+    item: &CallHierarchyItem,
+    direction: CallDirection,
+    budget: &mut CallBudget,
+) -> Result<HierarchyNode> {
+    let mut root = HierarchyNode::new(source.item_path(item).await, Some(item_location(item)));
+
+    for current in [CallDirection::Incoming, CallDirection::Outgoing] {
+        let (included, label) = match current {
+            CallDirection::Incoming => (direction.includes_incoming(), "incoming calls (callers)"),
+            _ => (direction.includes_outgoing(), "outgoing calls (callees)"),
+        };
+        if !included {
+            continue;
+        }
+
+        let mut walk = CallWalk {
+            direction: current,
+            budget: &mut *budget,
+            ancestors: vec![item_key(item)],
+            expanded: HashSet::new(),
+        };
+        let children = walk.expand(source, item, 1).await?;
+        root.children.push(HierarchyNode::group(label, children));
+    }
+
+    Ok(root)
+}
+
+/// State of the walk in one direction.
+struct CallWalk<'b> {
+    direction: CallDirection,
+    budget: &'b mut CallBudget,
+    ancestors: Vec<String>,
+    expanded: HashSet<String>,
+}
+
+impl CallWalk<'_> {
+    fn expand<'a, S: CallSource>(
+        &'a mut self,
+        source: &'a mut S,
+        item: &'a CallHierarchyItem,
+        depth: usize,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<HierarchyNode>>> + Send + 'a>> {
+        Box::pin(async move {
+            if depth > self.budget.max_depth {
+                return Ok(Vec::new());
+            }
+
+            let mut nodes = Vec::new();
+            for (call_item, call_sites) in source.calls(self.direction, item).await? {
+                if !self.budget.take_node() {
+                    break;
+                }
+
+                let key = item_key(&call_item);
+                let repeat = if self.ancestors.contains(&key) {
+                    Repeat::Cycle
+                } else if !self.expanded.insert(key.clone()) {
+                    Repeat::Seen
+                } else {
+                    Repeat::First
+                };
+
+                let path = source.item_path(&call_item).await;
+                let mut node = HierarchyNode::new(
+                    call_label(&path, &call_sites, repeat),
+                    Some(item_location(&call_item)),
+                );
+                if repeat == Repeat::First {
+                    self.ancestors.push(key);
+                    node.children = self.expand(source, &call_item, depth + 1).await?;
+                    self.ancestors.pop();
+                }
+                nodes.push(node);
+            }
+            Ok(nodes)
+        })
+    }
+}
+
+fn item_location(item: &CallHierarchyItem) -> Location {
+    Location {
+        uri: item.uri.clone(),
+        range: item.selection_range.clone(),
+    }
+}
+
+/// How a node relates to the nodes already in the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repeat {
+    First,
+    /// Already expanded in another branch.
+    Seen,
+    /// One of the node's own ancestors.
+    Cycle,
+}
+
+/// Label for a call node: the symbol path, its call sites (1-based
+/// `line:column` ranges) and a marker for repeated or recursive nodes.
+pub fn call_label(path: &str, call_sites: &[Range], repeat: Repeat) -> String {
+    let mut label = path.to_string();
+    if !call_sites.is_empty() {
+        let sites: Vec<String> = call_sites.iter().map(format_range).collect();
+        label.push_str(&format!(" [call sites: {}]", sites.join(", ")));
+    }
+    match repeat {
+        Repeat::First => {}
+        Repeat::Seen => label.push_str(" (expanded above)"),
+        Repeat::Cycle => label.push_str(" (cycle)"),
+    }
+    label
+}
+
+fn format_range(range: &Range) -> String {
+    format!(
+        "{}:{}-{}:{}",
+        range.start.line + 1,
+        range.start.character + 1,
+        range.end.line + 1,
+        range.end.character + 1
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CallBudget, CallDirection, CallSource, Repeat, call_label, call_tree};
+    use crate::analyzer::hierarchy::render_tree;
+    use crate::analyzer::protocol::{CallHierarchyItem, Position, Range};
+
+    #[test]
+    fn parses_directions() {
+        assert_eq!(
+            "Incoming".parse::<CallDirection>().unwrap(),
+            CallDirection::Incoming
+        );
+        assert_eq!(
+            "callees".parse::<CallDirection>().unwrap(),
+            CallDirection::Outgoing
+        );
+        assert!(CallDirection::Both.includes_incoming());
+        assert!("sideways".parse::<CallDirection>().is_err());
+    }
+
+    #[test]
+    fn budget_tracks_nodes() {
+        let mut budget = CallBudget::new(2, 1);
+        assert!(budget.take_node());
+        assert!(!budget.take_node());
+        assert!(budget.is_truncated());
+    }
+
+    /// `f` and `g` call each other.
+    struct MutualRecursion;
+
+    fn function(name: &str, line: u32) -> CallHierarchyItem {
+        let position = Position { line, character: 3 };
+        let range = Range {
+            start: position.clone(),
+            end: position,
+        };
+        CallHierarchyItem {
+            name: name.to_string(),
+            kind: 12,
+            detail: None,
+            uri: "file:///demo/src/lib.rs".to_string(),
+            range: range.clone(),
+            selection_range: range,
+            data: None,
+        }
+    }
+
+    impl CallSource for MutualRecursion {
+        async fn calls(
+            &mut self,
+            _direction: CallDirection,
+            item: &CallHierarchyItem,
+        ) -> anyhow::Result<Vec<(CallHierarchyItem, Vec<Range>)>> {
+            let other = if item.name == "f" {
+                function("g", 4)
+            } else {
+                function("f", 0)
+            };
+            Ok(vec![(other, Vec::new())])
+        }
+
+        async fn item_path(&mut self, item: &CallHierarchyItem) -> String {
+            format!("demo::{}", item.name)
+        }
+    }
+
+    #[tokio::test]
+    async fn expands_both_directions_independently() {
+        let mut budget = CallBudget::new(3, 20);
+        let tree = call_tree(
+            &mut MutualRecursion,
+            &function("f", 0),
+            CallDirection::Both,
+            &mut budget,
+        )
+        .await
+        .expect("tree built");
+
+        let lines: Vec<String> = render_tree(&tree)
+            .lines()
+            .map(|line| line.split(" (file://").next().unwrap_or(line).to_string())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "demo::f",
+                "├─ incoming calls (callers)",
+                "│  └─ demo::g",
+                "│     └─ demo::f (cycle)",
+                "└─ outgoing calls (callees)",
+                "   └─ demo::g",
+                "      └─ demo::f (cycle)",
+            ]
+        );
+    }
+
+    #[test]
+    fn labels_call_sites_and_cycles() {
+        let site = Range {
+            start: Position {
+                line: 4,
+                character: 8,
+            },
+            end: Position {
+                line: 4,
+                character: 14,
+            },
+        };
+        assert_eq!(
+            call_label("demo::run", &[site], Repeat::Cycle),
+            "demo::run [call sites: 5:9-5:15] (cycle)"
+        );
+        assert_eq!(call_label("demo::run", &[], Repeat::First), "demo::run");
+    }
+}
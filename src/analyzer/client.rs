@@ -1,10 +1,73 @@
 use anyhow::Result;
 use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::process::Stdio;
+use tokio::fs;
 use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::process::Child;
 
+use crate::analyzer::calls::{CallBudget, CallDirection, CallSource, call_tree};
+use crate::analyzer::completion::{CompletionCandidate, completion_items, render_completions};
+use crate::analyzer::edits::{numbered_lines, offset_at, position_at, write_files};
+use crate::analyzer::hierarchy::{
+    HierarchyNode, blanket_applies, blanket_impls, derived_traits, format_location, impl_header_at,
+    implemented_trait, negative_trait, render_tree, supertraits, type_kind_label,
+};
+use crate::analyzer::hover::{hover_markdown, parse_hover};
+use crate::analyzer::inlay::render_with_hints;
+use crate::analyzer::modules::{
+    crate_name, crate_src_root, insert_mod_declaration, insert_reexport, plan_module,
+    plan_module_at,
+};
+use crate::analyzer::outline::{
+    OUTLINE_KINDS, VisibilityFilter, build_outline, filter_outline, render_outline,
+};
 use crate::analyzer::protocol::*;
+use crate::analyzer::relocate::{MoveContext, plan_move, reference_targets, select_items};
+use crate::analyzer::runnables::render_runnables;
+use crate::analyzer::signature::render_signature_help;
+use crate::analyzer::ssr::{SsrMode, SsrScope, apply_file_edits, render_matches};
+use crate::analyzer::symbol::{SymbolIdentity, identity_from_definition};
+use crate::analyzer::syntax::impl_self_type;
+use crate::inspection::{InspectionLimits, truncate_with_limits};
+
+/// Navigation requests that, unlike `textDocument/definition`, report every
+/// target they find.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GotoKind {
+    Implementation,
+    TypeDefinition,
+    Declaration,
+}
+
+impl GotoKind {
+    pub fn method(self) -> &'static str {
+        match self {
+            GotoKind::Implementation => "textDocument/implementation",
+            GotoKind::TypeDefinition => "textDocument/typeDefinition",
+            GotoKind::Declaration => "textDocument/declaration",
+        }
+    }
+
+    fn singular(self) -> &'static str {
+        match self {
+            GotoKind::Implementation => "implementation",
+            GotoKind::TypeDefinition => "type definition",
+            GotoKind::Declaration => "declaration",
+        }
+    }
+
+    fn plural(self) -> &'static str {
+        match self {
+            GotoKind::Implementation => "implementations",
+            GotoKind::TypeDefinition => "type definitions",
+            GotoKind::Declaration => "declarations",
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct DefinitionDetails {
@@ -12,6 +75,59 @@ pub struct DefinitionDetails {
     pub symbol_path: SymbolPath,
 }
 
+const SYMBOL_KIND_MODULE: u32 = 2;
+const SYMBOL_KIND_TRAIT: u32 = 11;
+const SNIPPET_MAX_LINES: u32 = 5;
+const TYPE_HIERARCHY_MAX_DEPTH: usize = 4;
+
+/// All `.rs` files below `root`, sorted for stable output.
+async fn rust_source_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut stack = vec![root.to_path_buf()];
+    let mut files = Vec::new();
+
+    while let Some(path) = stack.pop() {
+        let mut entries = match fs::read_dir(&path).await {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        while let Some(entry) = entries.next_entry().await? {
+            let entry_path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                stack.push(entry_path);
+            } else if entry_path.extension().is_some_and(|ext| ext == "rs") {
+                files.push(entry_path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Call edges from rust-analyzer, labelled through each file's document
+/// symbols.
+struct AnalyzerCalls<'a> {
+    client: &'a mut RustAnalyzerClient,
+    symbol_cache: HashMap<String, Option<DocumentSymbolResponse>>,
+}
+
+impl CallSource for AnalyzerCalls<'_> {
+    async fn calls(
+        &mut self,
+        direction: CallDirection,
+        item: &CallHierarchyItem,
+    ) -> Result<Vec<(CallHierarchyItem, Vec<Range>)>> {
+        self.client.direct_calls(direction, item).await
+    }
+
+    async fn item_path(&mut self, item: &CallHierarchyItem) -> String {
+        self.client
+            .call_item_path(item, &mut self.symbol_cache)
+            .await
+    }
+}
+
 fn get_rust_analyzer_path() -> String {
     std::env::var("RUST_ANALYZER_PATH").unwrap_or_else(|_| {
         // Default to ~/.cargo/bin/rust-analyzer
@@ -24,6 +140,7 @@ pub struct RustAnalyzerClient {
     process: Option<Child>,
     request_id: u64,
     initialized: bool,
+    server_capabilities: Value,
 }
 
 impl Default for RustAnalyzerClient {
@@ -38,6 +155,7 @@ impl RustAnalyzerClient {
             process: None,
             request_id: 0,
             initialized: false,
+            server_capabilities: Value::Null,
         }
     }
 
@@ -57,7 +175,7 @@ impl RustAnalyzerClient {
     async fn initialize(&mut self) -> Result<()> {
         // Get current working directory
         let current_dir = std::env::current_dir()?;
-        let root_uri = format!("file://{}", current_dir.display());
+        let root_uri = file_uri(&current_dir);
 
         // Send initialize request
         let init_params = json!({
@@ -67,6 +185,17 @@ impl RustAnalyzerClient {
                 "version": "0.1.0"
             },
             "rootUri": root_uri,
+            "initializationOptions": {
+                "inlayHints": {
+                    "typeHints": { "enable": true },
+                    "parameterHints": { "enable": true },
+                    "chainingHints": { "enable": true },
+                    "lifetimeElisionHints": {
+                        "enable": "skip_trivial",
+                        "useParameterNames": true
+                    }
+                }
+            },
             "capabilities": {
                 "textDocument": {
                     "definition": {
@@ -75,6 +204,49 @@ impl RustAnalyzerClient {
                     "references": {
                         "dynamicRegistration": false
                     },
+                    "implementation": {
+                        "dynamicRegistration": false,
+                        "linkSupport": false
+                    },
+                    "typeDefinition": {
+                        "dynamicRegistration": false,
+                        "linkSupport": false
+                    },
+                    "declaration": {
+                        "dynamicRegistration": false,
+                        "linkSupport": false
+                    },
+                    "typeHierarchy": {
+                        "dynamicRegistration": false
+                    },
+                    "inlayHint": {
+                        "dynamicRegistration": false
+                    },
+                    "completion": {
+                        "dynamicRegistration": false,
+                        "completionItem": {
+                            "snippetSupport": true,
+                            "documentationFormat": ["markdown", "plaintext"],
+                            "labelDetailsSupport": true,
+                            "resolveSupport": {
+                                "properties": ["documentation", "detail", "additionalTextEdits"]
+                            }
+                        }
+                    },
+                    "signatureHelp": {
+                        "dynamicRegistration": false,
+                        "signatureInformation": {
+                            "documentationFormat": ["markdown", "plaintext"],
+                            "parameterInformation": {
+                                "labelOffsetSupport": true
+                            },
+                            "activeParameterSupport": true
+                        }
+                    },
+                    "hover": {
+                        "dynamicRegistration": false,
+                        "contentFormat": ["markdown", "plaintext"]
+                    },
                     "publishDiagnostics": {
                         "relatedInformation": true
                     }
@@ -83,13 +255,20 @@ impl RustAnalyzerClient {
                     "symbol": {
                         "dynamicRegistration": false
                     }
+                },
+                "experimental": {
+                    "hoverRange": true
                 }
             }
         });
 
-        let _response = self
+        let response = self
             .send_request_internal("initialize", init_params)
             .await?;
+        self.server_capabilities = response
+            .pointer("/result/capabilities")
+            .cloned()
+            .unwrap_or(Value::Null);
 
         // Send initialized notification
         self.send_notification("initialized", json!({})).await?;
@@ -125,50 +304,50 @@ impl RustAnalyzerClient {
         let content = message.to_string();
         let header = format!("Content-Length: {}\r\n\r\n", content.len());
 
-        if let Some(child) = &mut self.process {
-            if let Some(stdin) = child.stdin.as_mut() {
-                stdin.write_all(header.as_bytes()).await?;
-                stdin.write_all(content.as_bytes()).await?;
-                stdin.flush().await?;
-            }
+        if let Some(child) = &mut self.process
+            && let Some(stdin) = child.stdin.as_mut()
+        {
+            stdin.write_all(header.as_bytes()).await?;
+            stdin.write_all(content.as_bytes()).await?;
+            stdin.flush().await?;
         }
 
         Ok(())
     }
 
     async fn read_response(&mut self, expected_id: u64) -> Result<Value> {
-        if let Some(child) = &mut self.process {
-            if let Some(stdout) = child.stdout.as_mut() {
-                let mut reader = BufReader::new(stdout);
-
+        if let Some(child) = &mut self.process
+            && let Some(stdout) = child.stdout.as_mut()
+        {
+            let mut reader = BufReader::new(stdout);
+
+            loop {
+                // Read headers
+                let mut content_length: Option<usize> = None;
                 loop {
-                    // Read headers
-                    let mut content_length: Option<usize> = None;
-                    loop {
-                        let mut line = String::new();
-                        reader.read_line(&mut line).await?;
-
-                        if line == "\r\n" {
-                            break;
-                        }
+                    let mut line = String::new();
+                    reader.read_line(&mut line).await?;
 
-                        if let Some(stripped) = line.strip_prefix("Content-Length:") {
-                            let length_str = stripped.trim();
-                            content_length = Some(length_str.parse()?);
-                        }
+                    if line == "\r\n" {
+                        break;
                     }
 
-                    if let Some(length) = content_length {
-                        let mut content = vec![0u8; length];
-                        reader.read_exact(&mut content).await?;
+                    if let Some(stripped) = line.strip_prefix("Content-Length:") {
+                        let length_str = stripped.trim();
+                        content_length = Some(length_str.parse()?);
+                    }
+                }
 
-                        let response: Value = serde_json::from_slice(&content)?;
+                if let Some(length) = content_length {
+                    let mut content = vec![0u8; length];
+                    reader.read_exact(&mut content).await?;
 
-                        if let Some(id) = response.get("id") {
-                            if id.as_u64() == Some(expected_id) {
-                                return Ok(response);
-                            }
-                        }
+                    let response: Value = serde_json::from_slice(&content)?;
+
+                    if let Some(id) = response.get("id")
+                        && id.as_u64() == Some(expected_id)
+                    {
+                        return Ok(response);
                     }
                 }
             }
@@ -177,6 +356,35 @@ impl RustAnalyzerClient {
         Err(anyhow::anyhow!("Failed to read response"))
     }
 
+    /// Tell rust-analyzer about files we created or rewrote on disk so it does not
+    /// keep analysing stale contents.
+    async fn notify_files_changed(
+        &mut self,
+        created: &[PathBuf],
+        changed: &[PathBuf],
+    ) -> Result<()> {
+        let to_uris = |paths: &[PathBuf]| -> Vec<String> {
+            paths
+                .iter()
+                .map(|path| {
+                    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.clone());
+                    file_uri(&absolute)
+                })
+                .collect()
+        };
+
+        let params = create_did_change_watched_files_params(&to_uris(created), &to_uris(changed));
+        self.send_notification("workspace/didChangeWatchedFiles", params)
+            .await
+    }
+
+    /// Whether the server advertised `capability` (e.g. `typeHierarchyProvider`).
+    fn server_supports(&self, capability: &str) -> bool {
+        self.server_capabilities
+            .get(capability)
+            .is_some_and(|value| !value.is_null() && value != &Value::Bool(false))
+    }
+
     // Tool implementation methods
     fn ensure_initialized(&self) -> Result<()> {
         if self.initialized {
@@ -223,12 +431,11 @@ impl RustAnalyzerClient {
                     kind: symbol.kind,
                 }];
 
-                if let Some(children) = &symbol.children {
-                    if let Some(mut child_path) =
+                if let Some(children) = &symbol.children
+                    && let Some(mut child_path) =
                         Self::find_symbol_path_in_document_symbols(children, position)
-                    {
-                        path.append(&mut child_path);
-                    }
+                {
+                    path.append(&mut child_path);
                 }
 
                 return Some(path);
@@ -291,7 +498,7 @@ impl RustAnalyzerClient {
 
         let params = TextDocumentPositionParams {
             text_document: TextDocumentIdentifier {
-                uri: format!("file://{}", file_path),
+                uri: file_uri(file_path),
             },
             position: Position { line, character },
         };
@@ -375,6 +582,359 @@ impl RustAnalyzerClient {
         Ok(format!("References response: {response}"))
     }
 
+    /// Hover at a position, or over the range ending at `end` to get the type
+    /// of the selected expression.
+    pub async fn hover(
+        &mut self,
+        file_path: &str,
+        line: u32,
+        character: u32,
+        end: Option<Position>,
+    ) -> Result<String> {
+        if !self.initialized {
+            return Err(anyhow::anyhow!("Client not initialized"));
+        }
+
+        let start = Position { line, character };
+        let is_range = end.is_some();
+        let position = match end {
+            Some(end) => serde_json::to_value(Range { start, end })?,
+            None => serde_json::to_value(start)?,
+        };
+        let params = json!({
+            "textDocument": TextDocumentIdentifier {
+                uri: file_uri(file_path),
+            },
+            "position": position
+        });
+
+        let response = self
+            .send_request_internal("textDocument/hover", params)
+            .await?;
+        let result_value = Self::extract_result(&response)?;
+        let Some(contents) = result_value.get("contents") else {
+            return Ok("No hover information".to_string());
+        };
+
+        Ok(parse_hover(&hover_markdown(contents), is_range).render())
+    }
+
+    /// Source of `file_path` (or of the lines `range.start.line..=range.end.line`)
+    /// with inlay hints inlined, truncated to `limits`.
+    pub async fn inlay_hints(
+        &mut self,
+        file_path: &str,
+        range: Option<Range>,
+        limits: &InspectionLimits,
+    ) -> Result<String> {
+        if !self.initialized {
+            return Err(anyhow::anyhow!("Client not initialized"));
+        }
+
+        let source = fs::read_to_string(file_path).await?;
+        // `range` selects whole lines, so ask for hints up to the end of its
+        // last line.
+        let request_range = match &range {
+            Some(range) => Range {
+                start: Position {
+                    line: range.start.line,
+                    character: 0,
+                },
+                end: Position {
+                    line: range.end.line + 1,
+                    character: 0,
+                },
+            },
+            None => Range {
+                start: Position {
+                    line: 0,
+                    character: 0,
+                },
+                end: position_at(&source, source.len()),
+            },
+        };
+        let params = json!({
+            "textDocument": TextDocumentIdentifier {
+                uri: file_uri(file_path),
+            },
+            "range": request_range
+        });
+
+        let response = self
+            .send_request_internal("textDocument/inlayHint", params)
+            .await?;
+        let hints: Option<Vec<InlayHint>> =
+            serde_json::from_value(Self::extract_result(&response)?)?;
+        let hints = hints.unwrap_or_default();
+
+        let rendered = render_with_hints(&source, &hints, range.as_ref());
+        let (text, _, _) = truncate_with_limits(&rendered, limits);
+        Ok(text)
+    }
+
+    /// Nested item tree of a file, optionally filtered by item kind (see
+    /// [`OUTLINE_KINDS`]) and visibility.
+    pub async fn document_outline(
+        &mut self,
+        file_path: &str,
+        kinds: &[String],
+        visibility: Option<VisibilityFilter>,
+    ) -> Result<String> {
+        if !self.initialized {
+            return Err(anyhow::anyhow!("Client not initialized"));
+        }
+        if let Some(unknown) = kinds
+            .iter()
+            .find(|kind| !OUTLINE_KINDS.contains(&kind.as_str()))
+        {
+            return Err(anyhow::anyhow!(
+                "Unknown kind '{unknown}'; expected one of {}",
+                OUTLINE_KINDS.join(", ")
+            ));
+        }
+
+        let source = fs::read_to_string(file_path).await?;
+        let symbols = match self.request_document_symbols(&file_uri(file_path)).await? {
+            DocumentSymbolResponse::DocumentSymbols(symbols) => symbols,
+            DocumentSymbolResponse::SymbolInformation(infos) => infos
+                .into_iter()
+                .map(|info| DocumentSymbol {
+                    name: info.name,
+                    detail: info
+                        .container_name
+                        .map(|container| format!("in {container}")),
+                    kind: info.kind,
+                    range: info.location.range.clone(),
+                    selection_range: info.location.range,
+                    children: None,
+                })
+                .collect(),
+        };
+
+        let outline = filter_outline(build_outline(&source, &symbols), kinds, visibility);
+        Ok(render_outline(&outline))
+    }
+
+    pub async fn signature_help(
+        &mut self,
+        file_path: &str,
+        line: u32,
+        character: u32,
+    ) -> Result<String> {
+        if !self.initialized {
+            return Err(anyhow::anyhow!("Client not initialized"));
+        }
+
+        let params = create_text_document_position_params(file_path, line, character);
+        let response = self
+            .send_request_internal("textDocument/signatureHelp", params)
+            .await?;
+        let help: Option<SignatureHelp> = serde_json::from_value(Self::extract_result(&response)?)?;
+
+        Ok(match help {
+            Some(help) => render_signature_help(&help),
+            None => "No signature help available".to_string(),
+        })
+    }
+
+    /// Recursive expansion of the macro call, attribute or derive at a
+    /// position, or `None` when there is no macro there.
+    pub async fn expand_macro(
+        &mut self,
+        file_path: &str,
+        line: u32,
+        character: u32,
+    ) -> Result<Option<ExpandedMacro>> {
+        if !self.initialized {
+            return Err(anyhow::anyhow!("Client not initialized"));
+        }
+
+        let params = create_text_document_position_params(file_path, line, character);
+        let response = self
+            .send_request_internal("rust-analyzer/expandMacro", params)
+            .await?;
+        Ok(serde_json::from_value(Self::extract_result(&response)?)?)
+    }
+
+    /// Identity of the outermost item containing a position, looking through
+    /// inline modules.
+    pub async fn enclosing_item(
+        &mut self,
+        file_path: &str,
+        line: u32,
+        character: u32,
+    ) -> Result<Option<SymbolIdentity>> {
+        self.ensure_initialized()?;
+
+        let uri = file_uri(file_path);
+        let DocumentSymbolResponse::DocumentSymbols(symbols) =
+            self.request_document_symbols(&uri).await?
+        else {
+            return Ok(None);
+        };
+
+        let position = Position { line, character };
+        let mut path = Vec::new();
+        let mut level = symbols.as_slice();
+        while let Some(symbol) = level
+            .iter()
+            .find(|symbol| Self::position_in_range(&symbol.range, &position))
+        {
+            path.push(SymbolPathSegment {
+                name: symbol.name.clone(),
+                kind: symbol.kind,
+            });
+            if symbol.kind != SYMBOL_KIND_MODULE {
+                return Ok(identity_from_definition(&uri, &path));
+            }
+            level = symbol.children.as_deref().unwrap_or_default();
+        }
+        Ok(None)
+    }
+
+    /// Tests, benches, binaries and doctests in a file, or only those
+    /// containing `position`, with the cargo invocations that run them.
+    pub async fn list_runnables(
+        &mut self,
+        file_path: &str,
+        position: Option<Position>,
+    ) -> Result<String> {
+        if !self.initialized {
+            return Err(anyhow::anyhow!("Client not initialized"));
+        }
+
+        let params = json!({
+            "textDocument": TextDocumentIdentifier {
+                uri: file_uri(file_path),
+            },
+            "position": position
+        });
+        let response = self
+            .send_request_internal("experimental/runnables", params)
+            .await?;
+        let runnables: Option<Vec<Runnable>> =
+            serde_json::from_value(Self::extract_result(&response)?)?;
+
+        Ok(match runnables {
+            Some(runnables) if !runnables.is_empty() => render_runnables(&runnables),
+            _ => "No runnables found".to_string(),
+        })
+    }
+
+    /// Tests that exercise the function at a position.
+    pub async fn related_tests(
+        &mut self,
+        file_path: &str,
+        line: u32,
+        character: u32,
+    ) -> Result<String> {
+        if !self.initialized {
+            return Err(anyhow::anyhow!("Client not initialized"));
+        }
+
+        let params = create_text_document_position_params(file_path, line, character);
+        let response = self
+            .send_request_internal("rust-analyzer/relatedTests", params)
+            .await?;
+        let tests: Option<Vec<TestInfo>> =
+            serde_json::from_value(Self::extract_result(&response)?)?;
+        let runnables: Vec<Runnable> = tests
+            .unwrap_or_default()
+            .into_iter()
+            .map(|test| test.runnable)
+            .collect();
+
+        Ok(if runnables.is_empty() {
+            "No related tests found".to_string()
+        } else {
+            render_runnables(&runnables)
+        })
+    }
+
+    /// Text of one of rust-analyzer's debug views (`rust-analyzer/syntaxTree`,
+    /// `viewHir`, `viewMir`, `viewItemTree` or `viewCrateGraph`). HIR and MIR
+    /// are rendered for the function at `position`.
+    pub async fn analyzer_view(
+        &mut self,
+        method: &str,
+        file_path: &str,
+        position: Option<Position>,
+    ) -> Result<String> {
+        if !self.initialized {
+            return Err(anyhow::anyhow!("Client not initialized"));
+        }
+
+        let params = create_analyzer_view_params(method, file_path, position.as_ref())?;
+        let response = self.send_request_internal(method, params).await?;
+        if let Some(message) = response.pointer("/error/message").and_then(|v| v.as_str()) {
+            return Err(anyhow::anyhow!("{method} failed: {message}"));
+        }
+        match Self::extract_result(&response)? {
+            Value::String(text) => Ok(text),
+            Value::Null => Err(anyhow::anyhow!("{method} returned no output")),
+            other => Ok(other.to_string()),
+        }
+    }
+
+    /// Completion candidates at a position, keeping those starting with
+    /// `prefix` (case-insensitive) up to `max_results`. Kept items are
+    /// resolved so their docs and auto-import edits are filled in.
+    pub async fn complete_at(
+        &mut self,
+        file_path: &str,
+        line: u32,
+        character: u32,
+        prefix: Option<&str>,
+        max_results: usize,
+    ) -> Result<String> {
+        if !self.initialized {
+            return Err(anyhow::anyhow!("Client not initialized"));
+        }
+
+        let params = json!({
+            "textDocument": TextDocumentIdentifier {
+                uri: file_uri(file_path),
+            },
+            "position": Position { line, character },
+            "context": { "triggerKind": 1 }
+        });
+        let response = self
+            .send_request_internal("textDocument/completion", params)
+            .await?;
+        let items = completion_items(Self::extract_result(&response)?);
+
+        let mut matching: Vec<(CompletionCandidate, Value)> = items
+            .into_iter()
+            .filter_map(|item| Some((CompletionCandidate::from_item(&item)?, item)))
+            .filter(|(candidate, _)| prefix.is_none_or(|prefix| candidate.matches_prefix(prefix)))
+            .collect();
+        matching.sort_by(|(a, _), (b, _)| a.sort_key().cmp(&b.sort_key()));
+        let total = matching.len();
+        matching.truncate(max_results);
+
+        let can_resolve = self
+            .server_capabilities
+            .pointer("/completionProvider/resolveProvider")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let mut candidates = Vec::with_capacity(matching.len());
+        for (candidate, item) in matching {
+            if !can_resolve {
+                candidates.push(candidate);
+                continue;
+            }
+            let response = self
+                .send_request_internal("completionItem/resolve", item)
+                .await?;
+            let resolved = Self::extract_result(&response)
+                .ok()
+                .and_then(|resolved| CompletionCandidate::from_item(&resolved));
+            candidates.push(resolved.unwrap_or(candidate));
+        }
+
+        Ok(render_completions(&candidates, total))
+    }
+
     pub async fn get_diagnostics(&mut self, file_path: &str) -> Result<String> {
         if !self.initialized {
             return Err(anyhow::anyhow!("Client not initialized"));
@@ -570,10 +1130,478 @@ impl RustAnalyzerClient {
         if !self.initialized {
             return Err(anyhow::anyhow!("Client not initialized"));
         }
-        // This would use rust-analyzer's type hierarchy capability
-        Ok(format!(
-            "Type hierarchy for symbol at {file_path}:{line}:{character}"
-        ))
+
+        let position = Position { line, character };
+        let root = if self.server_supports("typeHierarchyProvider") {
+            self.lsp_type_hierarchy(file_path, &position).await?
+        } else {
+            None
+        };
+        let root = match root {
+            Some(root) => root,
+            None => {
+                self.type_hierarchy_from_implementations(file_path, &position)
+                    .await?
+            }
+        };
+
+        Ok(render_tree(&root))
+    }
+
+    /// Build the hierarchy from `typeHierarchy/supertypes` and `subtypes`.
+    async fn lsp_type_hierarchy(
+        &mut self,
+        file_path: &str,
+        position: &Position,
+    ) -> Result<Option<HierarchyNode>> {
+        let params =
+            create_text_document_position_params(file_path, position.line, position.character);
+        let response = self
+            .send_request_internal("textDocument/prepareTypeHierarchy", params)
+            .await?;
+        let items: Option<Vec<TypeHierarchyItem>> =
+            serde_json::from_value(Self::extract_result(&response)?)?;
+        let Some(item) = items.and_then(|items| items.into_iter().next()) else {
+            return Ok(None);
+        };
+
+        let mut visited = HashSet::new();
+        let supertypes = self
+            .expand_type_hierarchy("typeHierarchy/supertypes", &item, 0, &mut visited)
+            .await?;
+        let mut visited = HashSet::new();
+        let subtypes = self
+            .expand_type_hierarchy("typeHierarchy/subtypes", &item, 0, &mut visited)
+            .await?;
+
+        let mut root = Self::type_hierarchy_node(&item);
+        root.children = vec![
+            HierarchyNode::group("supertypes", supertypes),
+            HierarchyNode::group("subtypes", subtypes),
+        ];
+        Ok(Some(root))
+    }
+
+    fn expand_type_hierarchy<'a>(
+        &'a mut self,
+        method: &'a str,
+        item: &'a TypeHierarchyItem,
+        depth: usize,
+        visited: &'a mut HashSet<String>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<HierarchyNode>>> + Send + 'a>> {
+        Box::pin(async move {
+            if depth >= TYPE_HIERARCHY_MAX_DEPTH {
+                return Ok(Vec::new());
+            }
+
+            let response = self
+                .send_request_internal(method, json!({ "item": item }))
+                .await?;
+            let related: Option<Vec<TypeHierarchyItem>> =
+                serde_json::from_value(Self::extract_result(&response)?)?;
+
+            let mut nodes = Vec::new();
+            for related_item in related.unwrap_or_default() {
+                let key = format!(
+                    "{}:{}:{}",
+                    related_item.uri,
+                    related_item.selection_range.start.line,
+                    related_item.selection_range.start.character
+                );
+                let mut node = Self::type_hierarchy_node(&related_item);
+                if visited.insert(key) {
+                    node.children = self
+                        .expand_type_hierarchy(method, &related_item, depth + 1, visited)
+                        .await?;
+                }
+                nodes.push(node);
+            }
+            Ok(nodes)
+        })
+    }
+
+    fn type_hierarchy_node(item: &TypeHierarchyItem) -> HierarchyNode {
+        let label = match &item.detail {
+            Some(detail) if !detail.is_empty() => {
+                format!("{} {} — {detail}", type_kind_label(item.kind), item.name)
+            }
+            _ => format!("{} {}", type_kind_label(item.kind), item.name),
+        };
+        HierarchyNode::new(
+            label,
+            Some(Location {
+                uri: item.uri.clone(),
+                range: item.selection_range.clone(),
+            }),
+        )
+    }
+
+    /// Fallback for servers without type hierarchy support: supertraits come
+    /// from the declaration, implementors and implemented traits from
+    /// `textDocument/implementation`, plus derives and the blanket impls in
+    /// the crate's sources whose bounds are among those traits.
+    async fn type_hierarchy_from_implementations(
+        &mut self,
+        file_path: &str,
+        position: &Position,
+    ) -> Result<HierarchyNode> {
+        let source = fs::read_to_string(file_path).await?;
+        let uri = file_uri(file_path);
+        let symbol = match self.request_document_symbols(&uri).await? {
+            DocumentSymbolResponse::DocumentSymbols(symbols) => {
+                Self::document_symbol_at(&symbols, position).cloned()
+            }
+            DocumentSymbolResponse::SymbolInformation(infos) => infos
+                .into_iter()
+                .find(|info| Self::position_in_range(&info.location.range, position))
+                .map(|info| DocumentSymbol {
+                    name: info.name,
+                    detail: None,
+                    kind: info.kind,
+                    range: info.location.range.clone(),
+                    selection_range: info.location.range,
+                    children: None,
+                }),
+        }
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "No type or trait found at {file_path}:{}:{}",
+                position.line,
+                position.character
+            )
+        })?;
+
+        let name_position = symbol.selection_range.start.clone();
+        let mut root = HierarchyNode::new(
+            format!("{} {}", type_kind_label(symbol.kind), symbol.name),
+            Some(Location {
+                uri: uri.clone(),
+                range: symbol.selection_range.clone(),
+            }),
+        );
+
+        let implementations = self
+            .request_locations("textDocument/implementation", file_path, &name_position)
+            .await?;
+        let mut file_cache: HashMap<String, String> = HashMap::new();
+        file_cache.insert(uri.clone(), source.clone());
+        let mut impl_headers = Vec::new();
+        for location in implementations {
+            if !file_cache.contains_key(&location.uri) {
+                let text = fs::read_to_string(path_from_uri(&location.uri))
+                    .await
+                    .unwrap_or_default();
+                file_cache.insert(location.uri.clone(), text);
+            }
+            let text = &file_cache[&location.uri];
+            let header = impl_header_at(text, offset_at(text, &location.range.start))
+                .map(|(_, header)| header);
+            impl_headers.push((header, location));
+        }
+
+        if symbol.kind == SYMBOL_KIND_TRAIT {
+            let declaration_start = offset_at(&source, &symbol.range.start);
+            let declaration_end = offset_at(&source, &symbol.range.end);
+            let mut supertrait_nodes = Vec::new();
+            for (path, offset) in supertraits(&source[declaration_start..declaration_end]) {
+                let bound_position = position_at(&source, declaration_start + offset);
+                let location = self
+                    .definition_details(file_path, bound_position.line, bound_position.character)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|details| details.location);
+                supertrait_nodes.push(HierarchyNode::new(path, location));
+            }
+
+            let implementors = impl_headers
+                .into_iter()
+                .map(|(header, location)| {
+                    HierarchyNode::new(header.unwrap_or_else(|| "impl".to_string()), Some(location))
+                })
+                .collect();
+            root.children = vec![
+                HierarchyNode::group("supertraits", supertrait_nodes),
+                HierarchyNode::group("implementors", implementors),
+            ];
+            root.children.retain(|group| !group.children.is_empty());
+            return Ok(root);
+        }
+
+        // Traits the type is known to implement, and auto traits it opts out
+        // of. Other auto traits depend on its fields and are only assumed.
+        let mut implemented: HashSet<String> = HashSet::new();
+        let mut opted_out: HashSet<String> = HashSet::new();
+        let mut inherent = Vec::new();
+        let mut trait_impls = Vec::new();
+        for (header, location) in impl_headers {
+            let header = header.unwrap_or_else(|| "impl".to_string());
+            let is_trait_impl = impl_self_type(&header).is_some_and(|(_, is_trait)| is_trait);
+            implemented.extend(implemented_trait(&header));
+            opted_out.extend(negative_trait(&header));
+            let node = HierarchyNode::new(header, Some(location));
+            if is_trait_impl {
+                trait_impls.push(node);
+            } else {
+                inherent.push(node);
+            }
+        }
+
+        let symbol_start = offset_at(&source, &symbol.range.start);
+        let derived_names = derived_traits(&source, symbol_start);
+        implemented.extend(derived_names.iter().cloned());
+        let derived = derived_names
+            .into_iter()
+            .map(|name| HierarchyNode::new(name, None))
+            .collect();
+
+        let mut blanket = Vec::new();
+        if let Some(src_root) = crate_src_root(Path::new(file_path)) {
+            for path in rust_source_files(&src_root).await? {
+                let uri = file_uri(&path);
+                let text = match file_cache.get(&uri) {
+                    Some(text) => text.clone(),
+                    None => fs::read_to_string(&path).await.unwrap_or_default(),
+                };
+                for (offset, header) in blanket_impls(&text) {
+                    let Some(assumed) = blanket_applies(&header, &implemented, &opted_out) else {
+                        continue;
+                    };
+                    let label = if assumed.is_empty() {
+                        header
+                    } else {
+                        format!("{header} (assuming the type is {})", assumed.join(" + "))
+                    };
+                    let start = position_at(&text, offset);
+                    blanket.push(HierarchyNode::new(
+                        label,
+                        Some(Location {
+                            uri: uri.clone(),
+                            range: Range {
+                                start: start.clone(),
+                                end: start,
+                            },
+                        }),
+                    ));
+                }
+            }
+        }
+
+        root.children = vec![
+            HierarchyNode::group("trait impls", trait_impls),
+            HierarchyNode::group("derived", derived),
+            HierarchyNode::group("inherent impls", inherent),
+        ];
+        root.children.retain(|group| !group.children.is_empty());
+        // Only the crate's own sources are scanned, so say so even when empty.
+        root.children.push(HierarchyNode::group(
+            "blanket impls in this crate (std and dependency blanket impls are not listed)",
+            blanket,
+        ));
+        Ok(root)
+    }
+
+    fn document_symbol_at<'a>(
+        symbols: &'a [DocumentSymbol],
+        position: &Position,
+    ) -> Option<&'a DocumentSymbol> {
+        symbols
+            .iter()
+            .find(|symbol| Self::position_in_range(&symbol.range, position))
+            .map(|symbol| {
+                symbol
+                    .children
+                    .as_deref()
+                    .and_then(|children| Self::document_symbol_at(children, position))
+                    .filter(|child| Self::position_in_range(&child.selection_range, position))
+                    .unwrap_or(symbol)
+            })
+    }
+
+    /// Send a navigation request such as `textDocument/implementation` and
+    /// return every location it yields.
+    async fn request_locations(
+        &mut self,
+        method: &str,
+        file_path: &str,
+        position: &Position,
+    ) -> Result<Vec<Location>> {
+        let params =
+            create_text_document_position_params(file_path, position.line, position.character);
+        let response = self.send_request_internal(method, params).await?;
+
+        let result_value = Self::extract_result(&response)?;
+        let locations: Option<DefinitionResponse> = serde_json::from_value(result_value)?;
+        Ok(match locations {
+            Some(DefinitionResponse::SingleLocation(location)) => vec![location],
+            Some(DefinitionResponse::LocationArray(locations)) => locations,
+            Some(DefinitionResponse::LocationLinks(links)) => links
+                .into_iter()
+                .map(|link| Location {
+                    uri: link.target_uri,
+                    range: link.target_selection_range,
+                })
+                .collect(),
+            None => Vec::new(),
+        })
+    }
+
+    /// All targets of an implementation, type definition or declaration
+    /// request, each with its symbol path and a source snippet.
+    pub async fn goto_locations(
+        &mut self,
+        kind: GotoKind,
+        file_path: &str,
+        line: u32,
+        character: u32,
+    ) -> Result<String> {
+        if !self.initialized {
+            return Err(anyhow::anyhow!("Client not initialized"));
+        }
+
+        let locations = self
+            .request_locations(kind.method(), file_path, &Position { line, character })
+            .await?;
+        if locations.is_empty() {
+            return Ok(format!("No {} found", kind.plural()));
+        }
+
+        let noun = if locations.len() == 1 {
+            kind.singular()
+        } else {
+            kind.plural()
+        };
+        let mut output = format!("{} {noun}:", locations.len());
+        let mut file_cache: HashMap<String, String> = HashMap::new();
+        for (index, location) in locations.iter().enumerate() {
+            let symbol_path = match self.request_document_symbols(&location.uri).await {
+                Ok(symbols) => Self::symbol_path_from_response(symbols, &location.range.start)
+                    .unwrap_or_default(),
+                Err(_) => Vec::new(),
+            };
+            let path_display =
+                Self::format_symbol_path(&symbol_path).unwrap_or_else(|| "<unnamed>".to_string());
+
+            if !file_cache.contains_key(&location.uri) {
+                let text = fs::read_to_string(path_from_uri(&location.uri))
+                    .await
+                    .unwrap_or_default();
+                file_cache.insert(location.uri.clone(), text);
+            }
+            let last_line = location
+                .range
+                .end
+                .line
+                .min(location.range.start.line + SNIPPET_MAX_LINES - 1);
+            let snippet = numbered_lines(
+                &file_cache[&location.uri],
+                location.range.start.line as usize,
+                last_line as usize,
+            );
+
+            output.push_str(&format!(
+                "\n\n{}. {} ({path_display})\n{snippet}",
+                index + 1,
+                format_location(location)
+            ));
+        }
+        Ok(output)
+    }
+
+    pub async fn call_hierarchy(
+        &mut self,
+        file_path: &str,
+        line: u32,
+        character: u32,
+        direction: CallDirection,
+        max_depth: usize,
+        max_nodes: usize,
+    ) -> Result<String> {
+        if !self.initialized {
+            return Err(anyhow::anyhow!("Client not initialized"));
+        }
+
+        let params = create_text_document_position_params(file_path, line, character);
+        let response = self
+            .send_request_internal("textDocument/prepareCallHierarchy", params)
+            .await?;
+        let items: Option<Vec<CallHierarchyItem>> =
+            serde_json::from_value(Self::extract_result(&response)?)?;
+        let item = items
+            .and_then(|items| items.into_iter().next())
+            .ok_or_else(|| anyhow::anyhow!("No callable item at {file_path}:{line}:{character}"))?;
+
+        let mut budget = CallBudget::new(max_depth, max_nodes);
+        let mut source = AnalyzerCalls {
+            client: self,
+            symbol_cache: HashMap::new(),
+        };
+        let root = call_tree(&mut source, &item, direction, &mut budget).await?;
+
+        let mut output = render_tree(&root);
+        if budget.is_truncated() {
+            output.push_str(&format!("\n\nTruncated: node limit of {max_nodes} reached"));
+        }
+        Ok(output)
+    }
+
+    /// Callers (`incomingCalls`) or callees (`outgoingCalls`) of `item`.
+    async fn direct_calls(
+        &mut self,
+        direction: CallDirection,
+        item: &CallHierarchyItem,
+    ) -> Result<Vec<(CallHierarchyItem, Vec<Range>)>> {
+        let params = json!({ "item": item });
+        Ok(match direction {
+            CallDirection::Incoming => {
+                let response = self
+                    .send_request_internal("callHierarchy/incomingCalls", params)
+                    .await?;
+                let calls: Option<Vec<CallHierarchyIncomingCall>> =
+                    serde_json::from_value(Self::extract_result(&response)?)?;
+                calls
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|call| (call.from, call.from_ranges))
+                    .collect()
+            }
+            _ => {
+                let response = self
+                    .send_request_internal("callHierarchy/outgoingCalls", params)
+                    .await?;
+                let calls: Option<Vec<CallHierarchyOutgoingCall>> =
+                    serde_json::from_value(Self::extract_result(&response)?)?;
+                calls
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|call| (call.to, call.from_ranges))
+                    .collect()
+            }
+        })
+    }
+
+    /// `crate::module::item` path for a call hierarchy item, resolved through
+    /// the document symbols of its file.
+    async fn call_item_path(
+        &mut self,
+        item: &CallHierarchyItem,
+        symbol_cache: &mut HashMap<String, Option<DocumentSymbolResponse>>,
+    ) -> String {
+        if !symbol_cache.contains_key(&item.uri) {
+            let symbols = self.request_document_symbols(&item.uri).await.ok();
+            symbol_cache.insert(item.uri.clone(), symbols);
+        }
+
+        symbol_cache
+            .get(&item.uri)
+            .cloned()
+            .flatten()
+            .and_then(|symbols| {
+                Self::symbol_path_from_response(symbols, &item.selection_range.start)
+            })
+            .and_then(|path| identity_from_definition(&item.uri, &path))
+            .map(|identity| identity.qualified_path())
+            .unwrap_or_else(|| item.name.clone())
     }
 
     pub async fn suggest_dependencies(
@@ -595,14 +1623,58 @@ impl RustAnalyzerClient {
         module_name: &str,
         module_path: &str,
         is_public: bool,
+        reexport: bool,
     ) -> Result<String> {
         if !self.initialized {
             return Err(anyhow::anyhow!("Client not initialized"));
         }
+
+        let plan = plan_module(module_name, Path::new(module_path))?;
+        let parent_source = fs::read_to_string(&plan.parent_file).await?;
+        let visibility = if is_public { "pub " } else { "" };
+        let mut updated_parent = insert_mod_declaration(&parent_source, module_name, visibility)?;
+        if reexport {
+            updated_parent = insert_reexport(&updated_parent, module_name);
+        }
+
+        if let Some(dir) = plan.module_file.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&plan.module_file)
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!("Refusing to overwrite {}: {e}", plan.module_file.display())
+            })?;
+        // Without its declaration the new file would be an orphan that makes a
+        // retry fail with "already exists", so remove it again.
+        if let Err(e) = fs::write(&plan.parent_file, updated_parent).await {
+            let _ = fs::remove_file(&plan.module_file).await;
+            return Err(anyhow::anyhow!(
+                "Failed to declare the module in {}: {e}",
+                plan.parent_file.display()
+            ));
+        }
+
+        self.notify_files_changed(
+            std::slice::from_ref(&plan.module_file),
+            std::slice::from_ref(&plan.parent_file),
+        )
+        .await?;
+
         let visibility = if is_public { "pub " } else { "" };
-        Ok(format!(
-            "Created {visibility}module '{module_name}' at {module_path}"
-        ))
+        let mut summary = format!(
+            "Created {visibility}module '{module_name}' at {} (layout: {})\nDeclared `{visibility}mod {module_name};` in {}",
+            plan.module_file.display(),
+            plan.layout,
+            plan.parent_file.display()
+        );
+        if reexport {
+            summary.push_str(&format!("\nRe-exported with `pub use {module_name}::*;`"));
+        }
+        Ok(summary)
     }
 
     pub async fn move_items(
@@ -614,9 +1686,216 @@ impl RustAnalyzerClient {
         if !self.initialized {
             return Err(anyhow::anyhow!("Client not initialized"));
         }
-        Ok(format!(
-            "Moved {} items from {source_file} to {target_file}: {item_names:?}",
-            item_names.len()
-        ))
+        if item_names.is_empty() {
+            return Err(anyhow::anyhow!("No items to move"));
+        }
+
+        let source_path = std::path::absolute(source_file)?;
+        let target_path = std::path::absolute(target_file)?;
+        if source_path == target_path {
+            return Err(anyhow::anyhow!("Source and target are the same file"));
+        }
+        let src_root = crate_src_root(&source_path).ok_or_else(|| {
+            anyhow::anyhow!("{} is not inside a crate's src/", source_path.display())
+        })?;
+        if !target_path.starts_with(&src_root) {
+            return Err(anyhow::anyhow!(
+                "Target {} is not in the same crate as {}",
+                target_path.display(),
+                source_path.display()
+            ));
+        }
+        let crate_name = crate_name(&src_root).ok_or_else(|| {
+            anyhow::anyhow!("Could not read the package name for {}", src_root.display())
+        })?;
+
+        let source = fs::read_to_string(&source_path).await?;
+        let source_uri = file_uri(&source_path);
+        let symbols = match self.request_document_symbols(&source_uri).await? {
+            DocumentSymbolResponse::DocumentSymbols(symbols) => symbols,
+            DocumentSymbolResponse::SymbolInformation(infos) => infos
+                .into_iter()
+                .filter(|info| info.container_name.is_none())
+                .map(|info| DocumentSymbol {
+                    name: info.name,
+                    detail: None,
+                    kind: info.kind,
+                    range: info.location.range.clone(),
+                    selection_range: info.location.range,
+                    children: None,
+                })
+                .collect(),
+        };
+
+        let names: Vec<String> = item_names.iter().map(|name| name.to_string()).collect();
+        let items = select_items(&source, &symbols, &names)?;
+        let mut references = Vec::new();
+        for target in reference_targets(&source, &items) {
+            let locations = self
+                .request_references(&source_path, &target.position)
+                .await?;
+            references.push((target, locations));
+        }
+
+        let mut files = BTreeMap::new();
+        files.insert(source_path.clone(), source);
+        let mut wanted: Vec<PathBuf> = references
+            .iter()
+            .flat_map(|(_, locations)| {
+                locations
+                    .iter()
+                    .map(|location| path_from_uri(&location.uri))
+            })
+            .collect();
+        if target_path.exists() {
+            wanted.push(target_path.clone());
+        } else {
+            wanted.push(plan_module_at(&target_path)?.parent_file);
+        }
+        for path in wanted {
+            if !files.contains_key(&path)
+                && let Ok(text) = fs::read_to_string(&path).await
+            {
+                files.insert(path, text);
+            }
+        }
+
+        let context = MoveContext {
+            source_path,
+            target_path: target_path.clone(),
+            crate_name,
+        };
+        let plan = plan_move(&context, &symbols, &items, &references, &files)?;
+
+        write_files(&plan.files).await?;
+        let (changed, created): (Vec<PathBuf>, Vec<PathBuf>) = plan
+            .files
+            .keys()
+            .cloned()
+            .partition(|path| files.contains_key(path));
+        self.notify_files_changed(&created, &changed).await?;
+
+        let mut summary = format!(
+            "Moved {} to {}",
+            plan.moved.join(", "),
+            target_path.display()
+        );
+        if let Some(module) = &plan.created_module {
+            summary.push_str(&format!(
+                "\nCreated module '{}' declared in {}",
+                module.module_name,
+                module.parent_file.display()
+            ));
+        }
+        if !changed.is_empty() {
+            summary.push_str("\nUpdated files:");
+            for path in &changed {
+                summary.push_str(&format!("\n  {}", path.display()));
+            }
+        }
+        if !plan.widened.is_empty() {
+            summary.push_str(&format!(
+                "\nWidened visibility: {}",
+                plan.widened.join(", ")
+            ));
+        }
+        for note in &plan.notes {
+            summary.push_str(&format!("\nNote: {note}"));
+        }
+        Ok(summary)
+    }
+
+    /// Run a structural search and replace rule such as
+    /// `foo($a, $b) ==>> bar($b, $a)` over `scope`.
+    pub async fn ssr(&mut self, rule: &str, scope: &SsrScope, mode: SsrMode) -> Result<String> {
+        if !self.initialized {
+            return Err(anyhow::anyhow!("Client not initialized"));
+        }
+
+        let mut files: Vec<&str> = match scope {
+            _ if mode == SsrMode::ParseOnly => vec![scope.context_file()],
+            SsrScope::File(file) => vec![file],
+            SsrScope::Files(files) => files.iter().map(String::as_str).collect(),
+            SsrScope::Workspace { context_file } => vec![context_file],
+        };
+        files.sort_unstable();
+        files.dedup();
+        // rust-analyzer only matches inside `selections` of the request's
+        // document, and searches the whole workspace when there are none.
+        let restrict_to_file =
+            mode != SsrMode::ParseOnly && !matches!(scope, SsrScope::Workspace { .. });
+
+        // Keyed by path so the same file under two URIs is edited once.
+        let mut file_edits: BTreeMap<PathBuf, (String, Vec<TextEdit>)> = BTreeMap::new();
+        for file in files {
+            let mut selections = Vec::new();
+            if restrict_to_file {
+                let source = fs::read_to_string(file).await?;
+                selections.push(Range {
+                    start: Position {
+                        line: 0,
+                        character: 0,
+                    },
+                    end: position_at(&source, source.len()),
+                });
+            }
+            let params = json!({
+                "query": rule,
+                "parseOnly": mode == SsrMode::ParseOnly,
+                "textDocument": TextDocumentIdentifier {
+                    uri: file_uri(file),
+                },
+                "position": Position {
+                    line: 0,
+                    character: 0,
+                },
+                "selections": selections
+            });
+            let response = self
+                .send_request_internal("experimental/ssr", params)
+                .await?;
+            if let Some(message) = response.pointer("/error/message").and_then(Value::as_str) {
+                return Err(anyhow::anyhow!("Invalid SSR rule: {message}"));
+            }
+            let edit: Option<WorkspaceEdit> =
+                serde_json::from_value(Self::extract_result(&response)?)?;
+            for (uri, edits) in edit.unwrap_or_default().into_file_edits() {
+                file_edits
+                    .entry(path_from_uri(&uri))
+                    .or_insert_with(|| (uri, Vec::new()))
+                    .1
+                    .extend(edits);
+            }
+        }
+
+        if mode == SsrMode::ParseOnly {
+            return Ok(format!("Rule `{rule}` parsed successfully"));
+        }
+
+        let (matches, changed) = apply_file_edits(&mut file_edits, mode).await?;
+        if !changed.is_empty() {
+            self.notify_files_changed(&[], &changed).await?;
+        }
+
+        Ok(render_matches(&matches, mode == SsrMode::Apply))
+    }
+
+    async fn request_references(
+        &mut self,
+        file_path: &Path,
+        position: &Position,
+    ) -> Result<Vec<Location>> {
+        let params = create_references_params(
+            &file_path.display().to_string(),
+            position.line,
+            position.character,
+        );
+        let response = self
+            .send_request_internal("textDocument/references", params)
+            .await?;
+
+        let result_value = Self::extract_result(&response)?;
+        let locations: Option<Vec<Location>> = serde_json::from_value(result_value)?;
+        Ok(locations.unwrap_or_default())
     }
 }
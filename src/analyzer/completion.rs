@@ -0,0 +1,204 @@
+use serde_json::Value;
+
+use crate::analyzer::hover::{hover_markdown, plain_text};
+use crate::analyzer::protocol::TextEdit;
+
+pub const DEFAULT_MAX_COMPLETIONS: usize = 50;
+
+/// Fields of an LSP `CompletionItem` worth showing to an agent.
+#[derive(Debug, Clone)]
+pub struct CompletionCandidate {
+    pub label: String,
+    pub kind: &'static str,
+    pub detail: Option<String>,
+    pub insert_text: Option<String>,
+    pub is_snippet: bool,
+    pub documentation: Option<String>,
+    /// Edits applied alongside the completion, typically auto-imports.
+    pub additional_edits: Vec<TextEdit>,
+    filter_text: String,
+    sort_text: String,
+}
+
+impl CompletionCandidate {
+    pub fn from_item(item: &Value) -> Option<Self> {
+        let label = item.get("label")?.as_str()?.to_string();
+        let text = |key: &str| item.get(key).and_then(Value::as_str).map(str::to_string);
+
+        let label_detail = item
+            .pointer("/labelDetails/detail")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|detail| !detail.is_empty());
+        let detail = match (text("detail"), label_detail) {
+            (Some(detail), _) if !detail.is_empty() => Some(detail),
+            (_, Some(label_detail)) => Some(label_detail.to_string()),
+            _ => None,
+        };
+        let insert_text = text("insertText").or_else(|| {
+            item.pointer("/textEdit/newText")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        });
+        let additional_edits = item
+            .get("additionalTextEdits")
+            .cloned()
+            .and_then(|edits| serde_json::from_value(edits).ok())
+            .unwrap_or_default();
+        let documentation = item
+            .get("documentation")
+            .map(|docs| plain_text(&hover_markdown(docs)))
+            .filter(|docs| !docs.is_empty());
+
+        Some(Self {
+            kind: completion_kind(item.get("kind").and_then(Value::as_u64).unwrap_or(0)),
+            detail,
+            is_snippet: item.get("insertTextFormat").and_then(Value::as_u64) == Some(2),
+            insert_text,
+            documentation,
+            additional_edits,
+            filter_text: text("filterText").unwrap_or_else(|| label.clone()),
+            sort_text: text("sortText").unwrap_or_else(|| label.clone()),
+            label,
+        })
+    }
+
+    /// Case-insensitive prefix match against the filter text or label.
+    pub fn matches_prefix(&self, prefix: &str) -> bool {
+        let prefix = prefix.to_lowercase();
+        self.filter_text.to_lowercase().starts_with(&prefix)
+            || self.label.to_lowercase().starts_with(&prefix)
+    }
+
+    pub fn sort_key(&self) -> (&str, &str) {
+        (&self.sort_text, &self.label)
+    }
+}
+
+/// Items of a completion response, which is either a `CompletionList` or a
+/// bare array.
+pub fn completion_items(result: Value) -> Vec<Value> {
+    match result {
+        Value::Array(items) => items,
+        Value::Object(mut list) => match list.remove("items") {
+            Some(Value::Array(items)) => items,
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+pub fn completion_kind(kind: u64) -> &'static str {
+    match kind {
+        1 => "text",
+        2 => "method",
+        3 => "function",
+        4 => "constructor",
+        5 => "field",
+        6 => "variable",
+        7 => "class",
+        8 => "trait",
+        9 => "module",
+        10 => "property",
+        11 => "unit",
+        12 => "value",
+        13 => "enum",
+        14 => "keyword",
+        15 => "snippet",
+        20 => "variant",
+        21 => "constant",
+        22 => "struct",
+        24 => "operator",
+        25 => "type parameter",
+        _ => "other",
+    }
+}
+
+/// One block per candidate: label, kind and detail, then the inserted text,
+/// auto-import edits and the first line of the docs when present.
+pub fn render_completions(candidates: &[CompletionCandidate], total: usize) -> String {
+    if candidates.is_empty() {
+        return "No completions".to_string();
+    }
+
+    let mut output = format!("{} of {total} completions:", candidates.len());
+    for candidate in candidates {
+        output.push_str(&format!("\n- {} [{}]", candidate.label, candidate.kind));
+        if let Some(detail) = &candidate.detail {
+            output.push_str(&format!(" {detail}"));
+        }
+        if let Some(insert) = &candidate.insert_text
+            && insert != &candidate.label
+        {
+            let label = if candidate.is_snippet {
+                "snippet"
+            } else {
+                "insert"
+            };
+            output.push_str(&format!("\n    {label}: {}", insert.replace('\n', "\\n")));
+        }
+        for edit in &candidate.additional_edits {
+            output.push_str(&format!(
+                "\n    auto-import at {}:{}: {}",
+                edit.range.start.line + 1,
+                edit.range.start.character + 1,
+                edit.new_text.trim()
+            ));
+        }
+        if let Some(summary) = candidate
+            .documentation
+            .as_deref()
+            .and_then(|docs| docs.lines().next())
+        {
+            output.push_str(&format!("\n    docs: {summary}"));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompletionCandidate, completion_items, render_completions};
+    use serde_json::json;
+
+    #[test]
+    fn parses_filters_and_renders_items() {
+        let items = completion_items(json!({
+            "isIncomplete": false,
+            "items": [
+                {
+                    "label": "push",
+                    "kind": 2,
+                    "detail": "fn(&mut self, T)",
+                    "insertTextFormat": 2,
+                    "textEdit": {"newText": "push(${1:value})", "range": {"start": {"line": 3, "character": 6}, "end": {"line": 3, "character": 6}}},
+                    "documentation": {"kind": "markdown", "value": "Appends an element.\n\nMore."},
+                    "sortText": "7fffffff"
+                },
+                {
+                    "label": "HashMap",
+                    "kind": 22,
+                    "labelDetails": {"detail": " (use std::collections::HashMap)"},
+                    "additionalTextEdits": [
+                        {"range": {"start": {"line": 0, "character": 0}, "end": {"line": 0, "character": 0}}, "newText": "use std::collections::HashMap;\n"}
+                    ]
+                },
+                {"label": "len", "kind": 2, "detail": "fn(&self) -> usize"}
+            ]
+        }));
+        let candidates: Vec<CompletionCandidate> = items
+            .iter()
+            .filter_map(CompletionCandidate::from_item)
+            .collect();
+        assert_eq!(candidates.len(), 3);
+
+        let filtered: Vec<CompletionCandidate> = candidates
+            .into_iter()
+            .filter(|candidate| candidate.matches_prefix("p") || candidate.matches_prefix("hash"))
+            .collect();
+        assert_eq!(
+            render_completions(&filtered, 3),
+            "2 of 3 completions:\n- push [method] fn(&mut self, T)\n    snippet: push(${1:value})\n    docs: Appends an element.\n- HashMap [struct] (use std::collections::HashMap)\n    auto-import at 1:1: use std::collections::HashMap;"
+        );
+    }
+}
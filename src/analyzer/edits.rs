@@ -0,0 +1,405 @@
+use anyhow::{Context, Result, bail};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::analyzer::protocol::{Position, TextEdit, WorkspaceEdit};
+
+/// A replacement expressed in byte offsets into a source string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ByteEdit {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+impl ByteEdit {
+    pub fn replace(start: usize, end: usize, text: impl Into<String>) -> Self {
+        Self {
+            start,
+            end,
+            text: text.into(),
+        }
+    }
+
+    pub fn insert(at: usize, text: impl Into<String>) -> Self {
+        Self::replace(at, at, text)
+    }
+
+    pub fn delete(start: usize, end: usize) -> Self {
+        Self::replace(start, end, String::new())
+    }
+}
+
+/// Convert an LSP position (UTF-16 code units) into a byte offset.
+///
+/// Positions past the end of a line clamp to the line end, and positions past
+/// the last line clamp to the end of the text, matching LSP semantics.
+pub fn offset_at(text: &str, position: &Position) -> usize {
+    let mut line_start = 0usize;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(newline) => line_start += newline + 1,
+            None => return text.len(),
+        }
+    }
+
+    let line_end = text[line_start..]
+        .find('\n')
+        .map(|newline| line_start + newline)
+        .unwrap_or(text.len());
+
+    let mut units = 0u32;
+    for (index, ch) in text[line_start..line_end].char_indices() {
+        if units >= position.character {
+            return line_start + index;
+        }
+        units += ch.len_utf16() as u32;
+    }
+    line_end
+}
+
+/// Convert a byte offset into an LSP position (UTF-16 code units).
+pub fn position_at(text: &str, offset: usize) -> Position {
+    let offset = offset.min(text.len());
+    let before = &text[..offset];
+    let line = before.matches('\n').count() as u32;
+    let line_start = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+    let character = before[line_start..]
+        .chars()
+        .map(|ch| ch.len_utf16() as u32)
+        .sum();
+    Position { line, character }
+}
+
+/// Byte offset of the start of the line containing `offset`.
+pub fn line_start(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())]
+        .rfind('\n')
+        .map(|index| index + 1)
+        .unwrap_or(0)
+}
+
+/// Byte offset just past the newline terminating the line containing `offset`.
+pub fn line_end_inclusive(text: &str, offset: usize) -> usize {
+    text[offset.min(text.len())..]
+        .find('\n')
+        .map(|index| offset + index + 1)
+        .unwrap_or(text.len())
+}
+
+/// Lines `first..=last` (0-based) of `text`, each prefixed with its 1-based
+/// line number.
+pub fn numbered_lines(text: &str, first: usize, last: usize) -> String {
+    let width = (last + 1).to_string().len();
+    text.lines()
+        .enumerate()
+        .skip(first)
+        .take(last.saturating_sub(first) + 1)
+        .map(|(index, line)| format!("{:>width$} | {line}", index + 1))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Apply byte edits to `text`. Edits must not overlap.
+pub fn apply_byte_edits(text: &str, edits: &[ByteEdit]) -> Result<String> {
+    let mut sorted: Vec<&ByteEdit> = edits.iter().collect();
+    sorted.sort_by_key(|edit| (edit.start, edit.end));
+
+    let mut output = String::with_capacity(text.len());
+    let mut cursor = 0usize;
+    for edit in sorted {
+        if edit.start < cursor || edit.end > text.len() || edit.start > edit.end {
+            bail!(
+                "Overlapping or out-of-bounds edit at bytes {}..{}",
+                edit.start,
+                edit.end
+            );
+        }
+        output.push_str(&text[cursor..edit.start]);
+        output.push_str(&edit.text);
+        cursor = edit.end;
+    }
+    output.push_str(&text[cursor..]);
+    Ok(output)
+}
+
+/// Apply LSP text edits to `text`.
+pub fn apply_text_edits(text: &str, edits: &[TextEdit]) -> Result<String> {
+    let byte_edits: Vec<ByteEdit> = edits
+        .iter()
+        .map(|edit| {
+            ByteEdit::replace(
+                offset_at(text, &edit.range.start),
+                offset_at(text, &edit.range.end),
+                edit.new_text.clone(),
+            )
+        })
+        .collect();
+    apply_byte_edits(text, &byte_edits)
+}
+
+impl WorkspaceEdit {
+    /// Text edits grouped by document URI, from either `changes` or
+    /// `documentChanges`.
+    pub fn into_file_edits(self) -> BTreeMap<String, Vec<TextEdit>> {
+        let mut files = self.changes.unwrap_or_default();
+        for change in self.document_changes.unwrap_or_default() {
+            files
+                .entry(change.text_document.uri)
+                .or_default()
+                .extend(change.edits);
+        }
+        files.retain(|_, edits| !edits.is_empty());
+        files
+    }
+}
+
+/// Collect the identifiers appearing in `text`, skipping line comments.
+/// Identifiers inside string literals are kept, since format strings can
+/// name items inline.
+pub fn identifiers(text: &str) -> Vec<&str> {
+    let mut idents = Vec::new();
+    for code in code_lines(text) {
+        let mut start = None;
+        for (index, ch) in code.char_indices() {
+            let is_ident = ch == '_' || ch.is_alphanumeric();
+            match (start, is_ident) {
+                (None, true) => start = Some(index),
+                (Some(begin), false) => {
+                    push_identifier(&mut idents, &code[begin..index]);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(begin) = start {
+            push_identifier(&mut idents, &code[begin..]);
+        }
+    }
+    idents
+}
+
+/// Where a string literal being scanned ends.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Literal {
+    Code,
+    /// A `"..."` string, where `\` escapes the next character.
+    Str,
+    /// A raw string closed by `"` and this many `#`.
+    Raw(usize),
+}
+
+/// Each line of `text` up to its `//` comment, ignoring `//` inside string
+/// and char literals. Strings may span lines.
+fn code_lines(text: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut literal = Literal::Code;
+    for line in text.lines() {
+        let mut end = line.len();
+        let mut index = 0;
+        while let Some(ch) = line[index..].chars().next() {
+            let rest = &line[index + ch.len_utf8()..];
+            match (literal, ch) {
+                (Literal::Str, '\\') => index += rest.chars().next().map_or(0, char::len_utf8),
+                (Literal::Str, '"') => literal = Literal::Code,
+                (Literal::Raw(hashes), '"')
+                    if rest.bytes().take_while(|&b| b == b'#').count() >= hashes =>
+                {
+                    literal = Literal::Code;
+                    index += hashes;
+                }
+                (Literal::Code, '"') => {
+                    let before = &line[..index];
+                    let hashes = before.len() - before.trim_end_matches('#').len();
+                    let prefix = before[..before.len() - hashes].strip_suffix('r');
+                    let prefix = prefix.map(|prefix| prefix.strip_suffix('b').unwrap_or(prefix));
+                    literal = match prefix {
+                        Some(prefix)
+                            if !prefix.ends_with(|c: char| c == '_' || c.is_alphanumeric()) =>
+                        {
+                            Literal::Raw(hashes)
+                        }
+                        _ => Literal::Str,
+                    };
+                }
+                (Literal::Code, '\'') => index += char_literal_len(rest),
+                (Literal::Code, '/') if rest.starts_with('/') => {
+                    end = index;
+                    break;
+                }
+                _ => {}
+            }
+            index += ch.len_utf8();
+        }
+        lines.push(&line[..end]);
+    }
+    lines
+}
+
+/// Length of the rest of a char literal after its opening `'`, or 0 when the
+/// quote starts a lifetime or label.
+fn char_literal_len(rest: &str) -> usize {
+    if let Some(escaped) = rest.strip_prefix('\\') {
+        let first = escaped.chars().next().map_or(0, char::len_utf8);
+        return escaped[first..]
+            .find('\'')
+            .map_or(0, |close| first + close + 2);
+    }
+    match rest.chars().next() {
+        Some(ch) if rest[ch.len_utf8()..].starts_with('\'') => ch.len_utf8() + 1,
+        _ => 0,
+    }
+}
+
+fn push_identifier<'a>(idents: &mut Vec<&'a str>, candidate: &'a str) {
+    if candidate
+        .chars()
+        .next()
+        .is_some_and(|first| first == '_' || first.is_alphabetic())
+    {
+        idents.push(candidate);
+    }
+}
+
+/// Write every file or none of them. Contents are staged next to their
+/// destinations and renamed into place; if a rename fails, the files already
+/// replaced get their old contents back and new ones are removed.
+pub async fn write_files(files: &BTreeMap<PathBuf, String>) -> Result<()> {
+    let mut staged = Vec::new();
+    for (path, text) in files {
+        let temp = staging_path(path);
+        let written = async {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).await?;
+            }
+            fs::write(&temp, text).await
+        }
+        .await;
+        if let Err(e) = written {
+            remove_staged(&staged).await;
+            return Err(e).with_context(|| format!("writing {}", path.display()));
+        }
+        staged.push((path.as_path(), temp));
+    }
+
+    let mut replaced: Vec<(&Path, Option<String>)> = Vec::new();
+    for (index, (path, temp)) in staged.iter().enumerate() {
+        let original = fs::read_to_string(path).await.ok();
+        if let Err(e) = fs::rename(temp, path).await {
+            for (path, original) in replaced {
+                let _ = match original {
+                    Some(text) => fs::write(path, text).await,
+                    None => fs::remove_file(path).await,
+                };
+            }
+            remove_staged(&staged[index..]).await;
+            return Err(e).with_context(|| format!("replacing {}", path.display()));
+        }
+        replaced.push((path, original));
+    }
+    Ok(())
+}
+
+fn staging_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.rustmcp-tmp"))
+}
+
+async fn remove_staged(staged: &[(&Path, PathBuf)]) {
+    for (_, temp) in staged {
+        let _ = fs::remove_file(temp).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ByteEdit, apply_byte_edits, identifiers, offset_at, position_at, write_files};
+    use crate::analyzer::protocol::Position;
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    #[test]
+    fn converts_utf16_positions() {
+        let text = "let a = \"é😀\";\nfoo();\n";
+        let offset = offset_at(
+            text,
+            &Position {
+                line: 0,
+                character: 12,
+            },
+        );
+        assert_eq!(&text[offset..], "\";\nfoo();\n");
+        assert_eq!(
+            position_at(text, offset).character,
+            12,
+            "round trips through UTF-16 units"
+        );
+
+        let second = offset_at(
+            text,
+            &Position {
+                line: 1,
+                character: 0,
+            },
+        );
+        assert_eq!(&text[second..], "foo();\n");
+    }
+
+    #[test]
+    fn applies_non_overlapping_edits() {
+        let text = "fn a() {}\nfn b() {}\n";
+        let edits = vec![ByteEdit::insert(0, "pub "), ByteEdit::replace(13, 14, "c")];
+        assert_eq!(
+            apply_byte_edits(text, &edits).unwrap(),
+            "pub fn a() {}\nfn c() {}\n"
+        );
+
+        let overlapping = vec![ByteEdit::delete(0, 5), ByteEdit::delete(3, 8)];
+        assert!(apply_byte_edits(text, &overlapping).is_err());
+    }
+
+    #[test]
+    fn collects_identifiers_outside_comments() {
+        let idents = identifiers("let x: Vec<Foo> = make(); // Bar\n");
+        assert_eq!(idents, vec!["let", "x", "Vec", "Foo", "make"]);
+
+        let idents = identifiers("let url = \"http://x\"; Foo::new() // Bar\n");
+        assert_eq!(idents, vec!["let", "url", "http", "x", "Foo", "new"]);
+
+        let idents = identifiers("let s = r#\"a\" // \"#; Foo('\"', '/', '\\'') // Bar\n");
+        assert_eq!(idents, vec!["let", "s", "r", "a", "Foo"]);
+
+        let idents = identifiers("fn f<'a>(x: &'a str) {} // Bar\n\"multi\n//line\"; Baz\n");
+        assert_eq!(
+            idents,
+            vec!["fn", "f", "a", "x", "a", "str", "multi", "line", "Baz"]
+        );
+    }
+
+    #[tokio::test]
+    async fn writes_all_files_or_none() {
+        let scratch = tempfile::tempdir().unwrap();
+        let root = scratch.path();
+        let existing = root.join("a.rs");
+        fs::write(&existing, "old").unwrap();
+        // A file where a directory is needed makes the second write fail.
+        fs::write(root.join("blocked"), "").unwrap();
+
+        let mut files = BTreeMap::new();
+        files.insert(existing.clone(), "new".to_string());
+        files.insert(root.join("blocked/b.rs"), "b".to_string());
+        assert!(write_files(&files).await.is_err());
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "old");
+        assert!(!root.join(".a.rs.rustmcp-tmp").exists());
+
+        files.remove(&root.join("blocked/b.rs"));
+        files.insert(root.join("src/c.rs"), "c".to_string());
+        write_files(&files).await.unwrap();
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "new");
+        assert_eq!(fs::read_to_string(root.join("src/c.rs")).unwrap(), "c");
+    }
+}
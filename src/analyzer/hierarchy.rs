@@ -0,0 +1,517 @@
+use std::collections::HashSet;
+
+use crate::analyzer::edits::{line_end_inclusive, line_start};
+use crate::analyzer::protocol::Location;
+use crate::analyzer::syntax::impl_self_type;
+
+/// One node of a rendered type hierarchy.
+#[derive(Debug, Clone)]
+pub struct HierarchyNode {
+    pub label: String,
+    pub location: Option<Location>,
+    pub children: Vec<HierarchyNode>,
+}
+
+impl HierarchyNode {
+    pub fn new(label: impl Into<String>, location: Option<Location>) -> Self {
+        Self {
+            label: label.into(),
+            location,
+            children: Vec::new(),
+        }
+    }
+
+    /// A grouping node such as "supertraits" or "implementors".
+    pub fn group(label: impl Into<String>, children: Vec<HierarchyNode>) -> Self {
+        Self {
+            label: label.into(),
+            location: None,
+            children,
+        }
+    }
+}
+
+/// Render `root` as an indented tree with `uri:line:column` locations.
+pub fn render_tree(root: &HierarchyNode) -> String {
+    let mut output = node_line(root);
+    render_children(&root.children, "", &mut output);
+    output
+}
+
+fn render_children(children: &[HierarchyNode], prefix: &str, output: &mut String) {
+    for (index, child) in children.iter().enumerate() {
+        let is_last = index + 1 == children.len();
+        let (branch, continuation) = if is_last {
+            ("└─ ", "   ")
+        } else {
+            ("├─ ", "│  ")
+        };
+        output.push('\n');
+        output.push_str(prefix);
+        output.push_str(branch);
+        output.push_str(&node_line(child));
+        render_children(&child.children, &format!("{prefix}{continuation}"), output);
+    }
+}
+
+fn node_line(node: &HierarchyNode) -> String {
+    match &node.location {
+        Some(location) => format!("{} ({})", node.label, format_location(location)),
+        None => node.label.clone(),
+    }
+}
+
+/// Keyword used when labelling a symbol of LSP `kind` in the tree.
+pub fn type_kind_label(kind: u32) -> &'static str {
+    match kind {
+        10 => "enum",
+        11 => "trait",
+        23 => "struct",
+        26 => "type parameter",
+        _ => "type",
+    }
+}
+
+pub fn format_location(location: &Location) -> String {
+    format!(
+        "{}:{}:{}",
+        location.uri,
+        location.range.start.line + 1,
+        location.range.start.character + 1
+    )
+}
+
+/// Supertraits declared on a trait, both as `trait A: B + C` bounds and as
+/// `where Self: D` clauses. Returns each bound's path with the byte offset of
+/// its last segment within `declaration`.
+pub fn supertraits(declaration: &str) -> Vec<(String, usize)> {
+    let Some(header_end) = declaration.find(['{', ';']) else {
+        return Vec::new();
+    };
+    let header = &declaration[..header_end];
+    let Some(trait_start) = find_keyword(header, "trait") else {
+        return Vec::new();
+    };
+
+    let mut cursor = trait_start + "trait".len();
+    let name_len = header[cursor..]
+        .trim_start()
+        .find(|ch: char| !(ch == '_' || ch.is_alphanumeric()))
+        .unwrap_or(0);
+    cursor += header[cursor..].len() - header[cursor..].trim_start().len() + name_len;
+    if header[cursor..].starts_with('<') {
+        cursor += matching_angle(&header[cursor..]).unwrap_or(0);
+    }
+
+    let where_start = find_keyword(&header[cursor..], "where").map(|offset| cursor + offset);
+    let mut bounds = Vec::new();
+    let limit = where_start.unwrap_or(header.len());
+    let rest = header[cursor..limit].trim_start();
+    if rest.starts_with(':') {
+        let list_start = limit - rest.len() + 1;
+        collect_bounds(header, list_start, limit, &mut bounds);
+    }
+
+    if let Some(where_start) = where_start {
+        let clauses_start = where_start + "where".len();
+        for (start, end) in split_top_level(header, clauses_start, header.len(), ',') {
+            let clause = &header[start..end];
+            let trimmed = clause.trim_start();
+            if let Some(after_self) = trimmed.strip_prefix("Self")
+                && let Some(list) = after_self.trim_start().strip_prefix(':')
+            {
+                let list_start = end - list.len();
+                collect_bounds(header, list_start, end, &mut bounds);
+            }
+        }
+    }
+
+    bounds
+}
+
+fn collect_bounds(text: &str, start: usize, end: usize, bounds: &mut Vec<(String, usize)>) {
+    for (bound_start, bound_end) in split_top_level(text, start, end, '+') {
+        let raw = &text[bound_start..bound_end];
+        let bound = raw.trim();
+        if bound.is_empty() || bound.starts_with('\'') || bound.starts_with('?') {
+            continue;
+        }
+
+        let path = bound.split('<').next().unwrap_or(bound).trim();
+        let leading = raw.len() - raw.trim_start().len();
+        let last_segment = path.rfind("::").map(|index| index + 2).unwrap_or(0);
+        bounds.push((path.to_string(), bound_start + leading + last_segment));
+    }
+}
+
+fn split_top_level(text: &str, start: usize, end: usize, separator: char) -> Vec<(usize, usize)> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut part_start = start;
+    for (index, ch) in text[start..end].char_indices() {
+        match ch {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            _ if ch == separator && depth == 0 => {
+                parts.push((part_start, start + index));
+                part_start = start + index + ch.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push((part_start, end));
+    parts
+}
+
+fn matching_angle(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (index, ch) in text.char_indices() {
+        match ch {
+            '<' => depth += 1,
+            '>' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(index + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn find_keyword(text: &str, keyword: &str) -> Option<usize> {
+    let is_ident = |ch: char| ch == '_' || ch.is_alphanumeric();
+    text.match_indices(keyword)
+        .map(|(index, _)| index)
+        .find(|&index| {
+            let before = text[..index].chars().next_back();
+            let after = text[index + keyword.len()..].chars().next();
+            !before.is_some_and(is_ident) && !after.is_some_and(is_ident)
+        })
+}
+
+/// The `impl ... {` header enclosing the line at `offset`, searching a few
+/// lines upwards. Returns the header's byte offset and its text with
+/// whitespace collapsed.
+pub fn impl_header_at(text: &str, offset: usize) -> Option<(usize, String)> {
+    let mut start = line_start(text, offset);
+    for _ in 0..8 {
+        let line = &text[start..line_end_inclusive(text, start)];
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("impl") || trimmed.starts_with("unsafe impl") {
+            let header_start = start + (line.len() - trimmed.len());
+            return Some((header_start, header_text(&text[header_start..])));
+        }
+        if start == 0 {
+            break;
+        }
+        start = line_start(text, start - 1);
+    }
+    None
+}
+
+fn header_text(text: &str) -> String {
+    let end = text.find(['{', ';']).unwrap_or(text.len());
+    text[..end].split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Trait impls whose self type is one of the impl's own type parameters,
+/// e.g. `impl<T: Display> ToString for T`. Returns the byte offset and header.
+pub fn blanket_impls(source: &str) -> Vec<(usize, String)> {
+    let mut impls = Vec::new();
+    let mut offset = 0usize;
+    for line in source.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let start = offset + (line.len() - trimmed.len());
+        offset += line.len();
+
+        let generics = trimmed
+            .strip_prefix("unsafe ")
+            .unwrap_or(trimmed)
+            .strip_prefix("impl<");
+        if generics.is_none() {
+            continue;
+        }
+
+        let header = header_text(&source[start..]);
+        let Some((self_type, true)) = impl_self_type(&header) else {
+            continue;
+        };
+        let params_start = header.find('<').unwrap_or(0);
+        let Some(params_len) = matching_angle(&header[params_start..]) else {
+            continue;
+        };
+        let params = &header[params_start + 1..params_start + params_len - 1];
+        let is_blanket = split_top_level(params, 0, params.len(), ',')
+            .into_iter()
+            .filter_map(|(param_start, param_end)| {
+                let param = params[param_start..param_end].trim();
+                (!param.starts_with('\'') && !param.starts_with("const "))
+                    .then(|| param.split([':', '=']).next().unwrap_or(param).trim())
+            })
+            .any(|param| param == self_type);
+        if is_blanket {
+            impls.push((start, header));
+        }
+    }
+    impls
+}
+
+/// Traits a blanket impl requires of its self type parameter, from both the
+/// parameter list and the where clause, e.g. `["Display"]` for
+/// `impl<T: fmt::Display + ?Sized> ToText for T`. Returns last segments.
+pub fn blanket_bounds(header: &str) -> Vec<String> {
+    let Some((param, true)) = impl_self_type(header) else {
+        return Vec::new();
+    };
+    let Some(params_start) = header.find('<') else {
+        return Vec::new();
+    };
+    let Some(params_len) = matching_angle(&header[params_start..]) else {
+        return Vec::new();
+    };
+    let params_end = params_start + params_len;
+    let is_param_clause = |clause: &str| {
+        clause
+            .trim_start()
+            .strip_prefix(param.as_str())
+            .is_some_and(|rest| rest.trim_start().starts_with(':'))
+    };
+
+    let mut bounds = Vec::new();
+    let mut clauses = split_top_level(header, params_start + 1, params_end - 1, ',');
+    if let Some(where_start) = find_keyword(&header[params_end..], "where") {
+        let clauses_start = params_end + where_start + "where".len();
+        clauses.extend(split_top_level(header, clauses_start, header.len(), ','));
+    }
+    for (start, end) in clauses {
+        let clause = &header[start..end];
+        if is_param_clause(clause) {
+            let list_start = start + clause.find(':').unwrap_or(0) + 1;
+            collect_bounds(header, list_start, end, &mut bounds);
+        }
+    }
+    bounds
+        .into_iter()
+        .map(|(path, _)| path.rsplit("::").next().unwrap_or(&path).to_string())
+        .collect()
+}
+
+/// Traits the compiler implements without an impl block, depending on the
+/// type's fields. Source alone cannot confirm them.
+pub const AUTO_TRAITS: [&str; 4] = ["Sized", "Send", "Sync", "Unpin"];
+
+/// Last segment of the trait a trait impl header implements, e.g. `Display`
+/// for `impl fmt::Display for Point`. `None` for inherent and negative impls.
+pub fn implemented_trait(header: &str) -> Option<String> {
+    match impl_trait_name(header)? {
+        (name, false) => Some(name),
+        (_, true) => None,
+    }
+}
+
+/// Trait a negative impl opts out of, e.g. `Send` for `impl !Send for Rc`.
+pub fn negative_trait(header: &str) -> Option<String> {
+    match impl_trait_name(header)? {
+        (name, true) => Some(name),
+        (_, false) => None,
+    }
+}
+
+/// Last segment of an impl's trait and whether the impl is negative.
+fn impl_trait_name(header: &str) -> Option<(String, bool)> {
+    let rest = header
+        .trim()
+        .trim_start_matches("unsafe ")
+        .strip_prefix("impl")?;
+    let mut rest = rest.trim_start();
+    if rest.starts_with('<') {
+        rest = rest[matching_angle(rest)?..].trim_start();
+    }
+    let trait_path = rest[..find_keyword(rest, "for")?].trim();
+    let (trait_path, negative) = match trait_path.strip_prefix('!') {
+        Some(path) => (path.trim_start(), true),
+        None => (trait_path, false),
+    };
+    let trait_path = trait_path.split('<').next().unwrap_or(trait_path);
+    let name = trait_path.rsplit("::").next()?.trim();
+    (!name.is_empty()).then(|| (name.to_string(), negative))
+}
+
+/// Whether the blanket impl `header` applies to a type implementing
+/// `implemented`. Auto-trait bounds the type has not opted out of are taken
+/// on trust and returned, so they can be shown as assumed; `None` when some
+/// other bound is not met.
+pub fn blanket_applies(
+    header: &str,
+    implemented: &HashSet<String>,
+    opted_out: &HashSet<String>,
+) -> Option<Vec<String>> {
+    let mut assumed = Vec::new();
+    for bound in blanket_bounds(header) {
+        if implemented.contains(&bound) {
+            continue;
+        }
+        if !AUTO_TRAITS.contains(&bound.as_str()) || opted_out.contains(&bound) {
+            return None;
+        }
+        assumed.push(bound);
+    }
+    Some(assumed)
+}
+
+/// Traits listed in `#[derive(...)]` attributes directly above `item_start`.
+pub fn derived_traits(source: &str, item_start: usize) -> Vec<String> {
+    let mut start = line_start(source, item_start);
+    let mut attributes = String::new();
+    while start > 0 {
+        let previous = line_start(source, start - 1);
+        let line = source[previous..start].trim();
+        let is_attribute_part = line.starts_with("#[")
+            || line.starts_with("///")
+            || line.starts_with("//")
+            || (!attributes.is_empty() && !line.is_empty() && !line.ends_with(['}', ';']));
+        if !is_attribute_part {
+            break;
+        }
+        attributes.insert_str(0, line);
+        start = previous;
+    }
+
+    let mut traits = Vec::new();
+    let mut rest = attributes.as_str();
+    while let Some(index) = rest.find("derive(") {
+        rest = &rest[index + "derive(".len()..];
+        let end = rest.find(')').unwrap_or(rest.len());
+        traits.extend(
+            rest[..end]
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(|name| name.rsplit("::").next().unwrap_or(name).to_string()),
+        );
+        rest = &rest[end..];
+    }
+    traits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        HierarchyNode, blanket_applies, blanket_bounds, blanket_impls, derived_traits,
+        impl_header_at, implemented_trait, negative_trait, render_tree, supertraits,
+    };
+    use std::collections::HashSet;
+
+    #[test]
+    fn parses_supertraits_from_bounds_and_where_clauses() {
+        let declaration = "pub trait Shape<T: Copy>: fmt::Debug + Clone + 'static\nwhere\n    Self: Send,\n{\n    fn area(&self) -> f64;\n}";
+        let bounds = supertraits(declaration);
+        let names: Vec<&str> = bounds.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["fmt::Debug", "Clone", "Send"]);
+        assert!(declaration[bounds[0].1..].starts_with("Debug"));
+        assert!(declaration[bounds[2].1..].starts_with("Send"));
+
+        assert!(supertraits("trait Plain {}").is_empty());
+    }
+
+    #[test]
+    fn finds_impl_headers_and_blanket_impls() {
+        let source = "impl<T> Describe for T\nwhere\n    T: Debug,\n{\n    fn describe(&self) {}\n}\n\nimpl<T: Clone> Wrapper<T> {\n    fn get(&self) {}\n}\n";
+        let blanket = blanket_impls(source);
+        assert_eq!(blanket.len(), 1);
+        assert_eq!(blanket[0].1, "impl<T> Describe for T where T: Debug,");
+
+        let method = source.find("fn get").unwrap();
+        let (_, header) = impl_header_at(source, method).unwrap();
+        assert_eq!(header, "impl<T: Clone> Wrapper<T>");
+    }
+
+    #[test]
+    fn reads_blanket_bounds_and_implemented_traits() {
+        assert_eq!(
+            blanket_bounds(
+                "impl<T: fmt::Display + ?Sized, U> ToText<U> for T where T: Send + 'static, U: Copy"
+            ),
+            vec!["Display", "Send"]
+        );
+        assert_eq!(
+            blanket_bounds("impl<T> Describe for T where T: Debug,"),
+            vec!["Debug"]
+        );
+        assert!(blanket_bounds("impl<T> Any for T").is_empty());
+
+        assert_eq!(
+            implemented_trait("impl<'a> fmt::Display for Point<'a>").as_deref(),
+            Some("Display")
+        );
+        assert_eq!(implemented_trait("impl Point"), None);
+        assert_eq!(implemented_trait("impl !Send for Point"), None);
+    }
+
+    #[test]
+    fn applies_blanket_impls_with_assumed_auto_traits() {
+        let set = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<HashSet<_>>()
+        };
+        let implemented = set(&["Display"]);
+        let header = "impl<T: Display + Send> Describe for T";
+
+        assert_eq!(
+            blanket_applies(header, &implemented, &set(&[])),
+            Some(vec!["Send".to_string()])
+        );
+        assert_eq!(blanket_applies(header, &implemented, &set(&["Send"])), None);
+        assert_eq!(
+            blanket_applies("impl<T: Debug> Describe for T", &implemented, &set(&[])),
+            None
+        );
+        assert_eq!(
+            blanket_applies("impl<T: Display> Describe for T", &implemented, &set(&[])),
+            Some(Vec::new())
+        );
+
+        assert_eq!(
+            negative_trait("impl !Send for Handle").as_deref(),
+            Some("Send")
+        );
+        assert_eq!(implemented_trait("impl !Send for Handle"), None);
+    }
+
+    #[test]
+    fn collects_derived_traits() {
+        let source =
+            "/// Docs\n#[derive(Debug, Clone)]\n#[derive(serde::Serialize)]\npub struct Point;\n";
+        let item = source.find("pub struct").unwrap();
+        assert_eq!(
+            derived_traits(source, item),
+            vec!["Debug", "Clone", "Serialize"]
+        );
+    }
+
+    #[test]
+    fn renders_nested_tree() {
+        let root = HierarchyNode {
+            label: "trait Shape".to_string(),
+            location: None,
+            children: vec![
+                HierarchyNode::group("supertraits", vec![HierarchyNode::new("Debug", None)]),
+                HierarchyNode::group(
+                    "implementors",
+                    vec![
+                        HierarchyNode::new("Circle", None),
+                        HierarchyNode::new("Square", None),
+                    ],
+                ),
+            ],
+        };
+        assert_eq!(
+            render_tree(&root),
+            "trait Shape\n├─ supertraits\n│  └─ Debug\n└─ implementors\n   ├─ Circle\n   └─ Square"
+        );
+    }
+}
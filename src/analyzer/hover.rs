@@ -0,0 +1,218 @@
+use serde_json::Value;
+
+/// Hover contents split into the parts an agent usually wants.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HoverInfo {
+    /// Module or type the item lives in, e.g. `core::option::Option`.
+    pub container: Option<String>,
+    pub signature: Option<String>,
+    /// Type of a binding, field, constant or selected expression.
+    pub ty: Option<String>,
+    pub docs: Option<String>,
+}
+
+impl HoverInfo {
+    /// Plain-text rendering with one labelled section per part.
+    pub fn render(&self) -> String {
+        let mut sections = Vec::new();
+        if let Some(container) = &self.container {
+            sections.push(format!("Container: {container}"));
+        }
+        if let Some(signature) = &self.signature {
+            sections.push(format!("Signature:\n{signature}"));
+        }
+        if let Some(ty) = &self.ty {
+            sections.push(format!("Type: {ty}"));
+        }
+        if let Some(docs) = &self.docs {
+            sections.push(format!("Docs:\n{docs}"));
+        }
+        if sections.is_empty() {
+            "No hover information".to_string()
+        } else {
+            sections.join("\n\n")
+        }
+    }
+}
+
+/// Flatten the `contents` of a hover response (`MarkupContent`,
+/// `MarkedString` or an array of them) into one markdown string.
+pub fn hover_markdown(contents: &Value) -> String {
+    match contents {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .map(hover_markdown)
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n---\n\n"),
+        Value::Object(object) => {
+            let value = object
+                .get("value")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            match object.get("language").and_then(Value::as_str) {
+                Some(language) => format!("```{language}\n{value}\n```"),
+                None => value.to_string(),
+            }
+        }
+        _ => String::new(),
+    }
+}
+
+/// Split rust-analyzer hover markdown into container, signature, type and
+/// docs. With `is_range` the code block holds the type of the selection.
+pub fn parse_hover(markdown: &str, is_range: bool) -> HoverInfo {
+    let mut sections = markdown.split("\n---\n");
+    let head = sections.next().unwrap_or_default();
+    let blocks = code_blocks(head);
+
+    let mut info = HoverInfo::default();
+    if is_range {
+        info.ty = blocks.last().cloned().or_else(|| non_empty(head));
+    } else {
+        match blocks.as_slice() {
+            [] => info.docs = non_empty(&plain_text(head)),
+            [signature] => info.signature = Some(signature.clone()),
+            [container, .., signature] => {
+                info.container = Some(container.clone());
+                info.signature = Some(signature.clone());
+            }
+        }
+        info.ty = info.signature.as_deref().and_then(type_from_signature);
+    }
+
+    let docs: Vec<String> = sections
+        .map(plain_text)
+        .filter(|section| !section.is_empty())
+        .collect();
+    if !docs.is_empty() {
+        let docs = docs.join("\n\n");
+        info.docs = Some(match info.docs.take() {
+            Some(existing) => format!("{existing}\n\n{docs}"),
+            None => docs,
+        });
+    }
+    info
+}
+
+fn code_blocks(markdown: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Vec<&str>> = None;
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            match current.take() {
+                Some(lines) => blocks.push(lines.join("\n").trim().to_string()),
+                None => current = Some(Vec::new()),
+            }
+        } else if let Some(lines) = current.as_mut() {
+            lines.push(line);
+        }
+    }
+    blocks.retain(|block| !block.is_empty());
+    blocks
+}
+
+/// Type written in a `let`, field, `const` or `static` signature.
+fn type_from_signature(signature: &str) -> Option<String> {
+    let first = signature
+        .lines()
+        .find(|line| !line.trim_start().starts_with("//"))?
+        .trim();
+    let is_function = first.contains("fn ") && first.contains('(');
+    let is_type_item = [
+        "struct ",
+        "enum ",
+        "trait ",
+        "union ",
+        "type ",
+        "mod ",
+        "macro_rules!",
+    ]
+    .iter()
+    .any(|keyword| first.contains(keyword));
+    if is_function || is_type_item {
+        return None;
+    }
+
+    let (_, ty) = first.split_once(": ")?;
+    let ty = ty
+        .split(" = ")
+        .next()
+        .unwrap_or(ty)
+        .trim()
+        .trim_end_matches(',');
+    non_empty(ty)
+}
+
+/// Strip code fences and link targets so markdown reads as plain text.
+pub fn plain_text(markdown: &str) -> String {
+    let mut lines = Vec::new();
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            continue;
+        }
+        lines.push(strip_links(line));
+    }
+    lines.join("\n").trim().to_string()
+}
+
+fn strip_links(line: &str) -> String {
+    let mut output = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(open) = rest.find('[') {
+        let Some(close) = rest[open..].find("](").map(|index| open + index) else {
+            break;
+        };
+        let Some(end) = rest[close..].find(')').map(|index| close + index) else {
+            break;
+        };
+        output.push_str(&rest[..open]);
+        output.push_str(&rest[open + 1..close]);
+        rest = &rest[end + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    let trimmed = text.trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hover_markdown, parse_hover};
+    use serde_json::json;
+
+    #[test]
+    fn splits_function_hover() {
+        let markdown = "\n```rust\ndemo::shapes\n```\n\n```rust\npub fn area(width: u32, height: u32) -> u32\n```\n\n---\n\nComputes the area. See [`Shape`](https://docs.rs/demo/Shape).";
+        let info = parse_hover(markdown, false);
+        assert_eq!(info.container.as_deref(), Some("demo::shapes"));
+        assert_eq!(
+            info.signature.as_deref(),
+            Some("pub fn area(width: u32, height: u32) -> u32")
+        );
+        assert_eq!(info.ty, None);
+        assert_eq!(
+            info.docs.as_deref(),
+            Some("Computes the area. See `Shape`.")
+        );
+    }
+
+    #[test]
+    fn extracts_binding_and_range_types() {
+        let local = hover_markdown(&json!({
+            "kind": "markdown",
+            "value": "```rust\nlet total: Vec<u8>\n```\n\n---\n\nsize = 24, align = 8"
+        }));
+        let info = parse_hover(&local, false);
+        assert_eq!(info.ty.as_deref(), Some("Vec<u8>"));
+        assert_eq!(info.docs.as_deref(), Some("size = 24, align = 8"));
+
+        let range = parse_hover("```rust\nOption<&str>\n```", true);
+        assert_eq!(range.ty.as_deref(), Some("Option<&str>"));
+        assert!(range.render().starts_with("Type: Option<&str>"));
+    }
+}
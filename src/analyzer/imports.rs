@@ -0,0 +1,577 @@
+use std::collections::BTreeSet;
+
+use crate::analyzer::edits::{line_end_inclusive, line_start};
+use crate::analyzer::modules::{header_end_offset, parse_mod_declaration};
+
+/// A parsed `use` tree such as `a::b::{c, d as e, f::*}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UseTree {
+    pub path: Vec<String>,
+    pub kind: UseTreeKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UseTreeKind {
+    Simple(Option<String>),
+    Glob,
+    Group(Vec<UseTree>),
+}
+
+/// A single flattened import from a [`UseTree`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UseEntry {
+    pub path: Vec<String>,
+    pub alias: Option<String>,
+    pub glob: bool,
+}
+
+impl UseEntry {
+    /// The name this entry brings into scope, if it binds a single name.
+    pub fn bound_name(&self) -> Option<&str> {
+        if self.glob {
+            return None;
+        }
+        self.alias
+            .as_deref()
+            .or_else(|| self.path.last().map(String::as_str))
+    }
+
+    pub fn render(&self) -> String {
+        let path = self.path.join("::");
+        match (&self.alias, self.glob) {
+            (_, true) => format!("{path}::*"),
+            (Some(alias), false) => format!("{path} as {alias}"),
+            (None, false) => path,
+        }
+    }
+}
+
+/// A `use` item located in a source file.
+#[derive(Debug, Clone)]
+pub struct UseStatement {
+    /// Byte offset of the first attribute line preceding the statement, or of
+    /// the statement itself when it has none.
+    pub attributes_start: usize,
+    /// Byte offset of the start of the line holding `use`.
+    pub start: usize,
+    /// Byte offset just past the terminating `;`.
+    pub end: usize,
+    pub indent: String,
+    pub visibility: String,
+    pub tree: UseTree,
+}
+
+impl UseStatement {
+    pub fn render(&self, tree: &UseTree) -> String {
+        format!("{}{}use {};", self.indent, self.visibility, tree.render())
+    }
+
+    pub fn is_top_level(&self) -> bool {
+        self.indent.is_empty()
+    }
+}
+
+impl UseTree {
+    pub fn entries(&self) -> Vec<UseEntry> {
+        let mut entries = Vec::new();
+        self.collect_entries(&[], &mut entries);
+        entries
+    }
+
+    fn collect_entries(&self, prefix: &[String], entries: &mut Vec<UseEntry>) {
+        let mut path = prefix.to_vec();
+        path.extend(self.path.iter().cloned());
+
+        match &self.kind {
+            UseTreeKind::Simple(alias) => {
+                if path.last().is_some_and(|segment| segment == "self") {
+                    path.pop();
+                }
+                entries.push(UseEntry {
+                    path,
+                    alias: alias.clone(),
+                    glob: false,
+                });
+            }
+            UseTreeKind::Glob => entries.push(UseEntry {
+                path,
+                alias: None,
+                glob: true,
+            }),
+            UseTreeKind::Group(children) => {
+                for child in children {
+                    child.collect_entries(&path, entries);
+                }
+            }
+        }
+    }
+
+    /// Drop every entry for which `keep` returns false. Returns whether the tree
+    /// still imports anything.
+    pub fn retain(&mut self, keep: &mut impl FnMut(&UseEntry) -> bool) -> bool {
+        self.retain_with_prefix(&[], keep)
+    }
+
+    fn retain_with_prefix(
+        &mut self,
+        prefix: &[String],
+        keep: &mut impl FnMut(&UseEntry) -> bool,
+    ) -> bool {
+        let mut path = prefix.to_vec();
+        path.extend(self.path.iter().cloned());
+
+        match &mut self.kind {
+            UseTreeKind::Group(children) => {
+                children.retain_mut(|child| child.retain_with_prefix(&path, keep));
+                !children.is_empty()
+            }
+            _ => {
+                let mut entries = Vec::new();
+                self.collect_entries(prefix, &mut entries);
+                entries.iter().all(keep)
+            }
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let path = self.path.join("::");
+        let join = |suffix: &str| {
+            if path.is_empty() {
+                suffix.to_string()
+            } else {
+                format!("{path}::{suffix}")
+            }
+        };
+
+        match &self.kind {
+            UseTreeKind::Simple(Some(alias)) => format!("{path} as {alias}"),
+            UseTreeKind::Simple(None) => path,
+            UseTreeKind::Glob => join("*"),
+            UseTreeKind::Group(children) if children.len() == 1 => {
+                let child = &children[0];
+                if child.path == ["self"] && !path.is_empty() {
+                    match &child.kind {
+                        UseTreeKind::Simple(Some(alias)) => format!("{path} as {alias}"),
+                        _ => path,
+                    }
+                } else {
+                    join(&child.render())
+                }
+            }
+            UseTreeKind::Group(children) => {
+                let inner = children
+                    .iter()
+                    .map(UseTree::render)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                join(&format!("{{{inner}}}"))
+            }
+        }
+    }
+}
+
+/// Parse the body of a `use` item (without `use` and the trailing `;`).
+pub fn parse_use_tree(text: &str) -> Option<UseTree> {
+    let tokens = tokenize(text)?;
+    let mut cursor = 0;
+    let tree = parse_tree(&tokens, &mut cursor)?;
+    (cursor == tokens.len()).then_some(tree)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    PathSep,
+    Open,
+    Close,
+    Comma,
+    Star,
+}
+
+fn tokenize(text: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = text.chars().collect();
+    let mut index = 0;
+
+    while index < chars.len() {
+        let ch = chars[index];
+        match ch {
+            c if c.is_whitespace() => index += 1,
+            ':' if chars.get(index + 1) == Some(&':') => {
+                tokens.push(Token::PathSep);
+                index += 2;
+            }
+            '{' => {
+                tokens.push(Token::Open);
+                index += 1;
+            }
+            '}' => {
+                tokens.push(Token::Close);
+                index += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                index += 1;
+            }
+            '*' => {
+                tokens.push(Token::Star);
+                index += 1;
+            }
+            c if c == '_' || c.is_alphanumeric() => {
+                let start = index;
+                while index < chars.len()
+                    && (chars[index] == '_'
+                        || chars[index] == '#'
+                        || chars[index].is_alphanumeric())
+                {
+                    index += 1;
+                }
+                tokens.push(Token::Ident(chars[start..index].iter().collect()));
+            }
+            _ => return None,
+        }
+    }
+
+    Some(tokens)
+}
+
+fn parse_tree(tokens: &[Token], cursor: &mut usize) -> Option<UseTree> {
+    let mut path = Vec::new();
+
+    if tokens.get(*cursor) == Some(&Token::PathSep) {
+        path.push(String::new());
+        *cursor += 1;
+    }
+
+    loop {
+        match tokens.get(*cursor)? {
+            Token::Ident(name) if name != "as" => {
+                path.push(name.clone());
+                *cursor += 1;
+                if tokens.get(*cursor) == Some(&Token::PathSep) {
+                    *cursor += 1;
+                    continue;
+                }
+
+                let alias = if tokens.get(*cursor) == Some(&Token::Ident("as".to_string())) {
+                    *cursor += 1;
+                    match tokens.get(*cursor)? {
+                        Token::Ident(alias) => {
+                            *cursor += 1;
+                            Some(alias.clone())
+                        }
+                        _ => return None,
+                    }
+                } else {
+                    None
+                };
+                return Some(UseTree {
+                    path,
+                    kind: UseTreeKind::Simple(alias),
+                });
+            }
+            Token::Star => {
+                *cursor += 1;
+                return Some(UseTree {
+                    path,
+                    kind: UseTreeKind::Glob,
+                });
+            }
+            Token::Open => {
+                *cursor += 1;
+                let mut children = Vec::new();
+                loop {
+                    if tokens.get(*cursor) == Some(&Token::Close) {
+                        *cursor += 1;
+                        break;
+                    }
+                    children.push(parse_tree(tokens, cursor)?);
+                    match tokens.get(*cursor)? {
+                        Token::Comma => *cursor += 1,
+                        Token::Close => {
+                            *cursor += 1;
+                            break;
+                        }
+                        _ => return None,
+                    }
+                }
+                return Some(UseTree {
+                    path,
+                    kind: UseTreeKind::Group(children),
+                });
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Find `use` items in `source`, including ones nested in inline modules.
+pub fn find_use_statements(source: &str) -> Vec<UseStatement> {
+    let mut statements = Vec::new();
+    let mut offset = 0usize;
+    let mut pending_attributes: Option<usize> = None;
+
+    while offset < source.len() {
+        let line_end = source[offset..]
+            .find('\n')
+            .map(|index| offset + index + 1)
+            .unwrap_or(source.len());
+        let line = &source[offset..line_end];
+        let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
+
+        if trimmed.starts_with("#[") && trimmed.trim_end().ends_with(']') {
+            pending_attributes.get_or_insert(offset);
+            offset = line_end;
+            continue;
+        }
+
+        let (visibility, rest) = split_visibility(trimmed);
+        if let Some(body_start) = rest.strip_prefix("use ")
+            && let Some(semicolon) = source[offset..].find(';')
+        {
+            let body_offset = body_start.as_ptr() as usize - source.as_ptr() as usize;
+            let end = offset + semicolon + 1;
+            let body = &source[body_offset..end - 1];
+
+            if let Some(tree) = parse_use_tree(body) {
+                statements.push(UseStatement {
+                    attributes_start: pending_attributes.unwrap_or(offset),
+                    start: offset,
+                    end,
+                    indent: indent.to_string(),
+                    visibility: visibility.to_string(),
+                    tree,
+                });
+                pending_attributes = None;
+                offset = line_start_after(source, end);
+                continue;
+            }
+        }
+
+        if !trimmed.trim().is_empty() {
+            pending_attributes = None;
+        }
+        offset = line_end;
+    }
+
+    statements
+}
+
+fn line_start_after(source: &str, offset: usize) -> usize {
+    let next = source[offset..]
+        .find('\n')
+        .map(|index| offset + index + 1)
+        .unwrap_or(source.len());
+    line_start(source, next.max(offset))
+}
+
+fn split_visibility(line: &str) -> (&str, &str) {
+    if line.starts_with("pub(")
+        && let Some(close) = line.find(')')
+    {
+        let after = &line[close + 1..];
+        let rest = after.trim_start();
+        return (&line[..line.len() - rest.len()], rest);
+    }
+    if let Some(rest) = line.strip_prefix("pub ") {
+        let rest = rest.trim_start();
+        return (&line[..line.len() - rest.len()], rest);
+    }
+    ("", line)
+}
+
+/// Insert top-level `use` lines after the existing imports (or after the `mod`
+/// declarations when there are none), skipping lines already present.
+pub fn insert_use_lines(source: &str, lines: &[String]) -> String {
+    let existing: BTreeSet<&str> = source.lines().map(str::trim).collect();
+    let missing: BTreeSet<&str> = lines
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !existing.contains(line))
+        .collect();
+    if missing.is_empty() {
+        return source.to_string();
+    }
+
+    let mut block = missing.into_iter().collect::<Vec<_>>().join("\n");
+    block.push('\n');
+
+    let last_use = find_use_statements(source)
+        .into_iter()
+        .rfind(UseStatement::is_top_level);
+    if let Some(statement) = last_use {
+        let at = line_end_inclusive(source, statement.end);
+        let mut updated = source.to_string();
+        if !updated[..at].ends_with('\n') {
+            updated.insert(at, '\n');
+            updated.insert_str(at + 1, &block);
+        } else {
+            updated.insert_str(at, &block);
+        }
+        return updated;
+    }
+
+    let mut at = header_end_offset(source);
+    let mut offset = 0usize;
+    for line in source.split_inclusive('\n') {
+        offset += line.len();
+        if parse_mod_declaration(line.trim_end_matches(['\r', '\n'])).is_some() {
+            at = offset;
+        }
+    }
+
+    let mut insertion = String::new();
+    if at > 0 && !source[..at].ends_with("\n\n") {
+        if !source[..at].ends_with('\n') {
+            insertion.push('\n');
+        }
+        insertion.push('\n');
+    }
+    insertion.push_str(&block);
+    if source[at..]
+        .lines()
+        .next()
+        .is_some_and(|line| !line.trim().is_empty())
+    {
+        insertion.push('\n');
+    }
+
+    let mut updated = source.to_string();
+    updated.insert_str(at, &insertion);
+    updated
+}
+
+/// Resolve a crate-local path to segments relative to the crate root.
+///
+/// Returns `None` for paths into other crates.
+pub fn absolutize(
+    path: &[String],
+    module_path: &[String],
+    crate_name: &str,
+) -> Option<Vec<String>> {
+    let first = path.first()?;
+    let crate_ident = crate_name.replace('-', "_");
+
+    match first.as_str() {
+        "crate" => Some(path[1..].to_vec()),
+        "self" => {
+            let mut resolved = module_path.to_vec();
+            resolved.extend(path[1..].iter().cloned());
+            Some(resolved)
+        }
+        "super" => {
+            let supers = path
+                .iter()
+                .take_while(|segment| *segment == "super")
+                .count();
+            if supers > module_path.len() {
+                return None;
+            }
+            let mut resolved = module_path[..module_path.len() - supers].to_vec();
+            resolved.extend(path[supers..].iter().cloned());
+            Some(resolved)
+        }
+        name if name == crate_ident => Some(path[1..].to_vec()),
+        _ => None,
+    }
+}
+
+/// Render crate-relative segments as a `crate::` path.
+pub fn crate_path(segments: &[String]) -> String {
+    if segments.is_empty() {
+        "crate".to_string()
+    } else {
+        format!("crate::{}", segments.join("::"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{absolutize, find_use_statements, insert_use_lines, parse_use_tree};
+
+    fn segments(path: &str) -> Vec<String> {
+        path.split("::").map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_and_flattens_nested_groups() {
+        let tree = parse_use_tree("crate::analyzer::{self, symbol::{SymbolKind as Kind, *}}")
+            .expect("tree parses");
+        let rendered: Vec<String> = tree.entries().iter().map(|entry| entry.render()).collect();
+        assert_eq!(
+            rendered,
+            vec![
+                "crate::analyzer",
+                "crate::analyzer::symbol::SymbolKind as Kind",
+                "crate::analyzer::symbol::*",
+            ]
+        );
+        assert_eq!(
+            tree.render(),
+            "crate::analyzer::{self, symbol::{SymbolKind as Kind, *}}"
+        );
+    }
+
+    #[test]
+    fn retains_entries_and_collapses_single_groups() {
+        let mut tree = parse_use_tree("std::{fmt, path::{Path, PathBuf}}").unwrap();
+        let remaining = tree.retain(&mut |entry| entry.bound_name() != Some("fmt"));
+        assert!(remaining);
+        assert_eq!(tree.render(), "std::path::{Path, PathBuf}");
+
+        let remaining = tree.retain(&mut |entry| entry.bound_name() == Some("Path"));
+        assert!(remaining);
+        assert_eq!(tree.render(), "std::path::Path");
+
+        assert!(!tree.retain(&mut |_| false));
+    }
+
+    #[test]
+    fn finds_multiline_statements_with_attributes() {
+        let source = "use std::fmt;\n#[cfg(test)]\npub(crate) use crate::{\n    a::B,\n    c,\n};\n\nfn main() {}\n";
+        let statements = find_use_statements(source);
+        assert_eq!(statements.len(), 2);
+        assert_eq!(
+            &source[statements[0].start..statements[0].end],
+            "use std::fmt;"
+        );
+        assert_eq!(statements[1].visibility, "pub(crate) ");
+        assert_eq!(
+            &source[statements[1].attributes_start..statements[1].start],
+            "#[cfg(test)]\n"
+        );
+        assert_eq!(statements[1].tree.render(), "crate::{a::B, c}");
+    }
+
+    #[test]
+    fn inserts_use_lines_after_existing_imports() {
+        let source = "use std::fmt;\n\nfn main() {}\n";
+        let updated = insert_use_lines(
+            source,
+            &["use crate::b::B;".to_string(), "use std::fmt;".to_string()],
+        );
+        assert_eq!(updated, "use std::fmt;\nuse crate::b::B;\n\nfn main() {}\n");
+
+        let updated = insert_use_lines("pub mod a;\nfn main() {}\n", &["use a::A;".to_string()]);
+        assert_eq!(updated, "pub mod a;\n\nuse a::A;\n\nfn main() {}\n");
+    }
+
+    #[test]
+    fn absolutizes_relative_paths() {
+        let module = segments("server::handler");
+        assert_eq!(
+            absolutize(&segments("super::parameters::X"), &module, "rustmcp"),
+            Some(segments("server::parameters::X"))
+        );
+        assert_eq!(
+            absolutize(&segments("self::Y"), &module, "rustmcp"),
+            Some(segments("server::handler::Y"))
+        );
+        assert_eq!(
+            absolutize(&segments("rustmcp::tools::Z"), &module, "rustmcp"),
+            Some(segments("tools::Z"))
+        );
+        assert_eq!(absolutize(&segments("std::fmt"), &module, "rustmcp"), None);
+    }
+}
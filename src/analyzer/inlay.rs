@@ -0,0 +1,96 @@
+use crate::analyzer::edits::{ByteEdit, apply_byte_edits, offset_at};
+use crate::analyzer::protocol::{InlayHint, InlayHintLabel, Range};
+
+impl InlayHint {
+    pub fn label_text(&self) -> String {
+        match &self.label {
+            InlayHintLabel::String(text) => text.clone(),
+            InlayHintLabel::Parts(parts) => parts.iter().map(|part| part.value.as_str()).collect(),
+        }
+    }
+}
+
+/// Render `source` with each hint inlined as a `/*...*/` annotation, prefixed
+/// with 1-based line numbers. Only lines inside `range` are shown.
+pub fn render_with_hints(source: &str, hints: &[InlayHint], range: Option<&Range>) -> String {
+    let edits: Vec<ByteEdit> = hints
+        .iter()
+        .map(|hint| {
+            let mut text = String::new();
+            if hint.padding_left {
+                text.push(' ');
+            }
+            text.push_str(&format!("/*{}*/", hint.label_text().trim()));
+            if hint.padding_right {
+                text.push(' ');
+            }
+            ByteEdit::insert(offset_at(source, &hint.position), text)
+        })
+        .collect();
+
+    // Hints never overlap one another, but several can share a position.
+    let annotated = apply_byte_edits(source, &edits).unwrap_or_else(|_| source.to_string());
+
+    let (first, last) = match range {
+        Some(range) => (range.start.line as usize, range.end.line as usize),
+        None => (0, usize::MAX),
+    };
+    let width = annotated.lines().count().max(1).to_string().len();
+    annotated
+        .lines()
+        .enumerate()
+        .filter(|(index, _)| *index >= first && *index <= last)
+        .map(|(index, line)| format!("{:>width$} | {line}", index + 1))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_with_hints;
+    use crate::analyzer::protocol::{
+        InlayHint, InlayHintLabel, InlayHintLabelPart, Position, Range,
+    };
+
+    fn hint(line: u32, character: u32, label: InlayHintLabel, padding_right: bool) -> InlayHint {
+        InlayHint {
+            position: Position { line, character },
+            label,
+            kind: None,
+            padding_left: false,
+            padding_right,
+        }
+    }
+
+    #[test]
+    fn inlines_type_and_parameter_hints() {
+        let source = "fn main() {\n    let total = add(1, 2);\n}\n";
+        let hints = vec![
+            hint(1, 13, InlayHintLabel::String(": i32".to_string()), false),
+            hint(
+                1,
+                20,
+                InlayHintLabel::Parts(vec![InlayHintLabelPart {
+                    value: "a:".to_string(),
+                }]),
+                true,
+            ),
+            hint(1, 23, InlayHintLabel::String("b:".to_string()), true),
+        ];
+        let range = Range {
+            start: Position {
+                line: 1,
+                character: 0,
+            },
+            end: Position {
+                line: 1,
+                character: 0,
+            },
+        };
+
+        assert_eq!(
+            render_with_hints(source, &hints, Some(&range)),
+            "2 |     let total/*: i32*/ = add(/*a:*/ 1, /*b:*/ 2);"
+        );
+    }
+}
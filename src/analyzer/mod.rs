@@ -1,6 +1,20 @@
+pub mod calls;
 pub mod client;
+pub mod completion;
+pub mod edits;
+pub mod hierarchy;
+pub mod hover;
+pub mod imports;
+pub mod inlay;
 pub mod lsp;
+pub mod modules;
+pub mod outline;
 pub mod protocol;
+pub mod relocate;
+pub mod runnables;
+pub mod signature;
+pub mod ssr;
 pub mod symbol;
+pub mod syntax;
 
-pub use client::RustAnalyzerClient;
+pub use client::{GotoKind, RustAnalyzerClient};
@@ -0,0 +1,521 @@
+use anyhow::{Result, bail};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::analyzer::syntax::strip_visibility;
+
+/// On-disk convention used for modules that own submodules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleLayout {
+    /// `foo.rs` next to a `foo/` directory holding the submodules.
+    FileAndDir,
+    /// `foo/mod.rs` holding the module body.
+    ModRs,
+}
+
+impl fmt::Display for ModuleLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModuleLayout::FileAndDir => write!(f, "foo.rs + foo/"),
+            ModuleLayout::ModRs => write!(f, "foo/mod.rs"),
+        }
+    }
+}
+
+/// Where a new module goes and which file has to declare it.
+#[derive(Debug, Clone)]
+pub struct ModulePlan {
+    pub module_name: String,
+    pub module_file: PathBuf,
+    pub parent_file: PathBuf,
+    pub layout: ModuleLayout,
+}
+
+/// Resolve `module_path` into a [`ModulePlan`].
+///
+/// `module_path` may name the module file itself (`src/auth.rs`,
+/// `src/auth/mod.rs`), the parent module file (`src/lib.rs`, `src/server.rs`)
+/// or the directory the module should live in (`src/`).
+pub fn plan_module(module_name: &str, module_path: &Path) -> Result<ModulePlan> {
+    if !is_valid_module_name(module_name) {
+        bail!("`{module_name}` is not a valid module name");
+    }
+
+    let (dir, parent_file) = resolve_location(module_name, module_path)?;
+    let src_root = crate_src_root(&dir).unwrap_or_else(|| dir.clone());
+    let layout = detect_layout(&src_root);
+
+    let flat_file = dir.join(format!("{module_name}.rs"));
+    let mod_rs_file = dir.join(module_name).join("mod.rs");
+    for existing in [&flat_file, &mod_rs_file] {
+        if existing.exists() {
+            bail!(
+                "Module `{module_name}` already exists at {}",
+                existing.display()
+            );
+        }
+    }
+
+    let module_file = match layout {
+        ModuleLayout::FileAndDir => flat_file,
+        ModuleLayout::ModRs => mod_rs_file,
+    };
+
+    Ok(ModulePlan {
+        module_name: module_name.to_string(),
+        module_file,
+        parent_file,
+        layout,
+    })
+}
+
+fn resolve_location(module_name: &str, module_path: &Path) -> Result<(PathBuf, PathBuf)> {
+    let is_rust_file = module_path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("rs"));
+
+    if !is_rust_file {
+        let dir = module_path.to_path_buf();
+        let parent = find_parent_module_file(&dir).ok_or_else(|| {
+            anyhow::anyhow!(
+                "No parent module file found for directory {}",
+                dir.display()
+            )
+        })?;
+        return Ok((dir, parent));
+    }
+
+    let stem = module_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let file_dir = module_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let file_dir_name = file_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned());
+
+    let module_dir = if stem == module_name {
+        Some(file_dir.clone())
+    } else if stem == "mod" && file_dir_name.as_deref() == Some(module_name) {
+        Some(file_dir.parent().map(Path::to_path_buf).unwrap_or_default())
+    } else {
+        None
+    };
+
+    if let Some(dir) = module_dir {
+        let parent = find_parent_module_file(&dir).ok_or_else(|| {
+            anyhow::anyhow!("No parent module file found for {}", module_path.display())
+        })?;
+        return Ok((dir, parent));
+    }
+
+    if !module_path.exists() {
+        bail!(
+            "Parent module file {} does not exist",
+            module_path.display()
+        );
+    }
+
+    let dir = if matches!(stem.as_str(), "mod" | "lib" | "main") {
+        file_dir
+    } else {
+        module_path.with_extension("")
+    };
+    Ok((dir, module_path.to_path_buf()))
+}
+
+/// Plan a module for an explicit file path such as `src/auth.rs` or
+/// `src/auth/mod.rs`, keeping the layout the caller asked for.
+pub fn plan_module_at(module_file: &Path) -> Result<ModulePlan> {
+    let stem = module_file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let file_dir = module_file.parent().unwrap_or(Path::new(""));
+
+    let (module_name, dir, layout) = if stem == "mod" {
+        let name = file_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        (
+            name,
+            file_dir.parent().unwrap_or(Path::new("")),
+            ModuleLayout::ModRs,
+        )
+    } else {
+        (stem, file_dir, ModuleLayout::FileAndDir)
+    };
+
+    if !is_valid_module_name(&module_name) {
+        bail!(
+            "{} does not name a valid module file",
+            module_file.display()
+        );
+    }
+
+    let parent_file = find_parent_module_file(dir).ok_or_else(|| {
+        anyhow::anyhow!(
+            "No parent module found for {}; create the parent module first",
+            module_file.display()
+        )
+    })?;
+
+    Ok(ModulePlan {
+        module_name,
+        module_file: module_file.to_path_buf(),
+        parent_file,
+        layout,
+    })
+}
+
+/// Module path of `file` relative to its crate root, e.g. `["server", "handler"]`
+/// for `src/server/handler.rs`. The crate root file maps to an empty path.
+pub fn module_path_for_file(file: &Path) -> Option<Vec<String>> {
+    let src_root = crate_src_root(file)?;
+    let relative = file.strip_prefix(&src_root).ok()?;
+    let mut segments: Vec<String> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+
+    let last = segments.pop()?;
+    let stem = Path::new(&last).file_stem()?.to_string_lossy().into_owned();
+    let is_crate_root = segments.is_empty() && matches!(stem.as_str(), "lib" | "main");
+    if stem != "mod" && !is_crate_root {
+        segments.push(stem);
+    }
+    Some(segments)
+}
+
+/// Read the package name from the `Cargo.toml` next to `src_root`.
+pub fn crate_name(src_root: &Path) -> Option<String> {
+    let manifest = std::fs::read_to_string(src_root.parent()?.join("Cargo.toml")).ok()?;
+    let mut in_package = false;
+    for line in manifest.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_package = trimmed == "[package]";
+        } else if in_package
+            && let Some(value) = trimmed.strip_prefix("name")
+            && let Some(value) = value.trim_start().strip_prefix('=')
+        {
+            return Some(value.trim().trim_matches('"').replace('-', "_"));
+        }
+    }
+    None
+}
+
+/// Find the file that declares the modules living in `dir`.
+pub fn find_parent_module_file(dir: &Path) -> Option<PathBuf> {
+    for candidate in ["mod.rs", "lib.rs", "main.rs"] {
+        let path = dir.join(candidate);
+        if path.is_file() {
+            return Some(path);
+        }
+    }
+
+    let name = dir.file_name()?;
+    let sibling = dir.parent()?.join(format!("{}.rs", name.to_string_lossy()));
+    sibling.is_file().then_some(sibling)
+}
+
+/// Locate `src/` of the crate containing `path` by walking up to the nearest
+/// `Cargo.toml`.
+pub fn crate_src_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|ancestor| ancestor.join("Cargo.toml").is_file())
+        .map(|root| root.join("src"))
+        .filter(|src| src.is_dir())
+}
+
+/// Detect which convention the crate uses for modules with submodules.
+///
+/// Counts `foo/mod.rs` directories against `foo.rs` + `foo/` pairs and picks
+/// the majority, defaulting to the `foo.rs` + `foo/` layout when there is no
+/// evidence either way.
+pub fn detect_layout(src_root: &Path) -> ModuleLayout {
+    let mut mod_rs = 0usize;
+    let mut file_and_dir = 0usize;
+    let mut stack = vec![src_root.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+
+            if path.join("mod.rs").is_file() {
+                mod_rs += 1;
+            } else if path.with_extension("rs").is_file() {
+                file_and_dir += 1;
+            }
+            stack.push(path);
+        }
+    }
+
+    if mod_rs > file_and_dir {
+        ModuleLayout::ModRs
+    } else {
+        ModuleLayout::FileAndDir
+    }
+}
+
+pub fn is_valid_module_name(name: &str) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while",
+    ];
+
+    let mut chars = name.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+
+    (first == '_' || first.is_ascii_alphabetic())
+        && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+        && name != "_"
+        && !KEYWORDS.contains(&name)
+}
+
+/// Parse a top-level `mod foo;` / `pub(crate) mod foo;` line, returning the
+/// module name.
+pub fn parse_mod_declaration(line: &str) -> Option<&str> {
+    if line.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let rest = strip_visibility(line.trim_end());
+    let name = rest.strip_prefix("mod ")?.strip_suffix(';')?.trim();
+    is_valid_module_name(name).then_some(name)
+}
+
+fn is_top_level_pub_use(line: &str) -> bool {
+    line.starts_with("pub use ") && line.trim_end().ends_with(';')
+}
+
+/// Byte offset just past the leading `//!` docs and `#![...]` attributes.
+pub fn header_end_offset(source: &str) -> usize {
+    let lines: Vec<String> = source.lines().map(str::to_string).collect();
+    source
+        .split_inclusive('\n')
+        .take(header_end(&lines))
+        .map(str::len)
+        .sum()
+}
+
+/// Insert a `mod` declaration with the given visibility prefix (`""`,
+/// `"pub "` or `"pub(crate) "`) into `source`, keeping an existing block of
+/// declarations sorted.
+pub fn insert_mod_declaration(source: &str, module_name: &str, visibility: &str) -> Result<String> {
+    let mut lines: Vec<String> = source.lines().map(str::to_string).collect();
+    let declaration = format!("{visibility}mod {module_name};");
+
+    let declared: Vec<(usize, String)> = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            parse_mod_declaration(line).map(|name| (index, name.to_string()))
+        })
+        .collect();
+
+    if declared.iter().any(|(_, name)| name == module_name) {
+        bail!("Module `{module_name}` is already declared");
+    }
+
+    if let Some((index, _)) = declared
+        .iter()
+        .find(|(_, name)| name.as_str() > module_name)
+    {
+        let index = attribute_start(&lines, *index);
+        lines.insert(index, declaration);
+    } else if let Some((index, _)) = declared.last() {
+        lines.insert(index + 1, declaration);
+    } else {
+        let index = header_end(&lines);
+        let mut block = Vec::new();
+        if index > 0 && !lines[index - 1].trim().is_empty() {
+            block.push(String::new());
+        }
+        block.push(declaration);
+        if lines.get(index).is_some_and(|line| !line.trim().is_empty()) {
+            block.push(String::new());
+        }
+        lines.splice(index..index, block);
+    }
+
+    Ok(join_lines(lines, source))
+}
+
+/// Insert `pub use module::*;` into `source`, keeping existing top-level
+/// `pub use` lines sorted or placing it after the `mod` declarations.
+pub fn insert_reexport(source: &str, module_name: &str) -> String {
+    let mut lines: Vec<String> = source.lines().map(str::to_string).collect();
+    let reexport = format!("pub use {module_name}::*;");
+
+    if lines.iter().any(|line| line.trim() == reexport) {
+        return source.to_string();
+    }
+
+    let existing: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| is_top_level_pub_use(line))
+        .map(|(index, _)| index)
+        .collect();
+
+    if let Some(index) = existing
+        .iter()
+        .find(|index| lines[**index].as_str() > reexport.as_str())
+    {
+        lines.insert(*index, reexport);
+    } else if let Some(index) = existing.last() {
+        lines.insert(index + 1, reexport);
+    } else {
+        let index = lines
+            .iter()
+            .rposition(|line| parse_mod_declaration(line).is_some())
+            .map(|index| index + 1)
+            .unwrap_or_else(|| header_end(&lines));
+        let mut block = vec![String::new(), reexport];
+        if lines.get(index).is_some_and(|line| !line.trim().is_empty()) {
+            block.push(String::new());
+        }
+        lines.splice(index..index, block);
+    }
+
+    join_lines(lines, source)
+}
+
+fn attribute_start(lines: &[String], mut index: usize) -> usize {
+    while index > 0 {
+        let previous = lines[index - 1].trim_start();
+        if previous.starts_with("#[") || previous.starts_with("///") {
+            index -= 1;
+        } else {
+            break;
+        }
+    }
+    index
+}
+
+fn header_end(lines: &[String]) -> usize {
+    let mut end = 0;
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("//!") || trimmed.starts_with("#![") {
+            end = index + 1;
+        } else if !trimmed.is_empty() {
+            break;
+        }
+    }
+    end
+}
+
+fn join_lines(lines: Vec<String>, original: &str) -> String {
+    let mut joined = lines.join("\n");
+    if original.ends_with('\n') || original.is_empty() {
+        joined.push('\n');
+    }
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ModuleLayout, detect_layout, insert_mod_declaration, insert_reexport, is_valid_module_name,
+        parse_mod_declaration, plan_module,
+    };
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn scratch_crate() -> TempDir {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("src")).unwrap();
+        fs::write(
+            root.path().join("Cargo.toml"),
+            "[package]\nname = \"demo\"\n",
+        )
+        .unwrap();
+        fs::write(root.path().join("src/lib.rs"), "pub mod alpha;\n").unwrap();
+        root
+    }
+
+    #[test]
+    fn parses_mod_declarations() {
+        assert_eq!(parse_mod_declaration("pub mod server;"), Some("server"));
+        assert_eq!(parse_mod_declaration("pub(crate) mod util;"), Some("util"));
+        assert_eq!(parse_mod_declaration("mod tests {"), None);
+        assert_eq!(parse_mod_declaration("    mod nested;"), None);
+        assert!(!is_valid_module_name("fn"));
+        assert!(!is_valid_module_name("1abc"));
+    }
+
+    #[test]
+    fn inserts_declaration_in_sorted_position() {
+        let source = "pub mod analyzer;\npub mod server;\npub mod tools;\n";
+        let updated = insert_mod_declaration(source, "inspection", "pub ").unwrap();
+        assert_eq!(
+            updated,
+            "pub mod analyzer;\npub mod inspection;\npub mod server;\npub mod tools;\n"
+        );
+
+        let updated = insert_mod_declaration(source, "zeta", "").unwrap();
+        assert!(updated.ends_with("pub mod tools;\nmod zeta;\n"));
+    }
+
+    #[test]
+    fn inserts_declaration_after_header_when_none_exist() {
+        let source = "//! Crate docs\n\nuse std::fmt;\n";
+        let updated = insert_mod_declaration(source, "auth", "").unwrap();
+        assert_eq!(updated, "//! Crate docs\n\nmod auth;\n\nuse std::fmt;\n");
+    }
+
+    #[test]
+    fn refuses_duplicate_declaration() {
+        let err = insert_mod_declaration("mod auth;\n", "auth", "pub ").unwrap_err();
+        assert!(err.to_string().contains("already declared"));
+    }
+
+    #[test]
+    fn inserts_reexport_after_mod_block() {
+        let source = "pub mod analyzer;\npub mod types;\n\nfn main() {}\n";
+        let updated = insert_reexport(source, "types");
+        assert_eq!(
+            updated,
+            "pub mod analyzer;\npub mod types;\n\npub use types::*;\n\nfn main() {}\n"
+        );
+
+        let with_existing = "pub mod a;\npub mod c;\n\npub use a::*;\npub use c::*;\n";
+        let updated = insert_reexport(with_existing, "b");
+        assert!(updated.contains("pub use a::*;\npub use b::*;\npub use c::*;"));
+    }
+
+    #[test]
+    fn detects_layout_from_existing_modules() {
+        let root = scratch_crate();
+        let src = root.path().join("src");
+        assert_eq!(detect_layout(&src), ModuleLayout::FileAndDir);
+
+        fs::create_dir_all(src.join("alpha")).unwrap();
+        fs::write(src.join("alpha/mod.rs"), "").unwrap();
+        assert_eq!(detect_layout(&src), ModuleLayout::ModRs);
+
+        let plan = plan_module("beta", &src).unwrap();
+        assert_eq!(plan.module_file, src.join("beta/mod.rs"));
+        assert_eq!(plan.parent_file, src.join("lib.rs"));
+
+        let err = plan_module("alpha", &src.join("lib.rs")).unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+    }
+}
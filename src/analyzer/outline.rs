@@ -0,0 +1,329 @@
+use anyhow::bail;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::analyzer::edits::offset_at;
+use crate::analyzer::protocol::{DocumentSymbol, Range};
+use crate::analyzer::syntax::{impl_self_type, strip_visibility};
+
+/// Declared visibility of an outline entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    Public,
+    /// `pub(crate)`, `pub(super)` or `pub(in path)`.
+    Restricted,
+    Private,
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Visibility::Public => write!(f, "pub"),
+            Visibility::Restricted => write!(f, "restricted"),
+            Visibility::Private => write!(f, "private"),
+        }
+    }
+}
+
+/// Which visibilities an outline keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisibilityFilter {
+    /// Only `pub` items.
+    Public,
+    /// Items visible outside their module: `pub` and restricted `pub(...)`.
+    Crate,
+    /// Only private items.
+    Private,
+}
+
+impl VisibilityFilter {
+    fn accepts(self, visibility: Visibility) -> bool {
+        match self {
+            VisibilityFilter::Public => visibility == Visibility::Public,
+            VisibilityFilter::Crate => visibility != Visibility::Private,
+            VisibilityFilter::Private => visibility == Visibility::Private,
+        }
+    }
+}
+
+impl FromStr for VisibilityFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "public" | "pub" => Ok(VisibilityFilter::Public),
+            "crate" | "pub(crate)" => Ok(VisibilityFilter::Crate),
+            "private" => Ok(VisibilityFilter::Private),
+            other => bail!("Unknown visibility '{other}'; expected public, crate or private"),
+        }
+    }
+}
+
+pub const OUTLINE_KINDS: &[&str] = &[
+    "module", "struct", "enum", "union", "trait", "impl", "fn", "method", "const", "static",
+    "type", "macro", "field", "variant",
+];
+
+/// One item in a file's outline.
+#[derive(Debug, Clone)]
+pub struct OutlineEntry {
+    pub name: String,
+    pub kind: &'static str,
+    pub visibility: Visibility,
+    pub detail: Option<String>,
+    pub range: Range,
+    pub children: Vec<OutlineEntry>,
+}
+
+/// Build the outline of `source` from its document symbols, classifying kinds
+/// and visibilities from the declarations themselves.
+pub fn build_outline(source: &str, symbols: &[DocumentSymbol]) -> Vec<OutlineEntry> {
+    build_entries(source, symbols, None)
+}
+
+fn build_entries(
+    source: &str,
+    symbols: &[DocumentSymbol],
+    parent: Option<(&'static str, Visibility, bool)>,
+) -> Vec<OutlineEntry> {
+    symbols
+        .iter()
+        .map(|symbol| {
+            let declaration = declaration_text(source, symbol);
+            let kind = outline_kind(symbol, &declaration);
+            let visibility = match parent {
+                // Trait items and enum variants share their parent's visibility,
+                // trait impl items are as visible as the trait.
+                Some(("trait" | "enum", parent_visibility, _)) => parent_visibility,
+                Some(("impl", _, true)) => Visibility::Public,
+                _ => declared_visibility(&declaration),
+            };
+            let is_trait_impl = kind == "impl"
+                && impl_self_type(&symbol.name).is_some_and(|(_, is_trait)| is_trait);
+
+            OutlineEntry {
+                name: symbol.name.clone(),
+                kind,
+                visibility,
+                detail: symbol.detail.clone().filter(|detail| !detail.is_empty()),
+                range: symbol.range.clone(),
+                children: symbol
+                    .children
+                    .as_deref()
+                    .map(|children| {
+                        build_entries(source, children, Some((kind, visibility, is_trait_impl)))
+                    })
+                    .unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
+/// Text from the start of the symbol's range up to its name, without
+/// attributes and comments.
+fn declaration_text(source: &str, symbol: &DocumentSymbol) -> String {
+    let start = offset_at(source, &symbol.range.start);
+    let name = offset_at(source, &symbol.selection_range.start).max(start);
+    source[start..name]
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with("#[") && !line.starts_with("//"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn declared_visibility(declaration: &str) -> Visibility {
+    // The declaration stops at the item's name, so `pub x` arrives as `pub`.
+    let declaration = format!("{} ", declaration.trim());
+    if strip_visibility(&declaration).len() == declaration.len() {
+        Visibility::Private
+    } else if declaration.starts_with("pub(") || declaration.starts_with("pub (") {
+        Visibility::Restricted
+    } else {
+        Visibility::Public
+    }
+}
+
+fn outline_kind(symbol: &DocumentSymbol, declaration: &str) -> &'static str {
+    let has = |keyword: &str| {
+        declaration
+            .split(|ch: char| !(ch == '_' || ch == '!' || ch.is_alphanumeric()))
+            .any(|token| token == keyword)
+    };
+
+    if has("macro_rules!") || has("macro") {
+        return "macro";
+    }
+    if impl_self_type(&symbol.name).is_some() {
+        return "impl";
+    }
+    match symbol.kind {
+        2 => "module",
+        5 | 23 => "struct",
+        6 => "method",
+        7 | 8 => "field",
+        10 => "enum",
+        11 => "trait",
+        12 if has("fn") => "fn",
+        12 => "macro",
+        13 if has("static") => "static",
+        14 => "const",
+        19 => "impl",
+        22 => "variant",
+        26 if has("type") => "type",
+        _ if has("union") => "union",
+        _ if has("static") => "static",
+        _ if has("const") => "const",
+        _ => "item",
+    }
+}
+
+/// Keep entries matching both filters, plus the ancestors of any match so the
+/// tree keeps its shape.
+pub fn filter_outline(
+    entries: Vec<OutlineEntry>,
+    kinds: &[String],
+    visibility: Option<VisibilityFilter>,
+) -> Vec<OutlineEntry> {
+    entries
+        .into_iter()
+        .filter_map(|mut entry| {
+            entry.children = filter_outline(entry.children, kinds, visibility);
+            let kind_matches = kinds.is_empty() || kinds.iter().any(|kind| kind == entry.kind);
+            // Impl blocks have no visibility of their own; they only appear as
+            // containers of matching items.
+            let visibility_matches = visibility
+                .is_none_or(|filter| entry.kind != "impl" && filter.accepts(entry.visibility));
+            (kind_matches && visibility_matches || !entry.children.is_empty()).then_some(entry)
+        })
+        .collect()
+}
+
+/// Indented plain-text outline with `line:column` ranges (1-based).
+pub fn render_outline(entries: &[OutlineEntry]) -> String {
+    let mut lines = Vec::new();
+    render_entries(entries, 0, &mut lines);
+    if lines.is_empty() {
+        "No matching items".to_string()
+    } else {
+        lines.join("\n")
+    }
+}
+
+fn render_entries(entries: &[OutlineEntry], depth: usize, lines: &mut Vec<String>) {
+    for entry in entries {
+        let mut line = "  ".repeat(depth);
+        if entry.kind == "impl" {
+            line.push_str(&entry.name);
+        } else {
+            line.push_str(&format!("{} {}", entry.kind, entry.name));
+        }
+        if let Some(detail) = &entry.detail {
+            line.push_str(&format!(": {detail}"));
+        }
+        if entry.kind != "impl" {
+            line.push_str(&format!(" [{}]", entry.visibility));
+        }
+        line.push_str(&format!(
+            " {}:{}-{}:{}",
+            entry.range.start.line + 1,
+            entry.range.start.character + 1,
+            entry.range.end.line + 1,
+            entry.range.end.character + 1
+        ));
+        lines.push(line);
+        render_entries(&entry.children, depth + 1, lines);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VisibilityFilter, build_outline, filter_outline, render_outline};
+    use crate::analyzer::edits::position_at;
+    use crate::analyzer::protocol::{DocumentSymbol, Range};
+
+    fn symbol(
+        source: &str,
+        name: &str,
+        kind: u32,
+        text: &str,
+        ident: &str,
+        children: Vec<DocumentSymbol>,
+    ) -> DocumentSymbol {
+        let start = source.find(text).expect("item text present");
+        let name_start = start + text.find(ident).expect("ident present");
+        DocumentSymbol {
+            name: name.to_string(),
+            detail: None,
+            kind,
+            range: Range {
+                start: position_at(source, start),
+                end: position_at(source, start + text.len()),
+            },
+            selection_range: Range {
+                start: position_at(source, name_start),
+                end: position_at(source, name_start + ident.len()),
+            },
+            children: (!children.is_empty()).then_some(children),
+        }
+    }
+
+    #[test]
+    fn classifies_and_filters_items() {
+        let source = "pub struct Point {\n    pub x: i32,\n    y: i32,\n}\n\nimpl Point {\n    pub(crate) fn new() -> Self { todo!() }\n    fn helper() {}\n}\n\nmacro_rules! square { ($x:expr) => { $x * $x }; }\n";
+        let symbols = vec![
+            symbol(
+                source,
+                "Point",
+                23,
+                "pub struct Point {\n    pub x: i32,\n    y: i32,\n}",
+                "Point",
+                vec![
+                    symbol(source, "x", 8, "pub x: i32", "x", vec![]),
+                    symbol(source, "y", 8, "y: i32", "y", vec![]),
+                ],
+            ),
+            symbol(
+                source,
+                "impl Point",
+                19,
+                "impl Point {\n    pub(crate) fn new() -> Self { todo!() }\n    fn helper() {}\n}",
+                "Point",
+                vec![
+                    symbol(
+                        source,
+                        "new",
+                        12,
+                        "pub(crate) fn new() -> Self { todo!() }",
+                        "new",
+                        vec![],
+                    ),
+                    symbol(source, "helper", 12, "fn helper() {}", "helper", vec![]),
+                ],
+            ),
+            symbol(
+                source,
+                "square",
+                12,
+                "macro_rules! square { ($x:expr) => { $x * $x }; }",
+                "square",
+                vec![],
+            ),
+        ];
+
+        let outline = build_outline(source, &symbols);
+        assert_eq!(
+            render_outline(&outline),
+            "struct Point [pub] 1:1-4:2\n  field x [pub] 2:5-2:15\n  field y [private] 3:5-3:11\nimpl Point 6:1-9:2\n  fn new [restricted] 7:5-7:44\n  fn helper [private] 8:5-8:19\nmacro square [private] 11:1-11:50"
+        );
+
+        let crate_visible = filter_outline(outline.clone(), &[], Some(VisibilityFilter::Crate));
+        assert_eq!(
+            render_outline(&crate_visible),
+            "struct Point [pub] 1:1-4:2\n  field x [pub] 2:5-2:15\nimpl Point 6:1-9:2\n  fn new [restricted] 7:5-7:44"
+        );
+
+        let macros = filter_outline(outline, &["macro".to_string()], None);
+        assert_eq!(render_outline(&macros), "macro square [private] 11:1-11:50");
+    }
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Position {
@@ -88,6 +90,183 @@ pub enum DocumentSymbolResponse {
     SymbolInformation(Vec<SymbolInformation>),
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextEdit {
+    pub range: Range,
+    #[serde(rename = "newText")]
+    pub new_text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionedTextDocumentIdentifier {
+    pub uri: String,
+    #[serde(default)]
+    pub version: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextDocumentEdit {
+    #[serde(rename = "textDocument")]
+    pub text_document: VersionedTextDocumentIdentifier,
+    pub edits: Vec<TextEdit>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkspaceEdit {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changes: Option<BTreeMap<String, Vec<TextEdit>>>,
+    #[serde(
+        rename = "documentChanges",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub document_changes: Option<Vec<TextDocumentEdit>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypeHierarchyItem {
+    pub name: String,
+    pub kind: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub uri: String,
+    pub range: Range,
+    #[serde(rename = "selectionRange")]
+    pub selection_range: Range,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallHierarchyItem {
+    pub name: String,
+    pub kind: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub uri: String,
+    pub range: Range,
+    #[serde(rename = "selectionRange")]
+    pub selection_range: Range,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallHierarchyIncomingCall {
+    pub from: CallHierarchyItem,
+    #[serde(rename = "fromRanges")]
+    pub from_ranges: Vec<Range>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallHierarchyOutgoingCall {
+    pub to: CallHierarchyItem,
+    #[serde(rename = "fromRanges")]
+    pub from_ranges: Vec<Range>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InlayHintLabelPart {
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum InlayHintLabel {
+    String(String),
+    Parts(Vec<InlayHintLabelPart>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InlayHint {
+    pub position: Position,
+    pub label: InlayHintLabel,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<u32>,
+    #[serde(rename = "paddingLeft", default)]
+    pub padding_left: bool,
+    #[serde(rename = "paddingRight", default)]
+    pub padding_right: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterInformation {
+    /// Either the parameter text or `[start, end]` UTF-16 offsets into the
+    /// signature label.
+    pub label: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureInformation {
+    pub label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<Vec<ParameterInformation>>,
+    #[serde(
+        rename = "activeParameter",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub active_parameter: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureHelp {
+    pub signatures: Vec<SignatureInformation>,
+    #[serde(
+        rename = "activeSignature",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub active_signature: Option<u32>,
+    #[serde(
+        rename = "activeParameter",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub active_parameter: Option<u32>,
+}
+
+/// Result of rust-analyzer's `rust-analyzer/expandMacro` extension.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpandedMacro {
+    pub name: String,
+    pub expansion: String,
+}
+
+/// A cargo or shell invocation from rust-analyzer's `experimental/runnables`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Runnable {
+    pub label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<LocationLink>,
+    pub kind: String,
+    pub args: RunnableArgs,
+}
+
+/// Arguments of a runnable; cargo runnables use the `cargo*` fields, shell
+/// runnables `program` and `args`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RunnableArgs {
+    pub workspace_root: Option<String>,
+    pub cwd: Option<String>,
+    pub override_cargo: Option<String>,
+    pub cargo_args: Vec<String>,
+    pub cargo_extra_args: Vec<String>,
+    pub executable_args: Vec<String>,
+    pub program: Option<String>,
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestInfo {
+    pub runnable: Runnable,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SymbolPathSegment {
     pub name: String,
@@ -96,10 +275,48 @@ pub struct SymbolPathSegment {
 
 pub type SymbolPath = Vec<SymbolPathSegment>;
 
+/// `file://` URI for `path`, percent-encoding every byte outside the
+/// characters a URI path may contain.
+pub fn file_uri(path: impl AsRef<Path>) -> String {
+    let mut uri = String::from("file://");
+    for &byte in path.as_ref().as_os_str().as_encoded_bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/!$&'()*+,;=:@".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    uri
+}
+
+/// Path named by a `file://` URI, with percent-encoded bytes decoded.
+pub fn path_from_uri(uri: &str) -> PathBuf {
+    let encoded = uri.strip_prefix("file://").unwrap_or(uri).as_bytes();
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut index = 0;
+    while index < encoded.len() {
+        let escaped = encoded
+            .get(index + 1..index + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) if encoded[index] == b'%' => {
+                bytes.push(byte);
+                index += 3;
+            }
+            _ => {
+                bytes.push(encoded[index]);
+                index += 1;
+            }
+        }
+    }
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
 pub fn create_text_document_position_params(file_path: &str, line: u32, character: u32) -> Value {
     json!({
         "textDocument": {
-            "uri": format!("file://{}", file_path)
+            "uri": file_uri(file_path)
         },
         "position": {
             "line": line,
@@ -111,7 +328,7 @@ pub fn create_text_document_position_params(file_path: &str, line: u32, characte
 pub fn create_references_params(file_path: &str, line: u32, character: u32) -> Value {
     json!({
         "textDocument": {
-            "uri": format!("file://{}", file_path)
+            "uri": file_uri(file_path)
         },
         "position": {
             "line": line,
@@ -123,6 +340,30 @@ pub fn create_references_params(file_path: &str, line: u32, character: u32) -> V
     })
 }
 
+/// Params for one of rust-analyzer's `rust-analyzer/view*` and
+/// `rust-analyzer/syntaxTree` requests; the function-level views need a
+/// position.
+pub fn create_analyzer_view_params(
+    method: &str,
+    file_path: &str,
+    position: Option<&Position>,
+) -> anyhow::Result<Value> {
+    let text_document = json!({ "uri": file_uri(file_path) });
+    Ok(match method {
+        "rust-analyzer/syntaxTree" => json!({ "textDocument": text_document, "range": null }),
+        "rust-analyzer/viewItemTree" => json!({ "textDocument": text_document }),
+        "rust-analyzer/viewCrateGraph" => json!({ "full": false }),
+        "rust-analyzer/viewHir"
+        | "rust-analyzer/viewMir"
+        | "rust-analyzer/viewRecursiveMemoryLayout" => {
+            let position = position
+                .ok_or_else(|| anyhow::anyhow!("{method} requires a line and character"))?;
+            create_text_document_position_params(file_path, position.line, position.character)
+        }
+        _ => anyhow::bail!("Unsupported rust-analyzer view {method}"),
+    })
+}
+
 pub fn create_workspace_symbol_params(query: &str) -> Value {
     json!({
         "query": query
@@ -132,7 +373,7 @@ pub fn create_workspace_symbol_params(query: &str) -> Value {
 pub fn create_rename_params(file_path: &str, line: u32, character: u32, new_name: &str) -> Value {
     json!({
         "textDocument": {
-            "uri": format!("file://{}", file_path)
+            "uri": file_uri(file_path)
         },
         "position": {
             "line": line,
@@ -145,7 +386,7 @@ pub fn create_rename_params(file_path: &str, line: u32, character: u32, new_name
 pub fn create_formatting_params(file_path: &str) -> Value {
     json!({
         "textDocument": {
-            "uri": format!("file://{}", file_path)
+            "uri": file_uri(file_path)
         },
         "options": {
             "tabSize": 4,
@@ -153,3 +394,89 @@ pub fn create_formatting_params(file_path: &str) -> Value {
         }
     })
 }
+
+pub fn create_did_change_watched_files_params(created: &[String], changed: &[String]) -> Value {
+    // FileChangeType: 1 = Created, 2 = Changed
+    let changes: Vec<Value> = created
+        .iter()
+        .map(|uri| json!({ "uri": uri, "type": 1 }))
+        .chain(changed.iter().map(|uri| json!({ "uri": uri, "type": 2 })))
+        .collect();
+
+    json!({
+        "changes": changes
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Position, create_analyzer_view_params, file_uri, path_from_uri};
+    use crate::inspection::InspectionView;
+    use serde_json::{Value, json};
+    use std::path::Path;
+
+    #[test]
+    fn percent_encodes_file_uris() {
+        let path = Path::new("/work/my crate/src/#100%.rs");
+        let uri = file_uri(path);
+        assert_eq!(uri, "file:///work/my%20crate/src/%23100%25.rs");
+        assert_eq!(path_from_uri(&uri), path);
+        assert_eq!(
+            path_from_uri("file:///w/caf%C3%A9/src/lib.rs"),
+            Path::new("/w/café/src/lib.rs")
+        );
+    }
+
+    #[test]
+    fn maps_analyzer_views_to_requests() {
+        let request = |view: &str, position: Option<&Position>| {
+            let method = InspectionView::find(view)
+                .and_then(|view| view.analyzer_request)
+                .expect("view is served by rust-analyzer");
+            create_analyzer_view_params(method, "/w/src/lib.rs", position)
+                .map(|params| (method, params))
+        };
+        let document = json!({ "uri": "file:///w/src/lib.rs" });
+        let position = Position {
+            line: 4,
+            character: 7,
+        };
+        let at_position = json!({
+            "textDocument": document,
+            "position": { "line": 4, "character": 7 }
+        });
+
+        let cases: [(&str, &str, Value); 5] = [
+            (
+                "ra-syntax",
+                "rust-analyzer/syntaxTree",
+                json!({ "textDocument": document, "range": null }),
+            ),
+            ("ra-hir", "rust-analyzer/viewHir", at_position.clone()),
+            ("ra-mir", "rust-analyzer/viewMir", at_position),
+            (
+                "ra-item-tree",
+                "rust-analyzer/viewItemTree",
+                json!({ "textDocument": document }),
+            ),
+            (
+                "crate-graph",
+                "rust-analyzer/viewCrateGraph",
+                json!({ "full": false }),
+            ),
+        ];
+        for (view, method, params) in cases {
+            assert_eq!(
+                request(view, Some(&position)).unwrap(),
+                (method, params),
+                "{view}"
+            );
+        }
+
+        for view in ["ra-hir", "ra-mir"] {
+            let error = request(view, None).unwrap_err();
+            assert!(error.to_string().contains("requires a line and character"));
+        }
+        assert!(request("ra-syntax", None).is_ok());
+    }
+}
@@ -0,0 +1,1011 @@
+use anyhow::{Result, bail};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::PathBuf;
+
+use crate::analyzer::edits::{
+    ByteEdit, apply_byte_edits, identifiers, line_end_inclusive, line_start, offset_at,
+};
+use crate::analyzer::imports::{
+    UseStatement, absolutize, crate_path, find_use_statements, insert_use_lines,
+};
+use crate::analyzer::modules::{
+    ModulePlan, crate_src_root, insert_mod_declaration, module_path_for_file, plan_module_at,
+};
+use crate::analyzer::protocol::{DocumentSymbol, Location, Position, path_from_uri};
+use crate::analyzer::syntax::{impl_self_type, strip_visibility};
+
+const KIND_FIELD: u32 = 8;
+const KIND_STRUCT: u32 = 23;
+
+/// A top-level item (or impl block) selected for a move.
+#[derive(Debug, Clone)]
+pub struct SelectedItem {
+    pub name: String,
+    pub symbol: DocumentSymbol,
+    /// Byte range in the source file, including attributes and doc comments.
+    pub start: usize,
+    pub end: usize,
+    pub is_impl: bool,
+    pub is_trait_impl: bool,
+}
+
+/// A declaration whose references decide whether a move breaks privacy.
+#[derive(Debug, Clone)]
+pub struct ReferenceTarget {
+    pub label: String,
+    pub position: Position,
+    /// Byte offset where a widened visibility would be inserted.
+    pub declaration: usize,
+    /// True for moved items themselves, false for fields and associated items.
+    pub is_item: bool,
+}
+
+/// Paths involved in a move. All paths are absolute.
+#[derive(Debug, Clone)]
+pub struct MoveContext {
+    pub source_path: PathBuf,
+    pub target_path: PathBuf,
+    pub crate_name: String,
+}
+
+#[derive(Debug, Default)]
+pub struct MovePlan {
+    /// New contents for every file that changes.
+    pub files: BTreeMap<PathBuf, String>,
+    pub created_module: Option<ModulePlan>,
+    pub moved: Vec<String>,
+    /// Declarations made visible, with the visibility they were given.
+    pub widened: Vec<String>,
+    pub notes: Vec<String>,
+}
+
+/// Select the named top-level items from `symbols`, together with the impl
+/// blocks whose self type is one of them.
+pub fn select_items(
+    source: &str,
+    symbols: &[DocumentSymbol],
+    item_names: &[String],
+) -> Result<Vec<SelectedItem>> {
+    let wanted: BTreeSet<&str> = item_names.iter().map(String::as_str).collect();
+    let mut found = BTreeSet::new();
+    let mut items = Vec::new();
+
+    for symbol in symbols {
+        let impl_info = impl_self_type(&symbol.name);
+        let (is_selected, is_impl, is_trait_impl) = match &impl_info {
+            Some((self_type, is_trait_impl)) => {
+                (wanted.contains(self_type.as_str()), true, *is_trait_impl)
+            }
+            None => (wanted.contains(symbol.name.as_str()), false, false),
+        };
+
+        if !is_selected {
+            continue;
+        }
+
+        if !is_impl {
+            found.insert(symbol.name.as_str());
+        }
+
+        let (start, end) = item_byte_range(source, symbol);
+        items.push(SelectedItem {
+            name: symbol.name.clone(),
+            symbol: symbol.clone(),
+            start,
+            end,
+            is_impl,
+            is_trait_impl,
+        });
+    }
+
+    let missing: Vec<&str> = wanted.difference(&found).copied().collect();
+    if !missing.is_empty() {
+        bail!("Items not found at the top level: {}", missing.join(", "));
+    }
+
+    items.sort_by_key(|item| item.start);
+    Ok(items)
+}
+
+/// Declarations whose references must be checked: the moved items, the fields
+/// of moved structs and the associated items of moved inherent impls.
+pub fn reference_targets(source: &str, items: &[SelectedItem]) -> Vec<ReferenceTarget> {
+    let mut targets = Vec::new();
+    let owner_name = |item: &SelectedItem| {
+        impl_self_type(&item.name)
+            .map(|(name, _)| name)
+            .unwrap_or_else(|| item.name.clone())
+    };
+
+    for item in items {
+        if !item.is_impl {
+            targets.push(ReferenceTarget {
+                label: item.name.clone(),
+                position: item.symbol.selection_range.start.clone(),
+                declaration: declaration_offset(source, &item.symbol.selection_range.start),
+                is_item: true,
+            });
+        }
+
+        let children_visible = if item.is_impl {
+            !item.is_trait_impl
+        } else {
+            item.symbol.kind == KIND_STRUCT
+        };
+        if !children_visible {
+            continue;
+        }
+
+        for child in item.symbol.children.iter().flatten() {
+            let is_named = child
+                .name
+                .chars()
+                .next()
+                .is_some_and(|first| first == '_' || first.is_alphabetic());
+            if !is_named || (!item.is_impl && child.kind != KIND_FIELD) {
+                continue;
+            }
+
+            targets.push(ReferenceTarget {
+                label: format!("{}::{}", owner_name(item), child.name),
+                position: child.selection_range.start.clone(),
+                declaration: declaration_offset(source, &child.selection_range.start),
+                is_item: false,
+            });
+        }
+    }
+
+    targets
+}
+
+/// Compute every file edit needed to move `items` from the source file into
+/// the target file.
+///
+/// `files` must hold the current contents of the source file, the target file
+/// (when it exists), the target's parent module (when it does not) and every
+/// file mentioned in `references`.
+pub fn plan_move(
+    context: &MoveContext,
+    symbols: &[DocumentSymbol],
+    items: &[SelectedItem],
+    references: &[(ReferenceTarget, Vec<Location>)],
+    files: &BTreeMap<PathBuf, String>,
+) -> Result<MovePlan> {
+    let source_path = &context.source_path;
+    let target_path = &context.target_path;
+    let crate_name = context.crate_name.as_str();
+    let Some(source) = files.get(source_path) else {
+        bail!("Source file {} was not loaded", source_path.display());
+    };
+
+    let source_module = module_path_for_file(source_path)
+        .ok_or_else(|| anyhow::anyhow!("{} is not inside a crate's src/", source_path.display()))?;
+    let target_module = module_path_for_file(target_path)
+        .ok_or_else(|| anyhow::anyhow!("{} is not inside a crate's src/", target_path.display()))?;
+    if source_module == target_module {
+        bail!("Source and target are the same module");
+    }
+
+    let mut plan = MovePlan::default();
+    let cuts: Vec<(usize, usize)> = items.iter().map(|item| (item.start, item.end)).collect();
+    let in_cut = |offset: usize| {
+        cuts.iter()
+            .any(|(start, end)| offset >= *start && offset < *end)
+    };
+    let moved_names: BTreeSet<String> = items
+        .iter()
+        .filter(|item| !item.is_impl)
+        .map(|item| item.name.clone())
+        .collect();
+    plan.moved = moved_names.iter().cloned().collect();
+
+    let src_root = crate_src_root(source_path);
+    let root_of = |path: &PathBuf| match module_path_for_file(path) {
+        Some(module) if crate_src_root(path) == src_root => (module, CrateRoot::Local),
+        _ => (Vec::new(), CrateRoot::External),
+    };
+
+    // Widen moved declarations that are still referenced from outside the
+    // move, to `pub` when another crate root refers to them. A created target
+    // module gets the widest visibility any of those references needs.
+    let mut moved_edits = Vec::new();
+    let mut module_root = CrateRoot::Local;
+    for (target, locations) in references {
+        let outside_roots: Vec<CrateRoot> = locations
+            .iter()
+            .filter_map(|location| {
+                let path = path_from_uri(&location.uri);
+                if &path == source_path && in_cut(offset_at(source, &location.range.start)) {
+                    return None;
+                }
+                Some(root_of(&path).1)
+            })
+            .collect();
+
+        let Some(root) = outside_roots.into_iter().max() else {
+            continue;
+        };
+        module_root = module_root.max(root);
+        if !is_public_at(source, target.declaration) {
+            moved_edits.push(ByteEdit::insert(target.declaration, root.visibility()));
+            plan.widened.push(format!(
+                "{} ({})",
+                target.label,
+                root.visibility().trim_end()
+            ));
+        }
+    }
+
+    let moved_text = items
+        .iter()
+        .map(|item| {
+            let local: Vec<ByteEdit> = moved_edits
+                .iter()
+                .filter(|edit| edit.start >= item.start && edit.start < item.end)
+                .map(|edit| ByteEdit::insert(edit.start - item.start, edit.text.clone()))
+                .collect();
+            apply_byte_edits(&source[item.start..item.end], &local)
+        })
+        .collect::<Result<Vec<_>>>()?
+        .iter()
+        .map(|text| text.trim_end().to_string())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let moved_idents: HashSet<&str> = items
+        .iter()
+        .flat_map(|item| identifiers(&source[item.start..item.end]))
+        .collect();
+
+    let source_uses: Vec<UseStatement> = find_use_statements(source)
+        .into_iter()
+        .filter(|statement| !in_cut(statement.start))
+        .collect();
+    let remaining_idents = remaining_identifiers(source, &cuts, &source_uses);
+
+    // Carry imports the moved items depend on and prune the ones the source
+    // file no longer needs.
+    let mut source_edits: Vec<ByteEdit> = Vec::new();
+    let mut target_imports: Vec<String> = Vec::new();
+    let mut carried_names: HashSet<String> = HashSet::new();
+    for statement in source_uses
+        .iter()
+        .filter(|statement| statement.is_top_level())
+    {
+        let mut tree = statement.tree.clone();
+        let mut changed = false;
+        let remaining = tree.retain(&mut |entry| {
+            let bound = entry.bound_name();
+            let needed = entry.glob || bound.is_some_and(|name| moved_idents.contains(name));
+            if !needed {
+                return true;
+            }
+
+            let resolved = absolutize(&entry.path, &source_module, crate_name);
+            let imports_moved_item = resolved.as_ref().is_some_and(|path| {
+                path.len() == source_module.len() + 1
+                    && path.starts_with(&source_module)
+                    && moved_names.contains(&path[source_module.len()])
+            });
+            let imports_from_target = resolved.as_ref().is_some_and(|path| {
+                path.len() == target_module.len() + 1 && path.starts_with(&target_module)
+            });
+            if !imports_moved_item && !imports_from_target {
+                let path = match &resolved {
+                    Some(path) => crate_path(path),
+                    None => entry.path.join("::"),
+                };
+                let rendered = match (&entry.alias, entry.glob) {
+                    (_, true) => format!("use {path}::*;"),
+                    (Some(alias), false) => format!("use {path} as {alias};"),
+                    (None, false) => format!("use {path};"),
+                };
+                target_imports.push(rendered);
+                if let Some(name) = bound {
+                    carried_names.insert(name.to_string());
+                }
+            }
+
+            let keep = entry.glob || bound.is_some_and(|name| remaining_idents.contains(name));
+            changed |= !keep;
+            keep
+        });
+
+        if changed {
+            source_edits.push(replace_statement(
+                source,
+                statement,
+                remaining.then_some(&tree),
+                &[],
+            ));
+        }
+    }
+
+    // Items left behind that the moved code still uses.
+    for symbol in symbols {
+        if moved_names.contains(&symbol.name)
+            || impl_self_type(&symbol.name).is_some()
+            || !moved_idents.contains(symbol.name.as_str())
+            || carried_names.contains(&symbol.name)
+        {
+            continue;
+        }
+
+        target_imports.push(format!(
+            "use {}::{};",
+            crate_path(&source_module),
+            symbol.name
+        ));
+        let declaration = declaration_offset(source, &symbol.selection_range.start);
+        let declaration_line = &source[declaration..line_end_inclusive(source, declaration)];
+        if !is_public_at(source, declaration) && !declaration_line.contains("macro_rules!") {
+            source_edits.push(ByteEdit::insert(declaration, CrateRoot::Local.visibility()));
+            plan.widened.push(format!(
+                "{} ({})",
+                symbol.name,
+                CrateRoot::Local.visibility().trim_end()
+            ));
+        }
+    }
+
+    // Rewrite imports and paths that point at the old location.
+    let mut references_by_file: BTreeMap<PathBuf, Vec<(usize, String)>> = BTreeMap::new();
+    for (target, locations) in references.iter().filter(|(target, _)| target.is_item) {
+        for location in locations {
+            let path = path_from_uri(&location.uri);
+            let Some(text) = files.get(&path) else {
+                plan.notes.push(format!(
+                    "Skipped reference in unreadable file {}",
+                    path.display()
+                ));
+                continue;
+            };
+            let offset = offset_at(text, &location.range.start);
+            if &path == source_path && in_cut(offset) {
+                continue;
+            }
+            references_by_file
+                .entry(path)
+                .or_default()
+                .push((offset, target.label.clone()));
+        }
+    }
+    references_by_file.entry(source_path.clone()).or_default();
+
+    let relocation = Relocation {
+        crate_name,
+        source_module: &source_module,
+        target_module: &target_module,
+        moved_names: &moved_names,
+    };
+
+    for (path, file_references) in &references_by_file {
+        let text = &files[path];
+        let is_source = path == source_path;
+        let (file_module, root) = root_of(path);
+
+        let mut edits = if is_source {
+            std::mem::take(&mut source_edits)
+        } else {
+            Vec::new()
+        };
+        let statements: Vec<UseStatement> = find_use_statements(text)
+            .into_iter()
+            .filter(|statement| {
+                !(is_source && (in_cut(statement.start) || statement.is_top_level()))
+            })
+            .collect();
+
+        let mut bound_names = HashSet::new();
+        for statement in &statements {
+            let module = module_at(text, statement.start, &file_module);
+            if let Some(edit) =
+                relocation.rewrite_statement(text, statement, &module, root, &mut bound_names)
+            {
+                edits.push(edit);
+            }
+        }
+
+        let all_statements = find_use_statements(text);
+        let mut needs_import = BTreeSet::new();
+        for (offset, name) in file_references {
+            if all_statements
+                .iter()
+                .any(|statement| *offset >= statement.start && *offset < statement.end)
+            {
+                continue;
+            }
+
+            let module = module_at(text, *offset, &file_module);
+            let (prefix_start, prefix) = path_prefix(text, *offset);
+            let in_target = relocation.in_target(&module, root);
+            if prefix.is_empty() {
+                if !in_target && !bound_names.contains(name) {
+                    needs_import.insert(name.clone());
+                }
+                continue;
+            }
+
+            let segments: Vec<String> = prefix
+                .trim_end_matches("::")
+                .split("::")
+                .map(str::to_string)
+                .collect();
+            if relocation.resolve(&segments, &module, root).as_ref() == Some(&source_module) {
+                let replacement = if in_target {
+                    String::new()
+                } else {
+                    format!("{}::", relocation.render(&target_module, root))
+                };
+                edits.push(ByteEdit::replace(prefix_start, *offset, replacement));
+            }
+        }
+
+        if is_source {
+            edits.extend(
+                cuts.iter()
+                    .map(|(start, end)| ByteEdit::delete(*start, *end)),
+            );
+        }
+
+        if edits.is_empty() && needs_import.is_empty() {
+            continue;
+        }
+
+        let mut updated = apply_byte_edits(text, &edits)?;
+        if !needs_import.is_empty() {
+            let lines: Vec<String> = needs_import
+                .iter()
+                .map(|name| format!("use {}::{name};", relocation.render(&target_module, root)))
+                .collect();
+            updated = insert_use_lines(&updated, &lines);
+        }
+        if is_source {
+            updated = collapse_blank_lines(&updated);
+        }
+        plan.files.insert(path.clone(), updated);
+    }
+
+    // Write the moved items into the target, creating the module if needed.
+    let mut target_imports: Vec<String> = target_imports
+        .into_iter()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    target_imports.retain(|line| !line.is_empty());
+
+    let existing_target = plan
+        .files
+        .get(target_path)
+        .cloned()
+        .or_else(|| files.get(target_path).cloned());
+    let target_text = match existing_target {
+        Some(existing) => {
+            let mut updated = insert_use_lines(&existing, &target_imports);
+            let trimmed_len = updated.trim_end().len();
+            updated.truncate(trimmed_len);
+            if !updated.is_empty() {
+                updated.push_str("\n\n");
+            }
+            updated.push_str(&moved_text);
+            updated.push('\n');
+            updated
+        }
+        None => {
+            let module = plan_module_at(target_path)?;
+            let parent_text = plan
+                .files
+                .get(&module.parent_file)
+                .cloned()
+                .or_else(|| files.get(&module.parent_file).cloned())
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Parent module {} was not loaded",
+                        module.parent_file.display()
+                    )
+                })?;
+            let parent_text = insert_mod_declaration(
+                &parent_text,
+                &module.module_name,
+                module_root.visibility(),
+            )?;
+            plan.files.insert(module.parent_file.clone(), parent_text);
+            plan.created_module = Some(module);
+
+            let mut text = String::new();
+            if !target_imports.is_empty() {
+                text.push_str(&target_imports.join("\n"));
+                text.push_str("\n\n");
+            }
+            text.push_str(&moved_text);
+            text.push('\n');
+            text
+        }
+    };
+    plan.files.insert(target_path.clone(), target_text);
+
+    Ok(plan)
+}
+
+/// How a file names the crate whose items move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum CrateRoot {
+    /// A module of the crate itself, which names it `crate`.
+    Local,
+    /// Another target or workspace member, e.g. a file under tests/,
+    /// examples/ or benches/, which names it by its crate name.
+    External,
+}
+
+impl CrateRoot {
+    /// Visibility an item needs to be reachable from files with this root.
+    fn visibility(self) -> &'static str {
+        match self {
+            CrateRoot::Local => "pub(crate) ",
+            CrateRoot::External => "pub ",
+        }
+    }
+}
+
+struct Relocation<'a> {
+    crate_name: &'a str,
+    source_module: &'a [String],
+    target_module: &'a [String],
+    moved_names: &'a BTreeSet<String>,
+}
+
+impl Relocation<'_> {
+    /// Resolve `path` to segments relative to the moving crate's root, or
+    /// `None` when it points elsewhere.
+    fn resolve(&self, path: &[String], module: &[String], root: CrateRoot) -> Option<Vec<String>> {
+        match root {
+            CrateRoot::Local => absolutize(path, module, self.crate_name),
+            CrateRoot::External => match path.split_first() {
+                Some((first, rest)) if *first == self.crate_name.replace('-', "_") => {
+                    Some(rest.to_vec())
+                }
+                _ => None,
+            },
+        }
+    }
+
+    /// Render crate-relative segments as a path that resolves from a file.
+    fn render(&self, segments: &[String], root: CrateRoot) -> String {
+        match root {
+            CrateRoot::Local => crate_path(segments),
+            CrateRoot::External => std::iter::once(self.crate_name.replace('-', "_"))
+                .chain(segments.iter().cloned())
+                .collect::<Vec<_>>()
+                .join("::"),
+        }
+    }
+
+    fn in_target(&self, module: &[String], root: CrateRoot) -> bool {
+        root == CrateRoot::Local && module == self.target_module
+    }
+
+    /// Rewrite the entries of `statement` that import a moved item, returning
+    /// the edit for the statement if anything changed.
+    fn rewrite_statement(
+        &self,
+        text: &str,
+        statement: &UseStatement,
+        module: &[String],
+        root: CrateRoot,
+        bound_names: &mut HashSet<String>,
+    ) -> Option<ByteEdit> {
+        let mut tree = statement.tree.clone();
+        let mut extra_lines = Vec::new();
+
+        let remaining = tree.retain(&mut |entry| {
+            let Some(resolved) = self.resolve(&entry.path, module, root) else {
+                return true;
+            };
+            let points_at_moved = resolved.len() > self.source_module.len()
+                && resolved.starts_with(self.source_module)
+                && self
+                    .moved_names
+                    .contains(&resolved[self.source_module.len()]);
+            if !points_at_moved {
+                return true;
+            }
+
+            let mut relocated = self.target_module.to_vec();
+            relocated.extend(resolved[self.source_module.len()..].iter().cloned());
+            let is_local_item = self.in_target(module, root)
+                && relocated.len() == self.target_module.len() + 1
+                && entry.alias.is_none();
+            if !is_local_item {
+                let path = self.render(&relocated, root);
+                let body = match (&entry.alias, entry.glob) {
+                    (_, true) => format!("{path}::*"),
+                    (Some(alias), false) => format!("{path} as {alias}"),
+                    (None, false) => path,
+                };
+                extra_lines.push(format!(
+                    "{}{}use {body};",
+                    statement.indent, statement.visibility
+                ));
+            }
+            if let Some(name) = entry.bound_name() {
+                bound_names.insert(name.to_string());
+            }
+            false
+        });
+
+        if remaining && extra_lines.is_empty() {
+            return None;
+        }
+        Some(replace_statement(
+            text,
+            statement,
+            remaining.then_some(&tree),
+            &extra_lines,
+        ))
+    }
+}
+
+fn replace_statement(
+    text: &str,
+    statement: &UseStatement,
+    tree: Option<&crate::analyzer::imports::UseTree>,
+    extra_lines: &[String],
+) -> ByteEdit {
+    let mut lines: Vec<String> = tree
+        .map(|tree| statement.render(tree))
+        .into_iter()
+        .collect();
+    lines.extend(extra_lines.iter().cloned());
+
+    if lines.is_empty() {
+        ByteEdit::delete(
+            statement.attributes_start,
+            line_end_inclusive(text, statement.end),
+        )
+    } else {
+        ByteEdit::replace(statement.start, statement.end, lines.join("\n"))
+    }
+}
+
+/// Byte range of an item including preceding attributes, doc comments and
+/// comments, and its trailing newline.
+fn item_byte_range(source: &str, symbol: &DocumentSymbol) -> (usize, usize) {
+    let mut start = line_start(source, offset_at(source, &symbol.range.start));
+    let end = line_end_inclusive(source, offset_at(source, &symbol.range.end));
+
+    while start > 0 {
+        let previous_start = line_start(source, start - 1);
+        let previous = source[previous_start..start].trim();
+        let is_attached = previous.starts_with("#[")
+            || (previous.starts_with("//") && !previous.starts_with("//!"));
+        if is_attached {
+            start = previous_start;
+            continue;
+        }
+
+        // Multi-line attributes such as `#[derive(\n Debug,\n)]`.
+        if previous.ends_with(']') {
+            let mut cursor = previous_start;
+            let mut attribute_start = None;
+            for _ in 0..32 {
+                if cursor == 0 {
+                    break;
+                }
+                cursor = line_start(source, cursor - 1);
+                let line = source[cursor..].lines().next().unwrap_or_default().trim();
+                if line.is_empty() {
+                    break;
+                }
+                if line.starts_with("#[") {
+                    attribute_start = Some(cursor);
+                    break;
+                }
+            }
+            if let Some(attribute_start) = attribute_start {
+                start = attribute_start;
+                continue;
+            }
+        }
+        break;
+    }
+
+    (start, end)
+}
+
+fn declaration_offset(source: &str, position: &Position) -> usize {
+    let start = line_start(source, offset_at(source, position));
+    let line = &source[start..line_end_inclusive(source, start)];
+    start + (line.len() - line.trim_start().len())
+}
+
+fn is_public_at(source: &str, offset: usize) -> bool {
+    let rest = &source[offset..];
+    strip_visibility(rest).len() != rest.len()
+}
+
+fn remaining_identifiers<'a>(
+    source: &'a str,
+    cuts: &[(usize, usize)],
+    uses: &[UseStatement],
+) -> HashSet<&'a str> {
+    let mut excluded: Vec<(usize, usize)> = cuts.to_vec();
+    excluded.extend(
+        uses.iter()
+            .map(|statement| (statement.start, statement.end)),
+    );
+    excluded.sort();
+
+    let mut idents = HashSet::new();
+    let mut cursor = 0usize;
+    for (start, end) in excluded {
+        if start > cursor {
+            idents.extend(identifiers(&source[cursor..start]));
+        }
+        cursor = cursor.max(end);
+    }
+    idents.extend(identifiers(&source[cursor.min(source.len())..]));
+    idents
+}
+
+/// Module path at `offset`, accounting for inline `mod name { ... }` blocks.
+fn module_at(text: &str, offset: usize, file_module: &[String]) -> Vec<String> {
+    let mut inline = Vec::new();
+    let start = line_start(text, offset);
+    let line = text[start..].lines().next().unwrap_or_default();
+    let mut indent = line.len() - line.trim_start().len();
+
+    for previous in text[..start].lines().rev() {
+        if indent == 0 {
+            break;
+        }
+        let trimmed = previous.trim_start();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let previous_indent = previous.len() - trimmed.len();
+        if previous_indent < indent {
+            indent = previous_indent;
+            if let Some(name) = strip_visibility(trimmed)
+                .strip_prefix("mod ")
+                .and_then(|rest| rest.split_once('{'))
+                .map(|(name, _)| name.trim())
+            {
+                inline.insert(0, name.to_string());
+            }
+        }
+    }
+
+    let mut module = file_module.to_vec();
+    module.extend(inline);
+    module
+}
+
+/// The `a::b::` path written directly before the identifier at `offset`.
+fn path_prefix(text: &str, offset: usize) -> (usize, &str) {
+    let before = &text[..offset];
+    let start = before
+        .char_indices()
+        .rev()
+        .take_while(|(_, ch)| *ch == ':' || *ch == '_' || ch.is_alphanumeric())
+        .last()
+        .map(|(index, _)| index)
+        .unwrap_or(offset);
+    let prefix = &text[start..offset];
+    if prefix.ends_with("::") {
+        (start, prefix)
+    } else {
+        (offset, "")
+    }
+}
+
+fn collapse_blank_lines(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut blank_run = 0;
+    for line in text.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            blank_run += 1;
+            if blank_run > 1 {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        collapsed.push_str(line);
+    }
+    let trimmed = collapsed.trim_end().len();
+    collapsed.truncate(trimmed);
+    collapsed.push('\n');
+    collapsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MoveContext, path_from_uri, plan_move, reference_targets, select_items};
+    use crate::analyzer::edits::position_at;
+    use crate::analyzer::protocol::{DocumentSymbol, Location, Range, file_uri};
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::Path;
+
+    fn symbol(source: &str, name: &str, kind: u32, text: &str, ident: &str) -> DocumentSymbol {
+        let start = source.find(text).expect("item text present");
+        let end = start + text.len();
+        let name_start = start + text.find(ident).expect("ident present");
+        DocumentSymbol {
+            name: name.to_string(),
+            detail: None,
+            kind,
+            range: Range {
+                start: position_at(source, start),
+                end: position_at(source, end),
+            },
+            selection_range: Range {
+                start: position_at(source, name_start),
+                end: position_at(source, name_start + ident.len()),
+            },
+            children: None,
+        }
+    }
+
+    fn location(path: &Path, text: &str, offset: usize, len: usize) -> Location {
+        Location {
+            uri: file_uri(path),
+            range: Range {
+                start: position_at(text, offset),
+                end: position_at(text, offset + len),
+            },
+        }
+    }
+
+    #[test]
+    fn moves_item_with_impl_imports_and_references() {
+        let scratch = tempfile::tempdir().unwrap();
+        let root = scratch.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+
+        let lib_path = root.join("src/lib.rs");
+        let source_path = root.join("src/shapes.rs");
+        let target_path = root.join("src/geometry.rs");
+        let user_path = root.join("src/user.rs");
+        let test_path = root.join("tests/api.rs");
+
+        let lib = "pub mod shapes;\npub mod user;\n";
+        let source = "use std::fmt;\nuse std::collections::HashMap;\n\n/// A point.\n#[derive(Debug)]\nstruct Point {\n    x: i32,\n}\n\nimpl fmt::Display for Point {\n    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {\n        write!(f, \"{}\", scale(self.x))\n    }\n}\n\nfn scale(v: i32) -> i32 {\n    v * 2\n}\n\npub fn lookup() -> HashMap<i32, Point> {\n    HashMap::new()\n}\n";
+        let user = "use crate::shapes::{lookup, Point};\n\npub fn make() -> Point {\n    lookup();\n    crate::shapes::Point { x: 1 }\n}\n";
+        fs::write(&lib_path, lib).unwrap();
+        fs::write(&source_path, source).unwrap();
+        fs::write(&user_path, user).unwrap();
+        let test = "use demo::shapes::Point;\n\n#[test]\nfn builds() {\n    let _: Point = demo::shapes::Point { x: 1 };\n}\n";
+
+        let struct_text = "struct Point {\n    x: i32,\n}";
+        let mut point = symbol(source, "Point", 23, struct_text, "Point");
+        let field_offset = source.find("x: i32").unwrap();
+        point.children = Some(vec![DocumentSymbol {
+            children: None,
+            ..symbol(source, "x", 8, "x: i32", "x")
+        }]);
+        let display = symbol(
+            source,
+            "impl fmt::Display for Point",
+            19,
+            &source[source.find("impl fmt").unwrap()..source.find("fn scale").unwrap() - 2],
+            "impl",
+        );
+        let scale = symbol(
+            source,
+            "scale",
+            12,
+            "fn scale(v: i32) -> i32 {\n    v * 2\n}",
+            "scale",
+        );
+        let lookup = symbol(
+            source,
+            "lookup",
+            12,
+            "pub fn lookup() -> HashMap<i32, Point> {\n    HashMap::new()\n}",
+            "lookup",
+        );
+        let symbols = vec![point, display, scale, lookup];
+
+        let items = select_items(source, &symbols, &["Point".to_string()]).unwrap();
+        assert_eq!(items.len(), 2, "struct and its impl are selected");
+        assert!(source[items[0].start..items[0].end].starts_with("/// A point.\n#[derive(Debug)]"));
+
+        let targets = reference_targets(source, &items);
+        let point_decl = source.find("struct Point").unwrap() + "struct ".len();
+        let lookup_use = source.find("Point>").unwrap();
+        let user_use = user.find("Point}").unwrap();
+        let user_ret = user.find("Point {\n    lookup").unwrap();
+        let user_path_ref = user.rfind("Point { x").unwrap();
+        let references = vec![
+            (
+                targets[0].clone(),
+                vec![
+                    location(&source_path, source, point_decl, 5),
+                    location(&source_path, source, lookup_use, 5),
+                    location(&user_path, user, user_use, 5),
+                    location(&user_path, user, user_ret, 5),
+                    location(&user_path, user, user_path_ref, 5),
+                    location(&test_path, test, test.find("Point;").unwrap(), 5),
+                    location(&test_path, test, test.find("Point =").unwrap(), 5),
+                    location(&test_path, test, test.rfind("Point {").unwrap(), 5),
+                ],
+            ),
+            (
+                targets[1].clone(),
+                vec![
+                    location(&source_path, source, field_offset, 1),
+                    location(&user_path, user, user.find("x: 1").unwrap(), 1),
+                ],
+            ),
+        ];
+
+        let mut files = BTreeMap::new();
+        files.insert(lib_path.clone(), lib.to_string());
+        files.insert(source_path.clone(), source.to_string());
+        files.insert(user_path.clone(), user.to_string());
+        files.insert(test_path.clone(), test.to_string());
+
+        let context = MoveContext {
+            source_path: source_path.clone(),
+            target_path: target_path.clone(),
+            crate_name: "demo".to_string(),
+        };
+        let plan = plan_move(&context, &symbols, &items, &references, &files).unwrap();
+
+        assert_eq!(plan.moved, vec!["Point".to_string()]);
+        assert_eq!(
+            plan.widened,
+            vec!["Point (pub)", "Point::x (pub(crate))", "scale (pub(crate))"]
+        );
+
+        let target = &plan.files[&target_path];
+        assert_eq!(
+            target,
+            "use crate::shapes::scale;\nuse std::fmt;\n\n/// A point.\n#[derive(Debug)]\npub struct Point {\n    pub(crate) x: i32,\n}\n\nimpl fmt::Display for Point {\n    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {\n        write!(f, \"{}\", scale(self.x))\n    }\n}\n"
+        );
+
+        let updated_source = &plan.files[&source_path];
+        assert_eq!(
+            updated_source,
+            "use std::collections::HashMap;\nuse crate::geometry::Point;\n\npub(crate) fn scale(v: i32) -> i32 {\n    v * 2\n}\n\npub fn lookup() -> HashMap<i32, Point> {\n    HashMap::new()\n}\n"
+        );
+
+        let updated_user = &plan.files[&user_path];
+        assert_eq!(
+            updated_user,
+            "use crate::shapes::lookup;\nuse crate::geometry::Point;\n\npub fn make() -> Point {\n    lookup();\n    crate::geometry::Point { x: 1 }\n}\n"
+        );
+
+        assert_eq!(
+            plan.files[&test_path],
+            "use demo::geometry::Point;\n\n#[test]\nfn builds() {\n    let _: Point = demo::geometry::Point { x: 1 };\n}\n"
+        );
+
+        assert_eq!(
+            plan.files[&lib_path],
+            "pub mod geometry;\npub mod shapes;\npub mod user;\n"
+        );
+        assert!(plan.created_module.is_some());
+
+        // Without references from tests/, the module only needs `pub(crate)`.
+        let local_references: Vec<_> = references
+            .iter()
+            .map(|(target, locations)| {
+                let locations = locations
+                    .iter()
+                    .filter(|location| path_from_uri(&location.uri) != test_path)
+                    .cloned()
+                    .collect();
+                (target.clone(), locations)
+            })
+            .collect();
+        files.remove(&test_path);
+        let plan = plan_move(&context, &symbols, &items, &local_references, &files).unwrap();
+        assert_eq!(
+            plan.files[&lib_path],
+            "pub(crate) mod geometry;\npub mod shapes;\npub mod user;\n"
+        );
+        assert!(plan.files[&target_path].contains("pub(crate) struct Point"));
+    }
+}
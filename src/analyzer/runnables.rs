@@ -0,0 +1,121 @@
+use crate::analyzer::hierarchy::format_location;
+use crate::analyzer::protocol::{Location, Runnable};
+
+/// What a runnable does, taken from the first word of its label
+/// (`test`, `test-mod`, `bench`, `doctest`, `run`, `cargo`, ...).
+pub fn runnable_category(runnable: &Runnable) -> &str {
+    runnable
+        .label
+        .split_whitespace()
+        .next()
+        .unwrap_or("runnable")
+}
+
+/// Shell command line for a runnable, including the `--` separated test
+/// binary arguments.
+pub fn runnable_command(runnable: &Runnable) -> String {
+    let args = &runnable.args;
+    let mut words: Vec<&str> = Vec::new();
+    if runnable.kind == "shell" {
+        words.push(args.program.as_deref().unwrap_or("sh"));
+        words.extend(args.args.iter().map(String::as_str));
+    } else {
+        words.push(args.override_cargo.as_deref().unwrap_or("cargo"));
+        words.extend(args.cargo_args.iter().map(String::as_str));
+        words.extend(args.cargo_extra_args.iter().map(String::as_str));
+        if !args.executable_args.is_empty() {
+            words.push("--");
+            words.extend(args.executable_args.iter().map(String::as_str));
+        }
+    }
+    words
+        .into_iter()
+        .map(shell_quote)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn shell_quote(word: &str) -> String {
+    let is_plain = !word.is_empty()
+        && word
+            .chars()
+            .all(|ch| ch.is_alphanumeric() || "-_./:=@+,%".contains(ch));
+    if is_plain {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+/// One entry per runnable: label, location, command and working directory.
+pub fn render_runnables(runnables: &[Runnable]) -> String {
+    runnables
+        .iter()
+        .map(|runnable| {
+            let mut entry = format!("[{}] {}", runnable_category(runnable), runnable.label);
+            if let Some(link) = &runnable.location {
+                entry.push_str(&format!(
+                    " at {}",
+                    format_location(&Location {
+                        uri: link.target_uri.clone(),
+                        range: link.target_selection_range.clone(),
+                    })
+                ));
+            }
+            entry.push_str(&format!("\n  {}", runnable_command(runnable)));
+            if let Some(cwd) = runnable
+                .args
+                .cwd
+                .as_ref()
+                .or(runnable.args.workspace_root.as_ref())
+            {
+                entry.push_str(&format!("\n  cwd: {cwd}"));
+            }
+            entry
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_runnables, runnable_category};
+    use crate::analyzer::protocol::Runnable;
+    use serde_json::json;
+
+    #[test]
+    fn renders_cargo_commands() {
+        let runnables: Vec<Runnable> = serde_json::from_value(json!([
+            {
+                "label": "test tests::parses_scopes",
+                "kind": "cargo",
+                "location": {
+                    "targetUri": "file:///w/src/ssr.rs",
+                    "targetRange": {"start": {"line": 120, "character": 4}, "end": {"line": 130, "character": 5}},
+                    "targetSelectionRange": {"start": {"line": 121, "character": 7}, "end": {"line": 121, "character": 20}}
+                },
+                "args": {
+                    "workspaceRoot": "/w",
+                    "cargoArgs": ["test", "--package", "demo", "--lib"],
+                    "executableArgs": ["ssr::tests::parses_scopes", "--exact", "--show-output"]
+                }
+            },
+            {
+                "label": "doctest Point::new",
+                "kind": "cargo",
+                "args": {
+                    "cwd": "/w",
+                    "cargoArgs": ["test", "--doc", "--package", "demo"],
+                    "executableArgs": ["Point::new (line 12)", "--exact"]
+                }
+            }
+        ]))
+        .unwrap();
+
+        assert_eq!(runnable_category(&runnables[1]), "doctest");
+        assert_eq!(
+            render_runnables(&runnables),
+            "[test] test tests::parses_scopes at file:///w/src/ssr.rs:122:8\n  cargo test --package demo --lib -- ssr::tests::parses_scopes --exact --show-output\n  cwd: /w\n[doctest] doctest Point::new\n  cargo test --doc --package demo -- 'Point::new (line 12)' --exact\n  cwd: /w"
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use serde_json::Value;
+
+use crate::analyzer::hover::{hover_markdown, plain_text};
+use crate::analyzer::protocol::{ParameterInformation, SignatureHelp, SignatureInformation};
+
+/// Render every candidate signature with its parameters, marking the active
+/// signature and parameter.
+pub fn render_signature_help(help: &SignatureHelp) -> String {
+    if help.signatures.is_empty() {
+        return "No signature help available".to_string();
+    }
+
+    let active_signature = help.active_signature.unwrap_or(0) as usize;
+    let total = help.signatures.len();
+    help.signatures
+        .iter()
+        .enumerate()
+        .map(|(index, signature)| {
+            let is_active = index == active_signature;
+            let active_parameter = signature.active_parameter.or(help.active_parameter);
+            render_signature(signature, index, total, is_active, active_parameter)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn render_signature(
+    signature: &SignatureInformation,
+    index: usize,
+    total: usize,
+    is_active: bool,
+    active_parameter: Option<u32>,
+) -> String {
+    let marker = if is_active { " (active)" } else { "" };
+    let mut lines = vec![
+        format!("Signature {} of {total}{marker}:", index + 1),
+        signature.label.clone(),
+    ];
+
+    let parameters = signature.parameters.as_deref().unwrap_or_default();
+    if is_active
+        && let Some(active) = active_parameter.and_then(|active| parameters.get(active as usize))
+    {
+        lines.push(format!(
+            "Active parameter: {}",
+            parameter_label(&signature.label, active)
+        ));
+    }
+
+    if !parameters.is_empty() {
+        lines.push("Parameters:".to_string());
+        for (position, parameter) in parameters.iter().enumerate() {
+            let pointer = if is_active && active_parameter == Some(position as u32) {
+                ">"
+            } else {
+                "-"
+            };
+            let mut line = format!(
+                "  {pointer} {}",
+                parameter_label(&signature.label, parameter)
+            );
+            if let Some(docs) = documentation(parameter.documentation.as_ref()) {
+                line.push_str(&format!(" — {}", docs.replace('\n', " ")));
+            }
+            lines.push(line);
+        }
+    }
+
+    if let Some(docs) = documentation(signature.documentation.as_ref()) {
+        lines.push(format!("Docs:\n{docs}"));
+    }
+    lines.join("\n")
+}
+
+/// Text of a parameter, resolving `[start, end]` UTF-16 offsets into the
+/// signature label.
+fn parameter_label(signature_label: &str, parameter: &ParameterInformation) -> String {
+    match &parameter.label {
+        Value::String(label) => label.clone(),
+        Value::Array(bounds) => {
+            let offset = |index: usize| bounds.get(index).and_then(Value::as_u64).unwrap_or(0);
+            let units: Vec<u16> = signature_label.encode_utf16().collect();
+            let start = (offset(0) as usize).min(units.len());
+            let end = (offset(1) as usize).clamp(start, units.len());
+            String::from_utf16_lossy(&units[start..end])
+        }
+        other => other.to_string(),
+    }
+}
+
+fn documentation(value: Option<&Value>) -> Option<String> {
+    let text = plain_text(&hover_markdown(value?));
+    (!text.is_empty()).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_signature_help;
+    use crate::analyzer::protocol::SignatureHelp;
+    use serde_json::json;
+
+    #[test]
+    fn renders_signatures_with_active_parameter() {
+        let help: SignatureHelp = serde_json::from_value(json!({
+            "signatures": [
+                {
+                    "label": "fn insert<K: Hash>(&mut self, key: K, value: V) -> Option<V>",
+                    "documentation": {"kind": "markdown", "value": "Inserts a key-value pair."},
+                    "parameters": [
+                        {"label": [30, 36]},
+                        {"label": "value: V", "documentation": "The value."}
+                    ]
+                },
+                {"label": "fn insert_default(&mut self)"}
+            ],
+            "activeSignature": 0,
+            "activeParameter": 1
+        }))
+        .unwrap();
+
+        assert_eq!(
+            render_signature_help(&help),
+            "Signature 1 of 2 (active):\nfn insert<K: Hash>(&mut self, key: K, value: V) -> Option<V>\nActive parameter: value: V\nParameters:\n  - key: K\n  > value: V — The value.\nDocs:\nInserts a key-value pair.\n\nSignature 2 of 2:\nfn insert_default(&mut self)"
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use anyhow::{Result, bail};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tokio::fs;
+
+use crate::analyzer::edits::{apply_text_edits, offset_at, write_files};
+use crate::analyzer::protocol::{Range, TextEdit};
+
+/// Files a structural search and replace rule is matched against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SsrScope {
+    File(String),
+    Files(Vec<String>),
+    /// Every file in the workspace; paths in the rule resolve from
+    /// `context_file`.
+    Workspace {
+        context_file: String,
+    },
+}
+
+impl SsrScope {
+    pub fn parse(scope: &str, file_path: Option<&str>, files: Vec<String>) -> Result<Self> {
+        match scope.to_ascii_lowercase().as_str() {
+            "file" => match file_path {
+                Some(file_path) => Ok(SsrScope::File(file_path.to_string())),
+                None => bail!("The file scope requires file_path"),
+            },
+            "files" if files.is_empty() => bail!("The files scope requires a non-empty files list"),
+            "files" => Ok(SsrScope::Files(files)),
+            "workspace" => match file_path.or(files.first().map(String::as_str)) {
+                Some(context_file) => Ok(SsrScope::Workspace {
+                    context_file: context_file.to_string(),
+                }),
+                None => {
+                    bail!("The workspace scope requires file_path to resolve paths in the rule")
+                }
+            },
+            other => bail!("Unknown scope '{other}'; expected file, files or workspace"),
+        }
+    }
+
+    /// File whose position resolves paths in the rule.
+    pub fn context_file(&self) -> &str {
+        match self {
+            SsrScope::File(file) => file,
+            SsrScope::Files(files) => &files[0],
+            SsrScope::Workspace { context_file } => context_file,
+        }
+    }
+}
+
+/// What to do with a rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SsrMode {
+    /// Only check that the rule parses and its paths resolve.
+    ParseOnly,
+    /// List matches without editing files.
+    Search,
+    Apply,
+}
+
+/// One match of a rule and the text it is replaced with.
+#[derive(Debug, Clone)]
+pub struct SsrMatch {
+    pub uri: String,
+    pub range: Range,
+    pub matched: String,
+    pub replacement: String,
+}
+
+/// Matches in one file, from the edits rust-analyzer computed for it.
+pub fn ssr_matches(uri: &str, source: &str, edits: &[TextEdit]) -> Vec<SsrMatch> {
+    let mut matches: Vec<SsrMatch> = edits
+        .iter()
+        .map(|edit| {
+            let start = offset_at(source, &edit.range.start);
+            let end = offset_at(source, &edit.range.end).max(start);
+            SsrMatch {
+                uri: uri.to_string(),
+                range: edit.range.clone(),
+                matched: source[start..end].to_string(),
+                replacement: edit.new_text.clone(),
+            }
+        })
+        .collect();
+    matches.sort_by_key(|found| (found.range.start.line, found.range.start.character));
+    matches
+}
+
+/// Drop edits repeated across requests, e.g. when a file is listed twice or
+/// a workspace search also reports the context file.
+pub fn dedup_edits(edits: &mut Vec<TextEdit>) {
+    let key = |edit: &TextEdit| {
+        (
+            edit.range.start.line,
+            edit.range.start.character,
+            edit.range.end.line,
+            edit.range.end.character,
+        )
+    };
+    edits.sort_by_key(key);
+    edits.dedup_by(|a, b| key(a) == key(b) && a.new_text == b.new_text);
+}
+
+/// Match each file's edits against its contents and, in apply mode, write
+/// the rewritten files. Every file is rewritten in memory first and then
+/// written all at once, so a failure leaves the workspace untouched. Returns
+/// the matches and the files written.
+pub async fn apply_file_edits(
+    file_edits: &mut BTreeMap<PathBuf, (String, Vec<TextEdit>)>,
+    mode: SsrMode,
+) -> Result<(Vec<SsrMatch>, Vec<PathBuf>)> {
+    let mut matches = Vec::new();
+    let mut rewritten = BTreeMap::new();
+    for (path, (uri, edits)) in file_edits.iter_mut() {
+        dedup_edits(edits);
+        let source = fs::read_to_string(&path).await?;
+        matches.extend(ssr_matches(uri, &source, edits));
+        if mode == SsrMode::Apply {
+            let text = apply_text_edits(&source, edits).map_err(|e| {
+                anyhow::anyhow!("Failed to apply replacements to {}: {e}", path.display())
+            })?;
+            rewritten.insert(path.clone(), text);
+        }
+    }
+
+    write_files(&rewritten).await?;
+    Ok((matches, rewritten.into_keys().collect()))
+}
+
+/// List matches as `uri:line:column` (1-based) followed by the matched and
+/// replacement text in diff style.
+pub fn render_matches(matches: &[SsrMatch], applied: bool) -> String {
+    if matches.is_empty() {
+        return "No matches".to_string();
+    }
+
+    let mut files: Vec<&str> = matches.iter().map(|found| found.uri.as_str()).collect();
+    files.dedup();
+    let mut output = format!(
+        "{} {} {} in {} {}:",
+        if applied { "Applied" } else { "Found" },
+        matches.len(),
+        match (applied, matches.len()) {
+            (true, 1) => "replacement",
+            (true, _) => "replacements",
+            (false, 1) => "match",
+            (false, _) => "matches",
+        },
+        files.len(),
+        if files.len() == 1 { "file" } else { "files" }
+    );
+    for found in matches {
+        output.push_str(&format!(
+            "\n{}:{}:{}",
+            found.uri,
+            found.range.start.line + 1,
+            found.range.start.character + 1
+        ));
+        for line in found.matched.lines() {
+            output.push_str(&format!("\n  - {line}"));
+        }
+        for line in found.replacement.lines() {
+            output.push_str(&format!("\n  + {line}"));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SsrMode, SsrScope, apply_file_edits, dedup_edits, render_matches, ssr_matches};
+    use crate::analyzer::protocol::{Position, Range, TextEdit};
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    #[test]
+    fn parses_scopes() {
+        assert_eq!(
+            SsrScope::parse("workspace", Some("/w/src/lib.rs"), Vec::new()).unwrap(),
+            SsrScope::Workspace {
+                context_file: "/w/src/lib.rs".to_string()
+            }
+        );
+        let files = SsrScope::parse("files", None, vec!["/w/src/a.rs".to_string()]).unwrap();
+        assert_eq!(files.context_file(), "/w/src/a.rs");
+        assert!(SsrScope::parse("file", None, Vec::new()).is_err());
+        assert!(SsrScope::parse("crate", Some("/w/src/lib.rs"), Vec::new()).is_err());
+    }
+
+    #[test]
+    fn lists_matches_with_replacements() {
+        let source = "fn main() {\n    let x = foo(1, 2);\n}\n";
+        let edit = TextEdit {
+            range: Range {
+                start: Position {
+                    line: 1,
+                    character: 12,
+                },
+                end: Position {
+                    line: 1,
+                    character: 21,
+                },
+            },
+            new_text: "bar(2, 1)".to_string(),
+        };
+        let matches = ssr_matches("file:///w/src/main.rs", source, &[edit]);
+        assert_eq!(matches[0].matched, "foo(1, 2)");
+        assert_eq!(
+            render_matches(&matches, false),
+            "Found 1 match in 1 file:\nfile:///w/src/main.rs:2:13\n  - foo(1, 2)\n  + bar(2, 1)"
+        );
+    }
+
+    #[test]
+    fn drops_repeated_edits() {
+        let edit = |line, text: &str| TextEdit {
+            range: Range {
+                start: Position { line, character: 4 },
+                end: Position { line, character: 9 },
+            },
+            new_text: text.to_string(),
+        };
+        let mut edits = vec![edit(3, "bar()"), edit(1, "bar()"), edit(3, "bar()")];
+        dedup_edits(&mut edits);
+        let lines: Vec<u32> = edits.iter().map(|edit| edit.range.start.line).collect();
+        assert_eq!(lines, [1, 3]);
+    }
+
+    #[tokio::test]
+    async fn applies_to_every_file_or_none() {
+        let scratch = tempfile::tempdir().unwrap();
+        let root = scratch.path();
+        let source = "fn main() {\n    foo(1, 2);\n}\n";
+        let edit = TextEdit {
+            range: Range {
+                start: Position {
+                    line: 1,
+                    character: 4,
+                },
+                end: Position {
+                    line: 1,
+                    character: 13,
+                },
+            },
+            new_text: "bar(2, 1)".to_string(),
+        };
+        let mut file_edits = BTreeMap::new();
+        for name in ["a.rs", "b.rs"] {
+            let path = root.join(name);
+            fs::write(&path, source).unwrap();
+            file_edits.insert(path, (format!("file:///{name}"), vec![edit.clone()]));
+        }
+        // A directory where b.rs is staged makes b.rs unwritable.
+        fs::create_dir_all(root.join(".b.rs.rustmcp-tmp/blocked")).unwrap();
+
+        assert!(
+            apply_file_edits(&mut file_edits, SsrMode::Apply)
+                .await
+                .is_err()
+        );
+        for name in ["a.rs", "b.rs"] {
+            assert_eq!(fs::read_to_string(root.join(name)).unwrap(), source);
+        }
+
+        fs::remove_dir_all(root.join(".b.rs.rustmcp-tmp")).unwrap();
+        let (matches, written) = apply_file_edits(&mut file_edits, SsrMode::Apply)
+            .await
+            .unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(written, [root.join("a.rs"), root.join("b.rs")]);
+        assert_eq!(
+            fs::read_to_string(root.join("b.rs")).unwrap(),
+            "fn main() {\n    bar(2, 1);\n}\n"
+        );
+    }
+}
@@ -1,7 +1,7 @@
-use crate::analyzer::protocol::SymbolPathSegment;
+use crate::analyzer::protocol::{SymbolPathSegment, path_from_uri};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::path::{Component, Path, PathBuf};
+use std::path::{Component, Path};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SymbolKind {
@@ -20,6 +20,16 @@ pub struct SymbolIdentity {
     pub kind: SymbolKind,
 }
 
+impl SymbolIdentity {
+    /// Path in Rust syntax, e.g. `my_crate::module::item`.
+    pub fn qualified_path(&self) -> String {
+        let mut segments = vec![self.crate_name.as_str()];
+        segments.extend(self.module_path.iter().map(String::as_str));
+        segments.push(&self.item_name);
+        segments.join("::")
+    }
+}
+
 pub fn symbol_kind_from_lsp_kind(kind: u32, name_hint: Option<&str>) -> SymbolKind {
     match kind {
         6 => SymbolKind::Method,
@@ -27,10 +37,10 @@ pub fn symbol_kind_from_lsp_kind(kind: u32, name_hint: Option<&str>) -> SymbolKi
         12 => SymbolKind::FreeFunction,
         23 => SymbolKind::Impl,
         _ => {
-            if let Some(name) = name_hint {
-                if name.trim_start().starts_with("impl ") {
-                    return SymbolKind::Impl;
-                }
+            if let Some(name) = name_hint
+                && name.trim_start().starts_with("impl ")
+            {
+                return SymbolKind::Impl;
             }
             SymbolKind::Unknown
         }
@@ -146,12 +156,11 @@ fn parse_symbol_kind(kind_value: Option<&Value>, container_name: Option<&str>) -
         _ => SymbolKind::Unknown,
     };
 
-    if matches!(base_kind, SymbolKind::Unknown | SymbolKind::FreeFunction) {
-        if let Some(container) = container_name {
-            if container.trim_start().starts_with("impl ") {
-                return SymbolKind::Impl;
-            }
-        }
+    if matches!(base_kind, SymbolKind::Unknown | SymbolKind::FreeFunction)
+        && let Some(container) = container_name
+        && container.trim_start().starts_with("impl ")
+    {
+        return SymbolKind::Impl;
     }
 
     base_kind
@@ -173,13 +182,8 @@ fn container_segments(container: &str) -> Vec<String> {
         .collect()
 }
 
-fn path_from_uri(uri: &str) -> Option<PathBuf> {
-    let without_scheme = uri.strip_prefix("file://").unwrap_or(uri);
-    Some(PathBuf::from(without_scheme))
-}
-
 fn crate_name_from_uri(uri: &str) -> Option<String> {
-    let path = path_from_uri(uri)?;
+    let path = path_from_uri(uri);
     let components: Vec<String> = path
         .components()
         .filter_map(|component| match component {
@@ -188,10 +192,10 @@ fn crate_name_from_uri(uri: &str) -> Option<String> {
         })
         .collect();
 
-    if let Some(src_index) = components.iter().position(|component| component == "src") {
-        if src_index >= 1 {
-            return components.get(src_index - 1).cloned();
-        }
+    if let Some(src_index) = components.iter().position(|component| component == "src")
+        && src_index >= 1
+    {
+        return components.get(src_index - 1).cloned();
     }
 
     path.parent()
@@ -200,9 +204,7 @@ fn crate_name_from_uri(uri: &str) -> Option<String> {
 }
 
 fn module_path_from_uri(uri: &str) -> Vec<String> {
-    let Some(path) = path_from_uri(uri) else {
-        return Vec::new();
-    };
+    let path = path_from_uri(uri);
 
     let mut after_src = false;
     let mut segments: Vec<String> = path
@@ -227,10 +229,10 @@ fn module_path_from_uri(uri: &str) -> Vec<String> {
         let stem = Path::new(&last)
             .file_stem()
             .map(|stem| stem.to_string_lossy().into_owned());
-        if let Some(stem) = stem {
-            if stem != "mod" {
-                segments.push(stem);
-            }
+        if let Some(stem) = stem
+            && stem != "mod"
+        {
+            segments.push(stem);
         }
     }
 
@@ -0,0 +1,98 @@
+//! Text helpers for Rust declarations, shared by the analyzer tools and the
+//! compiler's symbol matching.
+
+/// Strip a leading `pub`/`pub(...)` visibility from `line`.
+pub fn strip_visibility(line: &str) -> &str {
+    if let Some(rest) = line.strip_prefix("pub(")
+        && let Some(close) = rest.find(')')
+    {
+        return rest[close + 1..].trim_start();
+    }
+    line.strip_prefix("pub ").unwrap_or(line)
+}
+
+/// Parse an impl symbol name such as `impl<T> Display for Foo<T>`, returning
+/// the self type's base name and whether it is a trait impl.
+pub fn impl_self_type(name: &str) -> Option<(String, bool)> {
+    let rest = name
+        .trim()
+        .trim_start_matches("unsafe ")
+        .strip_prefix("impl")?;
+    if !(rest.is_empty() || rest.starts_with([' ', '<'])) {
+        return None;
+    }
+
+    let mut rest = rest.trim_start();
+    if rest.starts_with('<') {
+        let mut depth = 0usize;
+        let mut end = rest.len();
+        for (index, ch) in rest.char_indices() {
+            match ch {
+                '<' => depth += 1,
+                '>' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = index + 1;
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        rest = rest[end..].trim_start();
+    }
+
+    let (self_type, is_trait_impl) = match split_top_level_for(rest) {
+        Some(self_type) => (self_type, true),
+        None => (rest, false),
+    };
+
+    let self_type = self_type
+        .split(" where")
+        .next()
+        .unwrap_or(self_type)
+        .trim()
+        .trim_start_matches('&')
+        .trim_start_matches("mut ")
+        .trim_start_matches("dyn ");
+    let base = self_type.split('<').next()?.trim();
+    let base = base.rsplit("::").next()?.trim();
+    (!base.is_empty()).then(|| (base.to_string(), is_trait_impl))
+}
+
+fn split_top_level_for(text: &str) -> Option<&str> {
+    let mut depth = 0i32;
+    for (index, ch) in text.char_indices() {
+        match ch {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            ' ' if depth == 0 && text[index..].starts_with(" for ") => {
+                return Some(&text[index + " for ".len()..]);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{impl_self_type, strip_visibility};
+
+    #[test]
+    fn parses_impl_self_types() {
+        assert_eq!(impl_self_type("impl Foo"), Some(("Foo".to_string(), false)));
+        assert_eq!(
+            impl_self_type("impl<T: Clone> fmt::Display for Wrapper<T>"),
+            Some(("Wrapper".to_string(), true))
+        );
+        assert_eq!(impl_self_type("implement"), None);
+    }
+
+    #[test]
+    fn strips_visibility() {
+        assert_eq!(strip_visibility("pub(crate) fn f()"), "fn f()");
+        assert_eq!(strip_visibility("pub struct S;"), "struct S;");
+        assert_eq!(strip_visibility("fn f()"), "fn f()");
+    }
+}
@@ -0,0 +1,465 @@
+//! Interleaving of extracted LLVM IR and assembly with the Rust source lines
+//! they were compiled from, as recorded in debuginfo.
+
+use super::extract::is_local_label;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// How extracted LLVM IR or assembly is rewritten before it is returned.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AnnotateOptions {
+    /// Comment each run of instructions with the source line it came from.
+    pub source_lines: bool,
+    /// Drop assembler directives, unused local labels and debug metadata.
+    pub strip_directives: bool,
+}
+
+impl AnnotateOptions {
+    pub fn is_enabled(&self) -> bool {
+        self.source_lines || self.strip_directives
+    }
+}
+
+/// Source files read on demand and split into lines.
+pub struct SourceLines<F> {
+    read: F,
+    files: HashMap<PathBuf, Option<Vec<String>>>,
+}
+
+impl<F: FnMut(&Path) -> Option<String>> SourceLines<F> {
+    pub fn new(read: F) -> Self {
+        Self {
+            read,
+            files: HashMap::new(),
+        }
+    }
+
+    fn line(&mut self, path: &Path, line: u32) -> Option<&str> {
+        let read = &mut self.read;
+        let lines = self
+            .files
+            .entry(path.to_path_buf())
+            .or_insert_with(|| read(path).map(|text| text.lines().map(str::to_string).collect()))
+            .as_ref()?;
+        lines
+            .get(line.checked_sub(1)? as usize)
+            .map(|text| text.trim())
+    }
+}
+
+/// A file named by debuginfo: as the compiler recorded it, and where to read it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SourceFile {
+    display: String,
+    path: PathBuf,
+}
+
+impl SourceFile {
+    fn new(directory: Option<&str>, name: &str) -> Self {
+        let path = match directory {
+            Some(directory) => Path::new(directory).join(name),
+            None => PathBuf::from(name),
+        };
+        Self {
+            display: name.to_string(),
+            path,
+        }
+    }
+}
+
+/// Writes the annotated output, adding a source comment whenever the
+/// location changes.
+struct Annotator<'a, F> {
+    comment: &'a str,
+    sources: &'a mut SourceLines<F>,
+    current: Option<(String, u32)>,
+    output: Vec<String>,
+}
+
+impl<F: FnMut(&Path) -> Option<String>> Annotator<'_, F> {
+    fn annotate(&mut self, file: &SourceFile, line: u32) {
+        // Line 0 marks compiler-generated code with no source location.
+        if line == 0 {
+            return;
+        }
+        let location = (file.display.clone(), line);
+        if self.current.as_ref() == Some(&location) {
+            return;
+        }
+        let annotation = match self.sources.line(&file.path, line) {
+            Some(text) => format!("{} {}:{line}: {text}", self.comment, file.display),
+            None => format!("{} {}:{line}", self.comment, file.display),
+        };
+        self.output.push(annotation);
+        self.current = Some(location);
+    }
+}
+
+/// Annotate and/or filter assembly extracted from one of `assemblies`.
+/// `.file` tables are read from the whole assembly that contains each
+/// function, since they are usually emitted before it.
+pub fn annotate_asm<F: FnMut(&Path) -> Option<String>>(
+    block: &str,
+    assemblies: &[&str],
+    comment: &str,
+    options: AnnotateOptions,
+    sources: &mut SourceLines<F>,
+) -> String {
+    let lines: Vec<&str> = block.lines().collect();
+    let mut files = HashMap::new();
+    let mut annotator = Annotator {
+        comment,
+        sources,
+        current: None,
+        output: Vec::new(),
+    };
+
+    for line in &lines {
+        let trimmed = line.trim();
+        let label = trimmed
+            .strip_suffix(':')
+            .filter(|label| !label.contains(char::is_whitespace));
+
+        if let Some(label) = label
+            && !is_local_label(label)
+            && !line.starts_with(char::is_whitespace)
+        {
+            files = assemblies
+                .iter()
+                .find(|asm| asm.lines().any(|asm_line| asm_line == *line))
+                .map(|asm| asm_file_table(asm))
+                .unwrap_or_default();
+            annotator.current = None;
+        }
+
+        if let Some(args) = directive_args(trimmed, ".loc")
+            && options.source_lines
+        {
+            let mut fields = args.split_whitespace().map(str::parse::<u32>);
+            if let (Some(Ok(file)), Some(Ok(source_line))) = (fields.next(), fields.next())
+                && let Some(file) = files.get(&file)
+            {
+                annotator.annotate(file, source_line);
+            }
+        }
+
+        if options.strip_directives {
+            let is_directive = trimmed.starts_with('.') && label.is_none();
+            let is_unused_label = label.is_some_and(|label| {
+                // References from directives do not count, as those are dropped too.
+                is_local_label(label)
+                    && !lines
+                        .iter()
+                        .any(|text| !text.trim_start().starts_with('.') && mentions(text, label))
+            });
+            if is_directive || is_unused_label {
+                continue;
+            }
+        }
+        annotator.output.push(line.to_string());
+    }
+
+    annotator.output.join("\n")
+}
+
+/// The arguments of `directive` if `line` is one, e.g. `1 3 9` for `.loc 1 3 9`.
+fn directive_args<'a>(line: &'a str, directive: &str) -> Option<&'a str> {
+    line.strip_prefix(directive)
+        .filter(|args| args.starts_with(char::is_whitespace))
+        .map(str::trim)
+}
+
+/// File numbers declared by `.file N "dir" "name"` or `.file N "name"`.
+fn asm_file_table(asm: &str) -> HashMap<u32, SourceFile> {
+    let mut files = HashMap::new();
+    for line in asm.lines() {
+        let Some(args) = directive_args(line.trim(), ".file") else {
+            continue;
+        };
+        let Some((number, rest)) = args.split_once(char::is_whitespace) else {
+            continue;
+        };
+        let Ok(number) = number.parse::<u32>() else {
+            continue;
+        };
+        let strings = quoted_strings(rest);
+        let file = match strings.as_slice() {
+            [directory, name, ..] => SourceFile::new(Some(directory), name),
+            [name] => SourceFile::new(None, name),
+            [] => continue,
+        };
+        files.insert(number, file);
+    }
+    files
+}
+
+/// The double-quoted strings in `text`, with `\"` and `\\` unescaped.
+fn quoted_strings(text: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut chars = text.chars();
+    while chars.any(|ch| ch == '"') {
+        let mut string = String::new();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '"' => break,
+                '\\' => string.extend(chars.next()),
+                ch => string.push(ch),
+            }
+        }
+        strings.push(string);
+    }
+    strings
+}
+
+/// Whether `text` mentions `name` as a whole word.
+fn mentions(text: &str, name: &str) -> bool {
+    text.match_indices(name).any(|(start, _)| {
+        !text[start + name.len()..].starts_with(|ch: char| ch.is_alphanumeric() || ch == '_')
+    })
+}
+
+/// Annotate and/or filter LLVM IR extracted from one of `modules`. Locations
+/// come from the `!dbg` attachment of each instruction, resolved through the
+/// metadata of the module that defines the function.
+pub fn annotate_llvm_ir<F: FnMut(&Path) -> Option<String>>(
+    block: &str,
+    modules: &[&str],
+    options: AnnotateOptions,
+    sources: &mut SourceLines<F>,
+) -> String {
+    let mut metadata = HashMap::new();
+    let mut annotator = Annotator {
+        comment: ";",
+        sources,
+        current: None,
+        output: Vec::new(),
+    };
+
+    for line in block.lines() {
+        if line.starts_with("define ") {
+            metadata = modules
+                .iter()
+                .find(|module| module.lines().any(|module_line| module_line == line))
+                .map(|module| metadata_table(module))
+                .unwrap_or_default();
+            annotator.current = None;
+        }
+
+        if options.source_lines
+            && let Some(id) = attachment(line, "dbg")
+            && let Some((file, source_line)) = resolve_location(&metadata, id)
+        {
+            annotator.annotate(&file, source_line);
+        }
+
+        if options.strip_directives {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("#dbg_") || trimmed.starts_with("call void @llvm.dbg.") {
+                continue;
+            }
+            annotator.output.push(strip_attachments(line));
+        } else {
+            annotator.output.push(line.to_string());
+        }
+    }
+
+    annotator.output.join("\n")
+}
+
+/// Numbered metadata nodes of a module, e.g. `12` to `!DILocation(line: 3, ...)`.
+fn metadata_table(module: &str) -> HashMap<u32, &str> {
+    module
+        .lines()
+        .filter_map(|line| {
+            let (id, node) = line.strip_prefix('!')?.split_once(" = ")?;
+            Some((id.parse().ok()?, node.trim_start_matches("distinct ")))
+        })
+        .collect()
+}
+
+/// The metadata id attached to an instruction as `!name !N`.
+fn attachment(line: &str, name: &str) -> Option<u32> {
+    let pattern = format!("!{name} !");
+    let start = line.find(&pattern)? + pattern.len();
+    let digits = line[start..]
+        .find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or(line.len() - start);
+    line[start..start + digits].parse().ok()
+}
+
+/// File and line of a `!DILocation`, following its scope to the `!DIFile`.
+fn resolve_location(metadata: &HashMap<u32, &str>, id: u32) -> Option<(SourceFile, u32)> {
+    let location = metadata.get(&id)?.strip_prefix("!DILocation(")?;
+    let line = field(location, "line")?.parse().ok()?;
+
+    let mut scope = metadata_ref(field(location, "scope")?)?;
+    // Lexical blocks without a file of their own defer to their parent scope.
+    for _ in 0..64 {
+        let node = metadata.get(&scope)?;
+        if let Some(file) = field(node, "file").and_then(metadata_ref) {
+            let file = metadata.get(&file)?.strip_prefix("!DIFile(")?;
+            let name = field(file, "filename")?;
+            return Some((SourceFile::new(field(file, "directory"), name), line));
+        }
+        scope = metadata_ref(field(node, "scope")?)?;
+    }
+    None
+}
+
+fn metadata_ref(value: &str) -> Option<u32> {
+    value.strip_prefix('!')?.parse().ok()
+}
+
+/// The value of `key: value` in a metadata node's field list, without quotes.
+fn field<'a>(node: &'a str, key: &str) -> Option<&'a str> {
+    let pattern = format!("{key}: ");
+    let (start, _) = node
+        .match_indices(&pattern)
+        .find(|(start, _)| *start == 0 || matches!(node.as_bytes()[start - 1], b'(' | b' '))?;
+    let value = &node[start + pattern.len()..];
+    if let Some(quoted) = value.strip_prefix('"') {
+        return quoted.split('"').next();
+    }
+    let end = value.find([',', ')']).unwrap_or(value.len());
+    Some(value[..end].trim())
+}
+
+/// Remove metadata attachments such as `, !dbg !12` and `!noalias !4`.
+fn strip_attachments(line: &str) -> String {
+    let mut stripped = line.to_string();
+    let mut from = 0;
+    while let Some(found) = stripped[from..].find(" !") {
+        let start = from + found;
+        let rest = &stripped[start + 2..];
+        let name_len = rest
+            .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_' || ch == '.'))
+            .unwrap_or(rest.len());
+        let value = &rest[name_len..];
+        let is_attachment = name_len > 0
+            && !rest.starts_with(|ch: char| ch.is_ascii_digit())
+            && value.starts_with(" !")
+            && value[2..].starts_with(|ch: char| ch.is_ascii_digit() || ch == '{');
+        if !is_attachment {
+            from = start + 2;
+            continue;
+        }
+
+        let value = &value[2..];
+        let value_len = if value.starts_with('{') {
+            value.find('}').map_or(value.len(), |end| end + 1)
+        } else {
+            value
+                .find(|ch: char| !ch.is_ascii_digit())
+                .unwrap_or(value.len())
+        };
+        let end = start + 2 + name_len + 2 + value_len;
+        let start = if stripped[..start].ends_with(',') {
+            start - 1
+        } else {
+            start
+        };
+        stripped.replace_range(start..end, "");
+        from = start;
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AnnotateOptions, SourceLines, annotate_asm, annotate_llvm_ir};
+    use std::path::Path;
+
+    const SOURCE: &str = "pub fn triple(x: u32) -> u32 {\n    let y = x * 3;\n    y + 1\n}\n";
+
+    fn sources() -> SourceLines<impl FnMut(&Path) -> Option<String>> {
+        SourceLines::new(|path: &Path| {
+            (path == Path::new("/ws/demo/src/lib.rs")).then(|| SOURCE.to_string())
+        })
+    }
+
+    #[test]
+    fn annotates_assembly_with_source_lines() {
+        let asm = "\
+\t.file\t\"demo.1a2b-cgu.0\"
+\t.section\t.text._ZN4demo6triple17h0123456789abcdefE,\"ax\",@progbits
+\t.globl\t_ZN4demo6triple17h0123456789abcdefE
+\t.p2align\t4
+_ZN4demo6triple17h0123456789abcdefE:
+.Lfunc_begin0:
+\t.file\t1 \"/ws/demo\" \"src/lib.rs\" md5 0x0123
+\t.loc\t1 1 0
+\t.cfi_startproc
+\t.loc\t1 2 13 prologue_end
+\tleal\t(%rdi,%rdi,2), %eax
+.Ltmp0:
+\t.loc\t1 3 5
+\tincl\t%eax
+\tretq
+.Ltmp1:
+.Lfunc_end0:
+\t.size\t_ZN4demo6triple17h0123456789abcdefE, .Lfunc_end0-_ZN4demo6triple17h0123456789abcdefE
+\t.cfi_endproc
+";
+        let block = asm
+            .lines()
+            .skip_while(|line| !line.starts_with("_ZN"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let options = AnnotateOptions {
+            source_lines: true,
+            strip_directives: true,
+        };
+
+        assert_eq!(
+            annotate_asm(&block, &[asm], "#", options, &mut sources()),
+            "\
+_ZN4demo6triple17h0123456789abcdefE:
+# src/lib.rs:1: pub fn triple(x: u32) -> u32 {
+# src/lib.rs:2: let y = x * 3;
+\tleal\t(%rdi,%rdi,2), %eax
+# src/lib.rs:3: y + 1
+\tincl\t%eax
+\tretq"
+        );
+    }
+
+    #[test]
+    fn annotates_llvm_ir_from_dbg_locations() {
+        let module = "\
+define i32 @_ZN4demo6triple17h0123456789abcdefE(i32 %x) unnamed_addr #0 !dbg !4 {
+start:
+    #dbg_value(i32 %x, !9, !DIExpression(), !10)
+  %y = mul i32 %x, 3, !dbg !11
+  %0 = add i32 %y, 1, !dbg !12
+  ret i32 %0, !dbg !13, !noalias !{}
+}
+
+!3 = !DIFile(filename: \"src/lib.rs\", directory: \"/ws/demo\")
+!4 = distinct !DISubprogram(name: \"triple\", scope: !5, file: !3, line: 1, scopeLine: 1, unit: !6)
+!7 = distinct !DILexicalBlock(scope: !4, line: 2, column: 5)
+!11 = !DILocation(line: 2, column: 13, scope: !7)
+!12 = !DILocation(line: 3, column: 5, scope: !7)
+!13 = !DILocation(line: 0, scope: !4)
+";
+        let block = module.split("\n\n").next().unwrap();
+        let options = AnnotateOptions {
+            source_lines: true,
+            strip_directives: true,
+        };
+
+        assert_eq!(
+            annotate_llvm_ir(block, &[module], options, &mut sources()),
+            "\
+define i32 @_ZN4demo6triple17h0123456789abcdefE(i32 %x) unnamed_addr #0 {
+start:
+; src/lib.rs:2: let y = x * 3;
+  %y = mul i32 %x, 3
+; src/lib.rs:3: y + 1
+  %0 = add i32 %y, 1
+  ret i32 %0
+}"
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    process::ExitStatus,
+    time::UNIX_EPOCH,
+};
+use tokio::fs;
+
+use super::{RunRequest, RunResult};
+
+/// Compiler output of earlier inspection runs, kept under the inspection
+/// target dir so that queries against an unchanged build skip `cargo rustc`.
+///
+/// Entries are keyed by a fingerprint of the workspace sources, the
+/// [`RunRequest`] and the toolchain. Each entry holds the run's output and a
+/// copy of its artifacts, laid out as they were in the target dir, since later
+/// builds overwrite the originals in place.
+#[derive(Debug, Clone)]
+pub struct ArtifactCache {
+    root: PathBuf,
+}
+
+/// What an entry records besides the artifact files.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedRun {
+    command: Vec<String>,
+    stdout: String,
+    stderr: String,
+    /// Artifact paths relative to the entry directory.
+    artifacts: Vec<PathBuf>,
+}
+
+impl ArtifactCache {
+    /// Directory name of the cache inside the inspection target dir.
+    pub const DIR_NAME: &'static str = "inspection-cache";
+    const RUN_FILE: &'static str = "run.json";
+    /// Entries kept after a store; the least recently stored go first.
+    const MAX_ENTRIES: usize = 32;
+
+    pub fn new(target_dir: &Path) -> Self {
+        Self {
+            root: target_dir.join(Self::DIR_NAME),
+        }
+    }
+
+    /// Key for running `request` against the current state of the workspace
+    /// with the given toolchain (`rustc -vV`).
+    pub async fn key(
+        workspace_root: &Path,
+        target_dir: &Path,
+        request: &RunRequest,
+        toolchain: Option<&str>,
+    ) -> Result<String> {
+        let mut hasher = StableHasher::default();
+        hash_sources(workspace_root, target_dir, &mut hasher).await?;
+        request.hash(&mut hasher);
+        toolchain.hash(&mut hasher);
+        Ok(format!("{:016x}", hasher.finish()))
+    }
+
+    /// The cached run for `key`, with artifact paths inside the entry.
+    pub async fn load(&self, key: &str) -> Result<Option<RunResult>> {
+        let entry = self.root.join(key);
+        let text = match fs::read_to_string(entry.join(Self::RUN_FILE)).await {
+            Ok(text) => text,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error).context("reading cached inspection run"),
+        };
+        let run: CachedRun =
+            serde_json::from_str(&text).context("parsing cached inspection run")?;
+
+        let artifacts: Vec<PathBuf> = run
+            .artifacts
+            .iter()
+            .map(|artifact| entry.join(artifact))
+            .collect();
+        for artifact in &artifacts {
+            if fs::metadata(artifact).await.is_err() {
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(RunResult {
+            // Only successful runs are stored.
+            status: ExitStatus::default(),
+            stdout: run.stdout,
+            stderr: run.stderr,
+            artifacts,
+            artifact_dir: entry,
+            command: run.command,
+        }))
+    }
+
+    /// Store a successful run under `key`, copying its artifacts.
+    pub async fn store(&self, key: &str, result: &RunResult) -> Result<()> {
+        let entry = self.root.join(key);
+        let mut artifacts = Vec::new();
+        for artifact in &result.artifacts {
+            let Ok(relative) = artifact.strip_prefix(&result.artifact_dir) else {
+                continue;
+            };
+            // Cargo's own bookkeeping (`.fingerprint`, `.cargo-lock`) is not output.
+            let is_bookkeeping = relative
+                .components()
+                .any(|component| component.as_os_str().to_string_lossy().starts_with('.'));
+            if is_bookkeeping {
+                continue;
+            }
+            let destination = entry.join(relative);
+            if let Some(parent) = destination.parent() {
+                fs::create_dir_all(parent)
+                    .await
+                    .with_context(|| format!("creating {}", parent.display()))?;
+            }
+            fs::copy(artifact, &destination)
+                .await
+                .with_context(|| format!("caching {}", artifact.display()))?;
+            artifacts.push(relative.to_path_buf());
+        }
+
+        let run = CachedRun {
+            command: result.command.clone(),
+            stdout: result.stdout.clone(),
+            stderr: result.stderr.clone(),
+            artifacts,
+        };
+        fs::create_dir_all(&entry)
+            .await
+            .with_context(|| format!("creating {}", entry.display()))?;
+        // Written last, so an entry is only visible once its artifacts are.
+        fs::write(entry.join(Self::RUN_FILE), serde_json::to_string(&run)?)
+            .await
+            .context("writing cached inspection run")?;
+        self.prune(Self::MAX_ENTRIES).await
+    }
+
+    /// Remove all but the `keep` most recently stored entries. Entries left
+    /// incomplete by an interrupted store are the first to go.
+    async fn prune(&self, keep: usize) -> Result<()> {
+        let mut entries = Vec::new();
+        let mut dir = fs::read_dir(&self.root)
+            .await
+            .with_context(|| format!("reading {}", self.root.display()))?;
+        while let Some(entry) = dir.next_entry().await? {
+            let stored = fs::metadata(entry.path().join(Self::RUN_FILE))
+                .await
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            entries.push((stored, entry.path()));
+        }
+
+        // Newest first; incomplete entries sort last.
+        entries.sort_by_key(|(stored, _)| std::cmp::Reverse(*stored));
+        for (_, path) in entries.into_iter().skip(keep) {
+            fs::remove_dir_all(&path)
+                .await
+                .with_context(|| format!("removing {}", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// 64-bit FNV-1a. Keys are written to disk, so unlike `DefaultHasher` the
+/// output must not change between Rust releases or platforms.
+struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    // Integers in a fixed byte order and width, so keys match across
+    // platforms; the signed variants forward to these.
+    fn write_u16(&mut self, value: u16) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, value: u128) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Hash the path, size and modification time of every file in the
+/// workspace, skipping hidden directories and build output.
+async fn hash_sources(root: &Path, target_dir: &Path, hasher: &mut impl Hasher) -> Result<()> {
+    let target_dir = std::path::absolute(root.join(target_dir)).ok();
+    let mut files = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        let mut entries = fs::read_dir(&dir)
+            .await
+            .with_context(|| format!("reading {}", dir.display()))?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let name = entry.file_name();
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                let skipped = name.to_string_lossy().starts_with('.')
+                    || name == "target"
+                    || std::path::absolute(&path).ok() == target_dir;
+                if !skipped {
+                    stack.push(path);
+                }
+            } else if file_type.is_file() {
+                let metadata = entry.metadata().await?;
+                let modified = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .unwrap_or_default();
+                files.push((path, metadata.len(), modified));
+            }
+        }
+    }
+
+    files.sort();
+    files.hash(hasher);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StableHasher;
+    use std::hash::{Hash, Hasher};
+
+    #[test]
+    fn hashes_with_fnv1a() {
+        let mut hasher = StableHasher::default();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+
+        let mut hasher = StableHasher::default();
+        (1u32, "src/lib.rs").hash(&mut hasher);
+        let mut same = StableHasher::default();
+        (1u32, "src/lib.rs").hash(&mut same);
+        assert_eq!(hasher.finish(), same.finish());
+    }
+}
@@ -0,0 +1,118 @@
+//! Demangling of Rust symbol names in both the legacy (`_ZN...E`) and the v0
+//! (`_R...`, `-Csymbol-mangling-version=v0`) schemes.
+//!
+//! Output is `rustc-demangle`'s alternate format: legacy names lose their
+//! `::h<hash>` suffix, v0 names omit crate disambiguators, and LLVM's
+//! `.llvm.<n>` suffixes are dropped.
+
+/// Demangle a Rust symbol, or return `None` when it is not one.
+pub fn demangle(symbol: &str) -> Option<String> {
+    let symbol = symbol.trim_matches('"');
+    rustc_demangle::try_demangle(symbol)
+        .ok()
+        .map(|demangled| format!("{demangled:#}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::demangle;
+
+    #[test]
+    fn demangles_legacy_and_v0_symbols() {
+        let cases = [
+            // Legacy paths, with `$LT$`/`$u20$` escapes and `..` separators.
+            ("_ZN4demo3run17h1f756ea7b9be71ceE", "demo::run"),
+            (
+                "_ZN58_$LT$demo..shapes..Point$u20$as$u20$core..fmt..Display$GT$3fmt17h5c6503e38a669438E",
+                "<demo::shapes::Point as core::fmt::Display>::fmt",
+            ),
+            (
+                "_ZN4demo6shapes16Wrapper$LT$T$GT$3get17hce23e66382b4d342E",
+                "demo::shapes::Wrapper<T>::get",
+            ),
+            (
+                "_ZN4demo3run28_$u7b$$u7b$closure$u7d$$u7d$17h0fd9b871fa81e09dE",
+                "demo::run::{{closure}}",
+            ),
+            (
+                "_ZN4core3ops8function6FnOnce40call_once$u7b$$u7b$vtable.shim$u7d$$u7d$17hdf4ba9e7484b7ceeE",
+                "core::ops::function::FnOnce::call_once{{vtable.shim}}",
+            ),
+            (
+                "_ZN4demo12_$ufc$nicode17he166a6c56cda0895E",
+                "demo::ünicode",
+            ),
+            // LLVM appends `.llvm.<n>` to symbols it internalizes.
+            (
+                "_ZN4demo3run17h1f756ea7b9be71ceE.llvm.7383409263617829301",
+                "demo::run",
+            ),
+            (
+                "_RNvCsauMP1AnkCw5_4demo3run.llvm.7383409263617829301",
+                "demo::run",
+            ),
+            // v0 paths and closures.
+            ("_RNvCsauMP1AnkCw5_4demo3run", "demo::run"),
+            (
+                "_RNCNvCsauMP1AnkCw5_4demo3run0B3_",
+                "demo::run::{closure#0}",
+            ),
+            // `<T as Trait>::method`, with backrefs into the path after `_R`.
+            (
+                "_RNvXCsauMP1AnkCw5_4demoNtB2_5PointNtNtCsgEmfK2I1SDS_4core3fmt7Display3fmt",
+                "<demo::Point as core::fmt::Display>::fmt",
+            ),
+            (
+                "_RNvXs_NtCsauMP1AnkCw5_4demo6shapesNtB4_5PointNtNtCsgEmfK2I1SDS_4core3fmt7Display3fmt",
+                "<demo::shapes::Point as core::fmt::Display>::fmt",
+            ),
+            (
+                "_RNvMs0_NtCsauMP1AnkCw5_4demo6shapesINtB5_7WrapperhE3getB7_",
+                "<demo::shapes::Wrapper<u8>>::get",
+            ),
+            (
+                "_RINvNvMs2_NtCslNYArtu3iFV_5alloc7raw_vecINtB8_11RawVecInnerpE7reserve21do_reserve_and_handleNtNtBa_5alloc6GlobalECsauMP1AnkCw5_4demo",
+                "<alloc::raw_vec::RawVecInner<_>>::reserve::do_reserve_and_handle::<alloc::alloc::Global>",
+            ),
+            // Generic instances and const generics.
+            (
+                "_RINvCsauMP1AnkCw5_4demo2idThcEEB2_",
+                "demo::id::<(u8, char)>",
+            ),
+            (
+                "_RNvMs_CsauMP1AnkCw5_4demoINtB4_3ArrKj3_E3lenB4_",
+                "<demo::Arr<3>>::len",
+            ),
+            (
+                "_RINvMs2_NtCsgEmfK2I1SDS_4core3fmtNtB6_9Arguments3newKj2_Kj1_ECsauMP1AnkCw5_4demo",
+                "<core::fmt::Arguments>::new::<2, 1>",
+            ),
+            // Higher-ranked `for<'a>` binders.
+            (
+                "_RINvCsauMP1AnkCw5_4demo2idFG_RL0_hERL0_hEB2_",
+                "demo::id::<for<'a> fn(&'a u8) -> &'a u8>",
+            ),
+            // Shims.
+            (
+                "_RNSNvYNCNvCsauMP1AnkCw5_4demo3run0INtNtNtCsgEmfK2I1SDS_4core3ops8function6FnOnceThEE9call_once6vtableB8_",
+                "<demo::run::{closure#0} as core::ops::function::FnOnce<(u8,)>>::call_once::{shim:vtable#0}",
+            ),
+            (
+                "_RNvYNvYeNtNtCslNYArtu3iFV_5alloc6borrow7ToOwned8to_ownedINtNtNtCsgEmfK2I1SDS_4core3ops8function6FnOnceTReEE9call_once",
+                "<<str as alloc::borrow::ToOwned>::to_owned as core::ops::function::FnOnce<(&str,)>>::call_once",
+            ),
+            // Punycode identifiers.
+            ("_RNvCsauMP1AnkCw5_4demou10nicode_2ya", "demo::ünicode"),
+        ];
+        for (mangled, expected) in cases {
+            assert_eq!(demangle(mangled).as_deref(), Some(expected), "{mangled}");
+        }
+
+        assert_eq!(
+            demangle("\"_RNvCsauMP1AnkCw5_4demo3run\"").as_deref(),
+            Some("demo::run")
+        );
+        assert_eq!(demangle("main"), None);
+        assert_eq!(demangle("_RNvC"), None);
+    }
+}
@@ -0,0 +1,364 @@
+//! Unified diffs of inspection output, with the noise that changes between
+//! otherwise identical compilations normalized away.
+
+use std::collections::HashMap;
+
+/// Prefixes of assembly, LLVM IR and MIR labels that are renumbered.
+const LABEL_PREFIXES: &[&str] = &[
+    ".Lfunc_begin",
+    ".Lfunc_end",
+    ".LBB",
+    ".Ltmp",
+    ".LCPI",
+    ".LJTI",
+    "bb.",
+    "bb",
+    "alloc",
+];
+
+/// Normalize text that differs between compilations without a change in
+/// codegen: symbol hashes (`17h...E`, v0 crate disambiguators, `anon.<hash>`)
+/// are masked, and labels, metadata ids (`!12`) and attribute groups (`#3`)
+/// are renumbered in order of first appearance.
+pub fn normalize_codegen(text: &str) -> String {
+    let mut numbering = Renumbering::default();
+    let mut normalized = String::with_capacity(text.len());
+
+    for line in text.lines() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut index = 0;
+        while index < chars.len() {
+            let ch = chars[index];
+            if is_word_char(ch) {
+                let start = index;
+                while index < chars.len() && is_word_char(chars[index]) {
+                    index += 1;
+                }
+                let word: String = chars[start..index].iter().collect();
+                normalized.push_str(&numbering.label(&mask_hashes(&word)));
+                continue;
+            }
+
+            let numbered = (ch == '!' || (ch == '#' && index > 0 && chars[index - 1] == ' '))
+                && chars.get(index + 1).is_some_and(char::is_ascii_digit);
+            if numbered {
+                let start = index + 1;
+                index = start;
+                while index < chars.len() && chars[index].is_ascii_digit() {
+                    index += 1;
+                }
+                let id: String = chars[start..index].iter().collect();
+                let kind = if ch == '!' { "!" } else { "#" };
+                normalized.push(ch);
+                normalized.push_str(&numbering.next(kind, &id).to_string());
+                continue;
+            }
+
+            normalized.push(ch);
+            index += 1;
+        }
+        normalized.truncate(normalized.trim_end().len());
+        normalized.push('\n');
+    }
+
+    normalized
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || matches!(ch, '_' | '.' | '$')
+}
+
+#[derive(Default)]
+struct Renumbering {
+    ids: HashMap<(String, String), usize>,
+    counts: HashMap<String, usize>,
+}
+
+impl Renumbering {
+    fn next(&mut self, kind: &str, id: &str) -> usize {
+        let key = (kind.to_string(), id.to_string());
+        if let Some(number) = self.ids.get(&key) {
+            return *number;
+        }
+        let count = self.counts.entry(kind.to_string()).or_default();
+        let number = *count;
+        *count += 1;
+        self.ids.insert(key, number);
+        number
+    }
+
+    /// `.LBB3_7` becomes `.LBB_0` for the first label seen, and so on.
+    fn label(&mut self, word: &str) -> String {
+        for prefix in LABEL_PREFIXES {
+            let Some(id) = word.strip_prefix(prefix) else {
+                continue;
+            };
+            let is_id = id.starts_with(|ch: char| ch.is_ascii_digit())
+                && id.chars().all(|ch| ch.is_ascii_digit() || ch == '_');
+            if is_id {
+                let separator = if id.contains('_') { "_" } else { "" };
+                return format!("{prefix}{separator}{}", self.next(prefix, id));
+            }
+        }
+        word.to_string()
+    }
+}
+
+/// Mask legacy symbol hashes, v0 crate disambiguators and the hash in
+/// `anon.<hash>.N` constants.
+fn mask_hashes(word: &str) -> String {
+    let mut masked = word.to_string();
+
+    let mut from = 0;
+    while let Some(found) = masked[from..].find("17h") {
+        let start = from + found + 3;
+        let hash = masked.get(start..start + 16);
+        if hash.is_some_and(|hash| hash.chars().all(|ch| ch.is_ascii_hexdigit()))
+            && masked[start + 16..].starts_with('E')
+        {
+            masked.replace_range(start..start + 16, "<hash>");
+        }
+        from = start;
+    }
+
+    if masked.starts_with("_R") {
+        let mut from = 0;
+        while let Some(found) = masked[from..].find("Cs") {
+            let start = from + found + 2;
+            let len = masked[start..]
+                .find(|ch: char| !ch.is_ascii_alphanumeric())
+                .unwrap_or(masked.len() - start);
+            if masked[start + len..].starts_with('_') {
+                masked.replace_range(start..start + len, "");
+            }
+            from = start;
+        }
+    }
+
+    if let Some(found) = masked.find("anon.") {
+        let start = found + 5;
+        let len = masked[start..]
+            .find(|ch: char| !ch.is_ascii_hexdigit())
+            .unwrap_or(masked.len() - start);
+        if len >= 16 {
+            masked.replace_range(start..start + len, "<hash>");
+        }
+    }
+
+    masked
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Render a unified diff of `before` and `after` with `context` lines
+/// around each change, or an empty string when they are equal.
+pub fn unified_diff(
+    before: &str,
+    after: &str,
+    before_label: &str,
+    after_label: &str,
+    context: usize,
+) -> String {
+    let old: Vec<&str> = before.lines().collect();
+    let new: Vec<&str> = after.lines().collect();
+    let edits = diff_lines(&old, &new);
+    if edits.iter().all(|edit| matches!(edit, Edit::Equal(..))) {
+        return String::new();
+    }
+
+    let mut output = format!("--- {before_label}\n+++ {after_label}\n");
+    let changed: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, edit)| !matches!(edit, Edit::Equal(..)))
+        .map(|(index, _)| index)
+        .collect();
+
+    // Group changes whose context windows touch into one hunk.
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for index in changed {
+        let start = index.saturating_sub(context);
+        let end = (index + context + 1).min(edits.len());
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    for (start, end) in hunks {
+        let hunk = &edits[start..end];
+        let (old_start, new_start) = edit_position(&edits, start);
+        let old_len = hunk
+            .iter()
+            .filter(|edit| !matches!(edit, Edit::Insert(_)))
+            .count();
+        let new_len = hunk
+            .iter()
+            .filter(|edit| !matches!(edit, Edit::Delete(_)))
+            .count();
+        output.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_len),
+            hunk_range(new_start, new_len)
+        ));
+        for edit in hunk {
+            let line = match *edit {
+                Edit::Equal(old_index, _) => format!(" {}", old[old_index]),
+                Edit::Delete(old_index) => format!("-{}", old[old_index]),
+                Edit::Insert(new_index) => format!("+{}", new[new_index]),
+            };
+            output.push_str(&line);
+            output.push('\n');
+        }
+    }
+
+    output
+}
+
+/// Line numbers (0-based) in the old and new text where edit `index` starts.
+fn edit_position(edits: &[Edit], index: usize) -> (usize, usize) {
+    edits[..index]
+        .iter()
+        .fold((0, 0), |(old, new), edit| match edit {
+            Edit::Equal(..) => (old + 1, new + 1),
+            Edit::Delete(_) => (old + 1, new),
+            Edit::Insert(_) => (old, new + 1),
+        })
+}
+
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{len}", start + 1),
+    }
+}
+
+/// Shortest edit script between two sequences of lines (Myers' algorithm).
+/// Only the live diagonals of each round are kept for the backtrack, so
+/// memory grows with the square of the edit distance rather than the input.
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = n + m;
+    let offset = max + 1;
+    let mut frontier = vec![0isize; 2 * max as usize + 3];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    'search: for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let index = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && frontier[index - 1] < frontier[index + 1]) {
+                frontier[index + 1]
+            } else {
+                frontier[index - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            frontier[index] = x;
+            if x >= n && y >= m {
+                trace.push(frontier[(offset - d) as usize..=(offset + d) as usize].to_vec());
+                break 'search;
+            }
+        }
+        trace.push(frontier[(offset - d) as usize..=(offset + d) as usize].to_vec());
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, round) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let (prev_k, prev_x) = if d == 0 {
+            (0, 0)
+        } else {
+            let previous = &trace[d as usize - 1];
+            let at = |k: isize| previous[(k + d - 1) as usize];
+            let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+                k + 1
+            } else {
+                k - 1
+            };
+            (prev_k, at(prev_k))
+        };
+        let prev_y = prev_x - prev_k;
+        debug_assert_eq!(round[(k + d) as usize], x);
+
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            edits.push(Edit::Equal(x as usize, y as usize));
+        }
+        if d > 0 {
+            if x == prev_x {
+                edits.push(Edit::Insert(prev_y as usize));
+            } else {
+                edits.push(Edit::Delete(prev_x as usize));
+            }
+            x = prev_x;
+            y = prev_y;
+        }
+    }
+
+    edits.reverse();
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_codegen, unified_diff};
+
+    #[test]
+    fn normalizes_noise_and_diffs_codegen() {
+        let unoptimized = "\
+define i32 @_ZN4demo6triple17h0123456789abcdefE(i32 %x) unnamed_addr #0 !dbg !12 {
+start:
+  %0 = mul i32 %x, 3
+  br label %bb1, !dbg !20
+bb1:
+  ret i32 %0, !dbg !21
+}
+";
+        let optimized = "\
+define i32 @_ZN4demo6triple17hfedcba9876543210E(i32 %x) unnamed_addr #4 !dbg !7 {
+start:
+  %0 = mul i32 %x, 3
+  ret i32 %0, !dbg !9
+}
+";
+        let before = normalize_codegen(unoptimized);
+        let after = normalize_codegen(optimized);
+        assert!(before.starts_with(
+            "define i32 @_ZN4demo6triple17h<hash>E(i32 %x) unnamed_addr #0 !dbg !0 {"
+        ));
+        assert_eq!(
+            normalize_codegen(
+                "call void @_RNvCsauMP1AnkCw5_4demo3run() @anon.0123456789abcdef0123456789abcdef.3 .LBB7_2"
+            ),
+            "call void @_RNvCs_4demo3run() @anon.<hash>.3 .LBB_0\n"
+        );
+
+        assert_eq!(
+            unified_diff(&before, &after, "opt-level=0", "opt-level=3", 1),
+            "\
+--- opt-level=0
++++ opt-level=3
+@@ -3,5 +3,3 @@
+   %0 = mul i32 %x, 3
+-  br label %bb0, !dbg !1
+-bb0:
+-  ret i32 %0, !dbg !2
++  ret i32 %0, !dbg !1
+ }
+"
+        );
+        assert_eq!(unified_diff(&before, &before, "a", "b", 3), "");
+    }
+}
@@ -1,40 +1,45 @@
 use crate::analyzer::symbol::SymbolIdentity;
+use crate::analyzer::syntax::{impl_self_type, strip_visibility};
+use crate::compiler::demangle::demangle;
 use anyhow::{Result, bail};
+use std::fmt;
 
 /// Normalized representation of a symbol used when matching compiler artifacts.
 ///
 /// The `def_name` follows Rust path semantics (e.g. `crate::module::item`).
-/// `mangled` can be provided when the fully qualified mangled name is known,
-/// otherwise the extractor will fall back to a best-effort prefix derived from
-/// the path segments.
+/// `mangled` can be provided when the fully qualified mangled name is known;
+/// otherwise compiled symbols are matched by their demangled path, in either
+/// the legacy or the v0 mangling scheme.
 #[derive(Debug, Clone)]
 pub struct NormalizedSymbol {
     pub def_name: String,
     pub item_name: String,
     pub mangled: Option<String>,
     pub target: Option<String>,
-    mangled_prefix: String,
+    /// Generic instance to select, matched against the type arguments of
+    /// demangled symbols, or `all` for every instance.
+    pub instance: Option<String>,
+    path: QualifiedPath,
 }
 
 impl NormalizedSymbol {
     /// Build a normalized symbol from an existing [`SymbolIdentity`].
     ///
-    /// The def-name is assembled as `crate::module::item`, and a mangling
-    /// prefix is derived from the same segments.
+    /// The def-name is assembled as `crate::module::item`.
     pub fn from_identity(identity: &SymbolIdentity) -> Self {
         let mut segments = vec![identity.crate_name.clone()];
         segments.extend(identity.module_path.clone());
         segments.push(identity.item_name.clone());
 
         let def_name = segments.join("::");
-        let mangled_prefix = encode_rust_mangled_prefix(&segments);
 
         Self {
             def_name,
             item_name: identity.item_name.clone(),
             mangled: None,
             target: None,
-            mangled_prefix,
+            instance: None,
+            path: QualifiedPath::from_identity(identity),
         }
     }
 
@@ -50,11 +55,275 @@ impl NormalizedSymbol {
         self
     }
 
-    fn mangled_prefix(&self) -> &str {
-        &self.mangled_prefix
+    /// Select one generic instance (e.g. `Vec<u8>`) or `all` of them.
+    pub fn with_instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Whether a demangled symbol names this item. Crate names are ignored
+    /// since the analyzer's guess may differ from the compiled crate's, and
+    /// trait impls match on the last segments of their self type and trait.
+    fn matches_demangled(&self, demangled: &str) -> bool {
+        let Some(found) = QualifiedPath::parse(demangled) else {
+            return false;
+        };
+        let wanted = &self.path;
+        if found.name != wanted.name {
+            return false;
+        }
+
+        let below_crate = |path: &[String]| path.get(1..).unwrap_or_default().to_vec();
+        let same_tail = |found: &[String], wanted: &[String]| {
+            found == wanted
+                || (!found.is_empty() && wanted.ends_with(found))
+                || (!wanted.is_empty() && found.ends_with(wanted))
+        };
+        match (&wanted.trait_path, &found.trait_path) {
+            (Some(wanted_trait), Some(found_trait)) => {
+                wanted_trait.last() == found_trait.last()
+                    && wanted.self_path.last() == found.self_path.last()
+            }
+            (Some(_), None) => false,
+            // Default trait methods sit below the trait's path.
+            (None, Some(found_trait)) => {
+                same_tail(&below_crate(found_trait), &below_crate(&wanted.self_path))
+            }
+            (None, None) => same_tail(
+                &below_crate(&found.self_path),
+                &below_crate(&wanted.self_path),
+            ),
+        }
+    }
+}
+
+/// A path split into the parts used to match demangled symbols: the self
+/// type (or module path of a free item), the trait of a `<Type as
+/// Trait>::item` path, and the item name. Generic arguments are dropped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct QualifiedPath {
+    self_path: Vec<String>,
+    trait_path: Option<Vec<String>>,
+    name: String,
+}
+
+impl QualifiedPath {
+    fn parse(path: &str) -> Option<Self> {
+        let segments = split_path(path);
+        let (first, rest) = segments.split_first()?;
+
+        if let Some(inner) = first.strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+            let (self_part, trait_part) = match split_top_level(inner, " as ").as_slice() {
+                [self_part] => (*self_part, None),
+                [self_part, trait_part] => (*self_part, Some(*trait_part)),
+                _ => return None,
+            };
+            let name = rest.iter().find(|segment| !segment.starts_with('<'))?;
+            return Some(Self {
+                self_path: path_segments(self_part),
+                trait_path: trait_part.map(path_segments),
+                name: strip_generics(name),
+            });
+        }
+
+        let mut segments = path_segments(path);
+        let name = segments.pop()?;
+        Some(Self {
+            self_path: segments,
+            trait_path: None,
+            name,
+        })
+    }
+
+    /// The path rustc would give an item: crate, modules, then any impl the
+    /// item sits in. `item_name` may itself be a path such as
+    /// `<Point as Display>::fmt`.
+    fn from_identity(identity: &SymbolIdentity) -> Self {
+        let mut self_path = vec![identity.crate_name.clone()];
+        let mut trait_path = None;
+        // Paths derived from the crate root file start with `lib` or `main`.
+        let modules = match identity.module_path.split_first() {
+            Some((root, rest)) if root == "lib" || root == "main" => rest,
+            _ => identity.module_path.as_slice(),
+        };
+        for segment in modules {
+            let is_impl = segment.starts_with("impl ") || segment.starts_with("impl<");
+            match impl_signature(segment).filter(|_| is_impl) {
+                Some((self_type, trait_name)) => {
+                    self_path.push(self_type);
+                    trait_path = trait_name.map(|name| vec![name]);
+                }
+                None => self_path.push(segment.clone()),
+            }
+        }
+
+        match Self::parse(&identity.item_name) {
+            Some(item) if item.trait_path.is_some() => item,
+            Some(item) => {
+                self_path.extend(item.self_path);
+                Self {
+                    self_path,
+                    trait_path,
+                    name: item.name,
+                }
+            }
+            None => Self {
+                self_path,
+                trait_path,
+                name: identity.item_name.clone(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for QualifiedPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.trait_path {
+            Some(trait_path) => write!(
+                f,
+                "<{} as {}>::{}",
+                self.self_path.join("::"),
+                trait_path.join("::"),
+                self.name
+            ),
+            None if self.self_path.is_empty() => f.write_str(&self.name),
+            None => write!(f, "{}::{}", self.self_path.join("::"), self.name),
+        }
     }
 }
 
+/// Path segments without generic arguments, dropping turbofish segments.
+fn path_segments(path: &str) -> Vec<String> {
+    split_path(path)
+        .into_iter()
+        .enumerate()
+        .filter(|(index, segment)| *index == 0 || !segment.starts_with('<'))
+        .map(|(_, segment)| strip_generics(segment))
+        .collect()
+}
+
+fn split_path(path: &str) -> Vec<&str> {
+    split_top_level(path.trim(), "::")
+}
+
+/// Split on `separator` outside of any brackets.
+fn split_top_level<'a>(text: &'a str, separator: &str) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut previous = '\0';
+    for (index, ch) in text.char_indices() {
+        match ch {
+            '<' | '(' | '[' | '{' => depth += 1,
+            // `->` in fn pointer types is not a bracket.
+            '>' if previous == '-' => {}
+            '>' | ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ if depth == 0 && index >= start && text[index..].starts_with(separator) => {
+                parts.push(&text[start..index]);
+                start = index + separator.len();
+            }
+            _ => {}
+        }
+        previous = ch;
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+pub(super) fn strip_generics(segment: &str) -> String {
+    let end = if segment.starts_with('<') {
+        segment.len()
+    } else {
+        segment.find(['<', '[']).unwrap_or(segment.len())
+    };
+    segment[..end].trim().to_string()
+}
+
+/// Generic argument lists of a demangled path's self type and item, e.g.
+/// `["u8", "u32"]` for `<demo::Wrapper<u8>>::get::<u32>`.
+fn instance_arguments(demangled: &str) -> Vec<String> {
+    let mut lists = Vec::new();
+    for (index, segment) in split_path(demangled).into_iter().enumerate() {
+        let inner = segment.strip_prefix('<').and_then(|s| s.strip_suffix('>'));
+        match inner {
+            Some(qualified) if index == 0 => {
+                let self_type = split_top_level(qualified, " as ")[0];
+                lists.extend(instance_arguments(self_type));
+            }
+            Some(turbofish) => lists.push(turbofish.trim().to_string()),
+            None => {
+                if let Some(open) = segment.find('<')
+                    && let Some(arguments) = segment[open + 1..].strip_suffix('>')
+                {
+                    lists.push(arguments.trim().to_string());
+                }
+            }
+        }
+    }
+    lists
+}
+
+/// Type arguments without whitespace, leading paths or lifetimes, so that
+/// `Vec<u8>` matches `alloc::vec::Vec<u8>` and `fn(&u8)` matches
+/// `for<'a> fn(&'a u8)`.
+fn normalize_instance(arguments: &str) -> String {
+    let mut normalized = String::new();
+    let mut ident = String::new();
+    let arguments = strip_lifetimes(arguments);
+    let mut chars = arguments
+        .chars()
+        .filter(|ch| !ch.is_whitespace())
+        .peekable();
+    while let Some(ch) = chars.next() {
+        if ch == '_' || ch.is_alphanumeric() {
+            ident.push(ch);
+            continue;
+        }
+        if ch == ':' && chars.peek() == Some(&':') && !ident.is_empty() {
+            chars.next();
+            ident.clear();
+            continue;
+        }
+        normalized.push_str(&ident);
+        ident.clear();
+        normalized.push(ch);
+    }
+    normalized.push_str(&ident);
+    normalized
+}
+
+/// Drop `for<...>` binders and the lifetimes in `text`, keeping `'c'` char
+/// constants.
+fn strip_lifetimes(text: &str) -> String {
+    let mut output = String::new();
+    let mut rest = text;
+    while let Some(ch) = rest.chars().next() {
+        let follows_ident = is_ident_char(output.chars().next_back());
+        if !follows_ident && let Some(binder) = rest.strip_prefix("for<") {
+            let end = binder.find('>').map_or(binder.len(), |end| end + 1);
+            rest = binder[end..].trim_start();
+            continue;
+        }
+        if ch == '\'' {
+            let len = rest[1..]
+                .find(|ch: char| !is_ident_char(Some(ch)))
+                .map_or(rest.len() - 1, |len| len);
+            let after = &rest[1 + len..];
+            if let Some(after_char) = after.strip_prefix('\'') {
+                output.push_str(&rest[..len + 2]);
+                rest = after_char;
+            } else {
+                rest = after.trim_start();
+                rest = rest.strip_prefix(',').map_or(rest, str::trim_start);
+            }
+            continue;
+        }
+        output.push(ch);
+        rest = &rest[ch.len_utf8()..];
+    }
+    output.replace("<>", "")
+}
+
 /// Extract MIR for a symbol using the def-name if available, otherwise falling
 /// back to the item name.
 pub fn extract_mir(mir_outputs: &[String], symbol: &NormalizedSymbol) -> Result<String> {
@@ -68,11 +337,13 @@ pub fn extract_mir(mir_outputs: &[String], symbol: &NormalizedSymbol) -> Result<
                 def_matches.push(Candidate {
                     header,
                     content: block.clone(),
+                    demangled: None,
                 });
             } else if block.contains(&symbol.item_name) {
                 name_matches.push(Candidate {
                     header,
                     content: block,
+                    demangled: None,
                 });
             }
         }
@@ -87,95 +358,42 @@ pub fn extract_mir(mir_outputs: &[String], symbol: &NormalizedSymbol) -> Result<
     select_unique_match(matches, "MIR", symbol)
 }
 
-/// Extract LLVM IR for a symbol, preferring an exact mangled match and falling
-/// back to mangled prefix or def-name in comments.
+/// Extract LLVM IR for a symbol, preferring an exact mangled match, then a
+/// match on the demangled path, then the def-name in comments.
 pub fn extract_llvm_ir(llvm_outputs: &[String], symbol: &NormalizedSymbol) -> Result<String> {
-    let mut exact_matches = Vec::new();
-    let mut prefix_matches = Vec::new();
-    let mut def_name_matches = Vec::new();
+    let mut matches = StagedMatches::default();
 
     for output in llvm_outputs {
         for (name, block) in split_llvm_blocks(output) {
-            let header = name.clone();
-            if let Some(mangled) = &symbol.mangled {
-                if name.contains(mangled) {
-                    exact_matches.push(Candidate {
-                        header,
-                        content: block.clone(),
-                    });
-                    continue;
-                }
-            }
-
-            if !symbol.mangled_prefix().is_empty() && name.contains(symbol.mangled_prefix()) {
-                prefix_matches.push(Candidate {
-                    header,
-                    content: block.clone(),
-                });
-                continue;
-            }
-
-            if block.contains(&symbol.def_name) {
-                def_name_matches.push(Candidate {
-                    header,
-                    content: block,
-                });
-            }
+            let candidate = Candidate::compiled(&name, block, ";");
+            matches.add(symbol, &name, candidate, |block| {
+                block.contains(&symbol.def_name)
+            });
         }
     }
 
-    if !exact_matches.is_empty() {
-        return select_unique_match(exact_matches, "LLVM IR", symbol);
-    }
-
-    if !prefix_matches.is_empty() {
-        return select_unique_match(prefix_matches, "LLVM IR (prefix)", symbol);
-    }
-
-    select_unique_match(def_name_matches, "LLVM IR", symbol)
+    matches.select("LLVM IR", symbol)
 }
 
 /// Extract assembly for a symbol within the given target triple. Uses mangled
-/// name, then prefix, then def-name matches.
+/// name, then demangled path, then def-name matches.
 pub fn extract_asm(
     assemblies: &[TargetedAssembly],
     symbol: &NormalizedSymbol,
     target_triple: &str,
 ) -> Result<String> {
-    let mut exact_matches = Vec::new();
-    let mut prefix_matches = Vec::new();
-    let mut name_matches = Vec::new();
+    let mut matches = StagedMatches::default();
+    let comment = asm_comment(target_triple);
 
     let mut found_target = false;
 
     for asm in assemblies.iter().filter(|asm| asm.target == target_triple) {
         found_target = true;
         for (label, block) in split_asm_blocks(&asm.content) {
-            let header = label.clone();
-            if let Some(mangled) = &symbol.mangled {
-                if label.contains(mangled) {
-                    exact_matches.push(Candidate {
-                        header,
-                        content: block.clone(),
-                    });
-                    continue;
-                }
-            }
-
-            if !symbol.mangled_prefix().is_empty() && label.contains(symbol.mangled_prefix()) {
-                prefix_matches.push(Candidate {
-                    header,
-                    content: block.clone(),
-                });
-                continue;
-            }
-
-            if block.contains(&symbol.def_name) || block.contains(&symbol.item_name) {
-                name_matches.push(Candidate {
-                    header,
-                    content: block,
-                });
-            }
+            let candidate = Candidate::compiled(&label, block, comment);
+            matches.add(symbol, &label, candidate, |block| {
+                block.contains(&symbol.def_name) || block.contains(&symbol.item_name)
+            });
         }
     }
 
@@ -187,15 +405,63 @@ pub fn extract_asm(
         );
     }
 
-    if !exact_matches.is_empty() {
-        return select_unique_match(exact_matches, "assembly", symbol);
+    matches.select("assembly", symbol)
+}
+
+/// The line comment marker of assembly for `target_triple`.
+pub fn asm_comment(target_triple: &str) -> &'static str {
+    if target_triple.starts_with("x86") || target_triple.starts_with("i686") {
+        "#"
+    } else {
+        "//"
     }
+}
 
-    if !prefix_matches.is_empty() {
-        return select_unique_match(prefix_matches, "assembly (prefix)", symbol);
+/// Candidates for a compiled symbol, grouped by how confidently they match.
+#[derive(Default)]
+struct StagedMatches {
+    exact: Vec<Candidate>,
+    demangled: Vec<Candidate>,
+    by_name: Vec<Candidate>,
+}
+
+impl StagedMatches {
+    fn add(
+        &mut self,
+        symbol: &NormalizedSymbol,
+        name: &str,
+        candidate: Candidate,
+        mentions_symbol: impl FnOnce(&str) -> bool,
+    ) {
+        let stage = if symbol
+            .mangled
+            .as_ref()
+            .is_some_and(|mangled| name.contains(mangled.as_str()))
+        {
+            &mut self.exact
+        } else if candidate
+            .demangled
+            .as_deref()
+            .is_some_and(|demangled| symbol.matches_demangled(demangled))
+        {
+            &mut self.demangled
+        } else if mentions_symbol(&candidate.content) {
+            &mut self.by_name
+        } else {
+            return;
+        };
+        stage.push(candidate);
     }
 
-    select_unique_match(name_matches, "assembly", symbol)
+    fn select(self, what: &str, symbol: &NormalizedSymbol) -> Result<String> {
+        if !self.exact.is_empty() {
+            select_unique_match(self.exact, what, symbol)
+        } else if !self.demangled.is_empty() {
+            select_unique_match(self.demangled, what, symbol)
+        } else {
+            select_unique_match(self.by_name, what, symbol)
+        }
+    }
 }
 
 /// Assembly output tagged by target triple.
@@ -209,6 +475,473 @@ pub struct TargetedAssembly {
 struct Candidate {
     header: String,
     content: String,
+    demangled: Option<String>,
+}
+
+impl Candidate {
+    /// Type arguments of a generic instance, e.g. `u8` for `demo::id::<u8>`.
+    fn instance(&self) -> Option<String> {
+        let lists = instance_arguments(self.demangled.as_deref()?);
+        (!lists.is_empty()).then(|| lists.join("; "))
+    }
+
+    fn is_instance(&self, wanted: &str) -> bool {
+        let lists = self
+            .demangled
+            .as_deref()
+            .map(instance_arguments)
+            .unwrap_or_default();
+        normalize_instance(&lists.join(";")) == wanted
+            || lists.iter().any(|list| normalize_instance(list) == wanted)
+    }
+
+    /// A function from LLVM IR or assembly, with its demangled name (if it
+    /// is a Rust symbol) in a `comment` line above the body.
+    fn compiled(name: &str, block: String, comment: &str) -> Self {
+        match demangle(name) {
+            Some(demangled) => Self {
+                header: format!("{demangled} ({name})"),
+                content: format!("{comment} {demangled}\n{block}"),
+                demangled: Some(demangled),
+            },
+            None => Self {
+                header: name.to_string(),
+                content: block,
+                demangled: None,
+            },
+        }
+    }
+}
+
+/// Extract an item from `-Zunpretty=expanded` output, along with the impl
+/// blocks for it such as those generated by derives.
+///
+/// `module_path` is relative to the crate root. An `item_name` of the form
+/// `impl Trait for Type` selects only the matching impl blocks.
+pub fn extract_expanded_item(
+    expanded: &str,
+    module_path: &[String],
+    item_name: &str,
+) -> Result<String> {
+    let mut body = expanded;
+    for module in module_path {
+        body = split_items(body)
+            .into_iter()
+            .map(|(start, end)| &body[start..end])
+            .find(|item| {
+                matches!(item_header(item), Some((keyword, name)) if keyword == "mod" && name == *module)
+            })
+            .and_then(|item| Some(&item[item.find('{')? + 1..item.rfind('}')?]))
+            .ok_or_else(|| anyhow::anyhow!("Module `{module}` not found in expanded output"))?;
+    }
+
+    let wanted_impl = impl_signature(item_name);
+    let type_name = wanted_impl
+        .as_ref()
+        .map(|(self_type, _)| self_type.as_str())
+        .unwrap_or(item_name);
+
+    let items: Vec<String> = split_items(body)
+        .into_iter()
+        .filter(|&(start, end)| match item_header(&body[start..end]) {
+            Some((keyword, header)) if keyword == "impl" => {
+                match (&wanted_impl, impl_signature(&header)) {
+                    (Some(wanted), Some(found)) => *wanted == found,
+                    (None, Some((self_type, _))) => self_type == type_name,
+                    _ => false,
+                }
+            }
+            Some((_, name)) => wanted_impl.is_none() && name == type_name,
+            None => false,
+        })
+        .map(|(start, end)| dedent_item(body, start, end))
+        .collect();
+
+    if items.is_empty() {
+        bail!(
+            "No item `{}` found in expanded output of `{}`",
+            item_name,
+            if module_path.is_empty() {
+                "crate".to_string()
+            } else {
+                module_path.join("::")
+            }
+        );
+    }
+    Ok(items.join("\n\n"))
+}
+
+/// Extract a function from pretty-printed source (`-Zunpretty=expanded` or
+/// `-Zunpretty=hir`), looking through modules and impl blocks.
+pub fn extract_source_fn(source: &str, symbol: &NormalizedSymbol, what: &str) -> Result<String> {
+    let mut functions = Vec::new();
+    collect_source_fns(
+        source,
+        &mut Vec::new(),
+        None,
+        &symbol.path.name,
+        &mut functions,
+    );
+
+    let (matched, by_name): (Vec<_>, Vec<_>) = functions
+        .into_iter()
+        .partition(|(path, _)| symbol.matches_demangled(path));
+    let matches = if matched.is_empty() { by_name } else { matched };
+    let candidates = matches
+        .into_iter()
+        .map(|(path, content)| Candidate {
+            header: path.trim_start_matches("crate::").to_string(),
+            content,
+            demangled: None,
+        })
+        .collect();
+
+    select_unique_match(candidates, what, symbol)
+}
+
+/// Functions named `name` in `source`, with the path rustc would give them
+/// (rooted at `crate`) and their dedented text.
+fn collect_source_fns(
+    source: &str,
+    modules: &mut Vec<String>,
+    impl_header: Option<&str>,
+    name: &str,
+    found: &mut Vec<(String, String)>,
+) {
+    for (start, end) in split_items(source) {
+        let item = &source[start..end];
+        let body = || Some(&item[item.find('{')? + 1..item.rfind('}')?]);
+        match item_header(item) {
+            Some((keyword, module)) if keyword == "mod" && impl_header.is_none() => {
+                if let Some(body) = body() {
+                    modules.push(module);
+                    collect_source_fns(body, modules, None, name, found);
+                    modules.pop();
+                }
+            }
+            Some((keyword, header)) if keyword == "impl" && impl_header.is_none() => {
+                if let Some(body) = body() {
+                    collect_source_fns(body, modules, Some(&header), name, found);
+                }
+            }
+            Some((keyword, function)) if keyword == "fn" && function == name => {
+                let mut path = vec!["crate".to_string()];
+                path.extend(modules.iter().cloned());
+                let path = match impl_header.and_then(impl_signature) {
+                    Some((self_type, Some(trait_name))) => {
+                        format!("<{}::{self_type} as {trait_name}>::{name}", path.join("::"))
+                    }
+                    Some((self_type, None)) => format!("{}::{self_type}::{name}", path.join("::")),
+                    None => format!("{}::{name}", path.join("::")),
+                };
+                found.push((path, dedent_item(source, start, end)));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Extract the tree of a body from `-Zunpretty=hir-tree` or
+/// `-Zunpretty=thir-tree` output, which lists one tree per `DefId`.
+///
+/// Impls appear as `{impl#N}` segments, so methods are told apart by the
+/// self type their tree mentions.
+pub fn extract_def_tree(output: &str, symbol: &NormalizedSymbol, what: &str) -> Result<String> {
+    let wanted: Vec<&str> = symbol
+        .path
+        .self_path
+        .iter()
+        .skip(1)
+        .map(String::as_str)
+        .collect();
+    let mut matches = Vec::new();
+    let mut by_name = Vec::new();
+
+    for (path, block) in split_def_blocks(output) {
+        let Some((name, parents)) = path.split_last() else {
+            continue;
+        };
+        if *name != symbol.path.name {
+            continue;
+        }
+        let modules: Vec<&str> = parents
+            .iter()
+            .copied()
+            .filter(|segment| !segment.starts_with("{impl#"))
+            .collect();
+        let in_impl = modules.len() < parents.len();
+        let matched = if in_impl {
+            wanted.len() == modules.len() + 1 && wanted.starts_with(&modules)
+        } else {
+            symbol.path.trait_path.is_none() && wanted == modules
+        };
+
+        let candidate = Candidate {
+            header: block_header(&block),
+            content: block,
+            demangled: None,
+        };
+        if matched {
+            matches.push(candidate);
+        } else {
+            by_name.push(candidate);
+        }
+    }
+
+    let mut matches = if matches.is_empty() { by_name } else { matches };
+    if matches.len() > 1
+        && let Some(self_type) = symbol.path.self_path.last()
+        && matches.iter().any(|m| mentions_word(&m.content, self_type))
+    {
+        matches.retain(|m| mentions_word(&m.content, self_type));
+    }
+
+    select_unique_match(matches, what, symbol)
+}
+
+fn mentions_word(text: &str, word: &str) -> bool {
+    text.match_indices(word).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + word.len()..].chars().next();
+        !is_ident_char(before) && !is_ident_char(after)
+    })
+}
+
+/// Extract the control-flow graph of a function from `-Zunpretty=mir-cfg`
+/// output, as a Graphviz digraph of its own.
+pub fn extract_mir_cfg(output: &str, symbol: &NormalizedSymbol) -> Result<String> {
+    let mut matches: Vec<Candidate> = Vec::new();
+    let mut by_name: Vec<Candidate> = Vec::new();
+
+    for block in split_cfg_subgraphs(output) {
+        let Some(signature) = cfg_signature(&block) else {
+            continue;
+        };
+        let Some(path) = signature
+            .strip_prefix("fn ")
+            .and_then(|rest| Some(&rest[..top_level_paren(rest)?]))
+        else {
+            continue;
+        };
+        let qualified = if path.starts_with('<') {
+            path.to_string()
+        } else {
+            format!("crate::{path}")
+        };
+
+        let stage = if symbol.matches_demangled(&qualified) {
+            &mut matches
+        } else if QualifiedPath::parse(path).is_some_and(|found| found.name == symbol.path.name) {
+            &mut by_name
+        } else {
+            continue;
+        };
+        // Constructors are printed once per use.
+        if !stage.iter().any(|m| m.content == block) {
+            stage.push(Candidate {
+                header: signature,
+                content: block,
+                demangled: None,
+            });
+        }
+    }
+
+    let matches = if matches.is_empty() { by_name } else { matches };
+    let subgraphs = select_unique_match(matches, "MIR CFG", symbol)?;
+    Ok(format!("digraph mir_cfg {{\n{subgraphs}\n}}"))
+}
+
+/// Byte ranges of the top-level items in `source`, each including its
+/// attributes and its terminating `;` or `}`.
+fn split_items(source: &str) -> Vec<(usize, usize)> {
+    let chars: Vec<(usize, char)> = source.char_indices().collect();
+    let mut items = Vec::new();
+    let mut start = None;
+    let mut depth = 0usize;
+    let mut index = 0;
+
+    while index < chars.len() {
+        let (offset, ch) = chars[index];
+        let next = chars.get(index + 1).map(|&(_, ch)| ch);
+        if start.is_none() && !ch.is_whitespace() {
+            start = Some(offset);
+        }
+
+        match ch {
+            '/' if next == Some('/') => {
+                while index < chars.len() && chars[index].1 != '\n' {
+                    index += 1;
+                }
+                continue;
+            }
+            '/' if next == Some('*') => {
+                index += 2;
+                while index + 1 < chars.len()
+                    && !(chars[index].1 == '*' && chars[index + 1].1 == '/')
+                {
+                    index += 1;
+                }
+                index += 2;
+                continue;
+            }
+            '"' => index = skip_string(&chars, index + 1, 0),
+            'r' if matches!(next, Some('"' | '#'))
+                && !is_ident_char(index.checked_sub(1).map(|i| chars[i].1)) =>
+            {
+                let hashes = chars[index + 1..]
+                    .iter()
+                    .take_while(|&&(_, ch)| ch == '#')
+                    .count();
+                if chars.get(index + 1 + hashes).map(|&(_, ch)| ch) == Some('"') {
+                    index = skip_string(&chars, index + 2 + hashes, hashes);
+                }
+            }
+            '\'' => {
+                // Char literals close within a few characters; lifetimes never do.
+                if next == Some('\\') {
+                    index += 2;
+                    while index < chars.len() && chars[index].1 != '\'' {
+                        index += 1;
+                    }
+                } else if chars.get(index + 2).map(|&(_, ch)| ch) == Some('\'') {
+                    index += 2;
+                }
+            }
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            '}' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0
+                    && let Some(item_start) = start
+                {
+                    let mut end = offset + 1;
+                    let rest = &source[end..];
+                    if rest.trim_start().starts_with(';') {
+                        end += rest.find(';').unwrap_or(0) + 1;
+                        while index + 1 < chars.len() && chars[index + 1].0 < end {
+                            index += 1;
+                        }
+                    }
+                    items.push((item_start, end));
+                    start = None;
+                }
+            }
+            ';' if depth == 0 => {
+                if let Some(item_start) = start.take() {
+                    items.push((item_start, offset + 1));
+                }
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+
+    items
+}
+
+/// Index just past the closing quote of a string whose body starts at
+/// `index`, for raw strings with `hashes` trailing `#`s.
+fn skip_string(chars: &[(usize, char)], mut index: usize, hashes: usize) -> usize {
+    while index < chars.len() {
+        match chars[index].1 {
+            '\\' if hashes == 0 => index += 1,
+            '"' if chars[index + 1..]
+                .iter()
+                .take(hashes)
+                .filter(|&&(_, ch)| ch == '#')
+                .count()
+                == hashes =>
+            {
+                return index + hashes;
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    index
+}
+
+fn is_ident_char(ch: Option<char>) -> bool {
+    ch.is_some_and(|ch| ch == '_' || ch.is_alphanumeric())
+}
+
+/// Keyword and name of an item, skipping attributes, visibility and
+/// qualifiers. Impl blocks return their whole header as the name.
+fn item_header(item: &str) -> Option<(String, String)> {
+    let text = item
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| line.starts_with("#") || line.starts_with("//"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut rest = strip_visibility(&text);
+    loop {
+        let stripped = ["default ", "unsafe ", "async ", "auto "]
+            .iter()
+            .find_map(|qualifier| rest.strip_prefix(qualifier))
+            .or_else(|| {
+                let after = rest.strip_prefix("const ")?;
+                ["fn ", "unsafe ", "async ", "extern "]
+                    .iter()
+                    .any(|next| after.starts_with(next))
+                    .then_some(after)
+            })
+            .or_else(|| {
+                let after = rest.strip_prefix("extern \"")?;
+                let after = after[after.find('"')? + 1..].trim_start();
+                after.starts_with("fn ").then_some(after)
+            });
+        match stripped {
+            Some(stripped) => rest = stripped.trim_start(),
+            None => break,
+        }
+    }
+
+    if rest.starts_with("impl") && impl_self_type(rest).is_some() {
+        let header = rest.split('{').next().unwrap_or(rest).trim();
+        return Some(("impl".to_string(), header.to_string()));
+    }
+
+    let (keyword, rest) = rest.split_once(' ')?;
+    let name: String = rest
+        .trim_start()
+        .chars()
+        .take_while(|&ch| ch == '_' || ch.is_alphanumeric())
+        .collect();
+    (!name.is_empty()).then(|| (keyword.to_string(), name))
+}
+
+/// Self type and trait (if any) of an impl header, by their last path
+/// segments without generics.
+fn impl_signature(header: &str) -> Option<(String, Option<String>)> {
+    let (self_type, is_trait_impl) = impl_self_type(header)?;
+    let trait_name = if is_trait_impl {
+        header
+            .split_once(" for ")
+            .and_then(|(trait_part, _)| impl_self_type(trait_part))
+            .map(|(name, _)| name)
+    } else {
+        None
+    };
+    Some((self_type, trait_name))
+}
+
+/// The item text with its nesting indentation removed.
+fn dedent_item(source: &str, start: usize, end: usize) -> String {
+    let indent = start - source[..start].rfind('\n').map_or(0, |newline| newline + 1);
+    source[start..end]
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            let leading = line.len() - line.trim_start_matches(' ').len();
+            if index == 0 {
+                line
+            } else {
+                &line[leading.min(indent)..]
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 fn split_mir_blocks(output: &str) -> Vec<String> {
@@ -240,6 +973,85 @@ fn split_mir_blocks(output: &str) -> Vec<String> {
     blocks
 }
 
+/// Blocks of hir-tree or thir-tree output, keyed by the path segments of
+/// their `DefId(0:4 ~ demo[f992]::utils::triple)` header below the crate.
+fn split_def_blocks(output: &str) -> Vec<(Vec<&str>, String)> {
+    let mut blocks: Vec<(Vec<&str>, String)> = Vec::new();
+
+    for line in output.lines() {
+        if line.starts_with("DefId(")
+            && let Some(path) = line
+                .split_once(" ~ ")
+                .and_then(|(_, rest)| rest.split_once(')'))
+                .map(|(path, _)| path)
+        {
+            let segments = path.split("::").skip(1).collect();
+            blocks.push((segments, String::new()));
+        }
+        if let Some((_, block)) = blocks.last_mut() {
+            block.push_str(line);
+            block.push('\n');
+        }
+    }
+
+    blocks
+        .into_iter()
+        .map(|(path, block)| (path, block.trim_end().to_string()))
+        .collect()
+}
+
+/// `subgraph cluster_*` blocks of mir-cfg output, one per body.
+fn split_cfg_subgraphs(output: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<String> = None;
+
+    for line in output.lines() {
+        if line.starts_with("subgraph ") {
+            current = Some(String::new());
+        }
+        if let Some(block) = current.as_mut() {
+            block.push_str(line);
+            block.push('\n');
+            if line == "}" {
+                blocks.push(block.trim_end().to_string());
+                current = None;
+            }
+        }
+    }
+
+    blocks
+}
+
+/// The function signature in a mir-cfg subgraph label, unescaped, e.g.
+/// `fn <Point as Debug>::fmt(_1: &'{erased} Point, ...) -> ...`.
+fn cfg_signature(block: &str) -> Option<String> {
+    let label = block
+        .lines()
+        .find_map(|line| line.trim_start().strip_prefix("label=<"))?;
+    let signature = label.split("<br").next()?;
+    Some(
+        signature
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&amp;", "&"),
+    )
+}
+
+/// Byte offset of the first `(` outside angle brackets.
+fn top_level_paren(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (index, ch) in text.char_indices() {
+        match ch {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            '(' if depth == 0 => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
 fn split_llvm_blocks(output: &str) -> Vec<(String, String)> {
     let mut blocks = Vec::new();
     let mut current_name: Option<String> = None;
@@ -257,16 +1069,20 @@ fn split_llvm_blocks(output: &str) -> Vec<(String, String)> {
         if current_name.is_some() {
             current_lines.push(line);
         }
+        // Attributes and metadata after the last function are not part of it.
+        if line.starts_with('}')
+            && let Some(name) = current_name.take()
+        {
+            blocks.push((name, current_lines.join("\n")));
+            current_lines.clear();
+        }
     }
 
     if let Some(name) = current_name {
         blocks.push((name, current_lines.join("\n")));
     }
 
-    blocks
-        .into_iter()
-        .map(|(name, block)| (name, block))
-        .collect()
+    blocks.into_iter().collect()
 }
 
 fn split_asm_blocks(output: &str) -> Vec<(String, String)> {
@@ -276,16 +1092,29 @@ fn split_asm_blocks(output: &str) -> Vec<(String, String)> {
 
     for line in output.lines() {
         let trimmed = line.trim();
-        if trimmed.ends_with(':') && !trimmed.starts_with('#') {
+        if let Some(label) = trimmed.strip_suffix(':')
+            && !trimmed.starts_with('#')
+            && !is_local_label(label)
+        {
             if let Some(label) = current_label.take() {
                 blocks.push((label, current_lines.join("\n")));
                 current_lines.clear();
             }
-            current_label = Some(trimmed.trim_end_matches(':').trim_matches('"').to_string());
+            current_label = Some(label.trim_matches('"').to_string());
         }
 
-        if current_label.is_some() {
+        if let Some(label) = &current_label {
             current_lines.push(line);
+            // Debug sections and data that follow the function end its block.
+            let ends_function = trimmed == ".cfi_endproc"
+                || trimmed
+                    .strip_prefix(".size")
+                    .is_some_and(|size| size.trim_start().starts_with(label.as_str()));
+            if ends_function {
+                blocks.push((label.clone(), current_lines.join("\n")));
+                current_lines.clear();
+                current_label = None;
+            }
         }
     }
 
@@ -293,10 +1122,16 @@ fn split_asm_blocks(output: &str) -> Vec<(String, String)> {
         blocks.push((label, current_lines.join("\n")));
     }
 
-    blocks
-        .into_iter()
-        .map(|(label, block)| (label, block))
-        .collect()
+    blocks.into_iter().collect()
+}
+
+/// Labels inside a function (`.LBB0_3`, `.Ltmp1`, `LBB0_3` on Mach-O) rather
+/// than the function's own symbol.
+pub(super) fn is_local_label(label: &str) -> bool {
+    label.starts_with(".L")
+        || ["LBB", "Ltmp", "Lfunc_"]
+            .iter()
+            .any(|prefix| label.starts_with(prefix))
 }
 
 fn extract_llvm_symbol_name(line: &str) -> Option<String> {
@@ -318,20 +1153,47 @@ fn block_header(block: &str) -> String {
 }
 
 fn select_unique_match(
-    matches: Vec<Candidate>,
+    mut matches: Vec<Candidate>,
     what: &str,
     symbol: &NormalizedSymbol,
 ) -> Result<String> {
+    // MIR and the source-level views have one generic body per item, so only
+    // codegen output has instances to pick from.
+    let has_instances = matches.iter().any(|m| m.demangled.is_some());
+    match symbol.instance.as_deref().filter(|_| has_instances) {
+        Some(all) if all.eq_ignore_ascii_case("all") && !matches.is_empty() => {
+            return Ok(matches
+                .into_iter()
+                .map(|m| m.content)
+                .collect::<Vec<_>>()
+                .join("\n\n"));
+        }
+        Some(instance) if !matches.is_empty() => {
+            let wanted = normalize_instance(instance);
+            let instances = instance_list(&matches);
+            matches.retain(|m| m.is_instance(&wanted));
+            if matches.is_empty() {
+                bail!(
+                    "No {} instance `{}` of `{}`. Instantiations:\n{}",
+                    what,
+                    instance,
+                    symbol.def_name,
+                    instances
+                );
+            }
+        }
+        _ => {}
+    }
+
     if matches.is_empty() {
         let mut looked_for = vec![
             format!("def-name `{}`", symbol.def_name),
             format!("item name `{}`", symbol.item_name),
+            format!("demangled path `{}`", symbol.path),
         ];
 
         if let Some(mangled) = &symbol.mangled {
             looked_for.push(format!("mangled `{mangled}`"));
-        } else if !symbol.mangled_prefix().is_empty() {
-            looked_for.push(format!("mangled prefix `{}`", symbol.mangled_prefix()));
         }
 
         bail!(
@@ -343,29 +1205,62 @@ fn select_unique_match(
     }
 
     if matches.len() > 1 {
-        let headers: Vec<String> = matches.iter().map(|m| m.header.clone()).collect();
+        let headers: Vec<String> = matches.iter().map(|m| format!("  {}", m.header)).collect();
+        // Generic instances share a path once their arguments are dropped.
+        let mut paths: Vec<String> = matches
+            .iter()
+            .filter_map(|m| QualifiedPath::parse(m.demangled.as_deref()?))
+            .map(|path| path.to_string())
+            .collect();
+        paths.sort_unstable();
+        paths.dedup();
+        if paths.len() == 1 && matches.iter().all(|m| m.demangled.is_some()) {
+            if matches.iter().all(|m| m.instance().is_none()) {
+                bail!(
+                    "`{}` has {} instantiations in {}:\n{}\nLegacy symbols do not name their type arguments; pass `instance` (e.g. `u8`, or `all`) to rebuild with v0 mangling and pick one.",
+                    paths[0],
+                    matches.len(),
+                    what,
+                    instance_list(&matches)
+                );
+            }
+            bail!(
+                "`{}` has {} instantiations in {}:\n{}\nPass `instance` with one of the type arguments above, or `all` to show every instantiation.",
+                paths[0],
+                matches.len(),
+                what,
+                instance_list(&matches)
+            );
+        }
         bail!(
-            "Multiple {} candidates matched `{}`: {}",
+            "Multiple {} candidates matched `{}`:\n{}\nPass `symbol_name` as a qualified path such as `Type::method` or `<Type as Trait>::method` to pick one.",
             what,
             symbol.def_name,
-            headers.join(", ")
+            headers.join("\n")
         );
     }
 
     Ok(matches[0].content.clone())
 }
 
-fn encode_rust_mangled_prefix(segments: &[String]) -> String {
-    let mut encoded = String::from("_ZN");
-    for segment in segments {
-        encoded.push_str(&format!("{}{}", segment.len(), segment));
-    }
-    encoded
+/// One line per candidate: its type arguments, then the symbol.
+fn instance_list(matches: &[Candidate]) -> String {
+    matches
+        .iter()
+        .map(|m| match m.instance() {
+            Some(instance) => format!("  {instance}: {}", m.header),
+            None => format!("  {}", m.header),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{NormalizedSymbol, TargetedAssembly, extract_asm, extract_llvm_ir, extract_mir};
+    use super::{
+        NormalizedSymbol, TargetedAssembly, extract_asm, extract_def_tree, extract_expanded_item,
+        extract_llvm_ir, extract_mir, extract_mir_cfg, extract_source_fn, normalize_instance,
+    };
     use crate::analyzer::symbol::{SymbolIdentity, SymbolKind};
 
     fn demo_symbol() -> NormalizedSymbol {
@@ -379,12 +1274,6 @@ mod tests {
         NormalizedSymbol::from_identity(&identity)
     }
 
-    #[test]
-    fn builds_mangled_prefix_from_segments() {
-        let identity = demo_symbol();
-        assert_eq!(identity.mangled_prefix(), "_ZN4demo5utils8do_thing");
-    }
-
     #[test]
     fn extracts_mir_by_def_name() {
         let mir = r#"
@@ -404,20 +1293,25 @@ fn demo::utils::other(_1: i32) -> i32 {
         let extracted = extract_mir(&[mir.to_string()], &symbol).expect("mir extracted");
         assert!(extracted.contains("do_thing"));
         assert!(!extracted.contains("other(_1"));
+
+        // MIR is generic, so an instance does not narrow it down.
+        let symbol = demo_symbol().with_instance("u8");
+        let extracted = extract_mir(&[mir.to_string()], &symbol).expect("instance ignored");
+        assert!(extracted.contains("do_thing"));
     }
 
     #[test]
-    fn extracts_llvm_ir_with_mangled_prefix() {
+    fn matches_legacy_symbols_by_demangled_path() {
         let llvm = r#"
 ; ModuleID = 'demo'
 source_filename = "demo"
 
-define dso_local void @_ZN4demo5utils8do_thing17h1234abcdE() #0 {
+define dso_local void @_ZN4demo5utils8do_thing17h1234abcd5678ef90E() #0 {
 entry-block:
   ret void
 }
 
-define dso_local void @_ZN4demo5utils9do_other17h99999999E() #0 {
+define dso_local void @_ZN4demo5utils8do_other17h9999999999999999E() #0 {
 entry-block:
   ret void
 }
@@ -427,6 +1321,92 @@ entry-block:
         let extracted = extract_llvm_ir(&[llvm.to_string()], &symbol).expect("llvm extracted");
         assert!(extracted.contains("_ZN4demo5utils8do_thing"));
         assert!(!extracted.contains("do_other17h"));
+        assert!(extracted.contains("demo::utils::do_thing"));
+    }
+
+    #[test]
+    fn matches_v0_symbols_by_demangled_path() {
+        let llvm = r#"
+define void @_RNvXs_NtCsauMP1AnkCw5_4demo6shapesNtB4_5PointNtNtCsgEmfK2I1SDS_4core3fmt7Display3fmt() {
+  ret void
+}
+
+define void @_RNvMNtCsauMP1AnkCw5_4demo6shapesNtB2_5Point3new() {
+  ret void
+}
+
+define void @_RNvMs0_NtCsauMP1AnkCw5_4demo6shapesINtB5_7WrapperhE3getB7_() {
+  ret void
+}
+
+define void @_RNvMs0_NtCsauMP1AnkCw5_4demo6shapesINtB5_7WrappermE3getB7_() {
+  ret void
+}
+        "#;
+        let outputs = [llvm.to_string()];
+        let symbol = |module_path: &[&str], item_name: &str| {
+            NormalizedSymbol::from_identity(&SymbolIdentity {
+                crate_name: "demo".to_string(),
+                module_path: module_path.iter().map(|name| name.to_string()).collect(),
+                item_name: item_name.to_string(),
+                kind: SymbolKind::Method,
+            })
+        };
+
+        let display = extract_llvm_ir(
+            &outputs,
+            &symbol(&["shapes", "impl fmt::Display for Point"], "fmt"),
+        )
+        .expect("trait impl extracted");
+        assert!(display.starts_with("; <demo::shapes::Point as core::fmt::Display>::fmt\ndefine"));
+
+        let explicit = extract_llvm_ir(&outputs, &symbol(&["lib"], "<Point as Display>::fmt"))
+            .expect("qualified path extracted");
+        assert_eq!(explicit, display);
+
+        let new = extract_llvm_ir(&outputs, &symbol(&["shapes"], "Point::new"))
+            .expect("inherent method extracted");
+        assert!(new.starts_with("; <demo::shapes::Point>::new\n"));
+
+        let get = symbol(&["shapes", "impl<T: Copy> Wrapper<T>"], "get");
+        let err = extract_llvm_ir(&outputs, &get).unwrap_err().to_string();
+        assert!(
+            err.starts_with("`demo::shapes::Wrapper::get` has 2 instantiations in LLVM IR:\n"),
+            "{err}"
+        );
+        assert!(
+            err.contains("\n  u8: <demo::shapes::Wrapper<u8>>::get (_RNvMs0_"),
+            "{err}"
+        );
+        assert!(
+            err.contains("\n  u32: <demo::shapes::Wrapper<u32>>::get (_RNvMs0_"),
+            "{err}"
+        );
+
+        let u32_get = extract_llvm_ir(&outputs, &get.clone().with_instance(" u32"))
+            .expect("instance selected");
+        assert!(u32_get.starts_with("; <demo::shapes::Wrapper<u32>>::get\n"));
+        let all = extract_llvm_ir(&outputs, &get.clone().with_instance("all"))
+            .expect("all instances extracted");
+        assert_eq!(all.matches("define void").count(), 2);
+        let err = extract_llvm_ir(&outputs, &get.with_instance("Vec<u8>"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("No LLVM IR instance `Vec<u8>`"), "{err}");
+    }
+
+    #[test]
+    fn normalizes_instances_without_lifetimes() {
+        assert_eq!(
+            normalize_instance("for<'a> fn(&'a u8) -> &'a u8"),
+            normalize_instance("fn(&u8) -> &u8")
+        );
+        assert_eq!(
+            normalize_instance("demo::Ref<'static, alloc::vec::Vec<u8>>"),
+            normalize_instance("Ref<Vec<u8>>")
+        );
+        assert_eq!(normalize_instance("Foo<'a>"), "Foo");
+        assert_eq!(normalize_instance("'c'"), "'c'");
     }
 
     #[test]
@@ -435,32 +1415,140 @@ entry-block:
             target: "x86_64-unknown-linux-gnu".to_string(),
             content: r#"
     .section    .text
-    .globl  _ZN4demo5utils8do_thing17h1234abcdE
-_ZN4demo5utils8do_thing17h1234abcdE:
+    .globl  _ZN4demo5utils8do_thing17h1234abcd5678ef90E
+_ZN4demo5utils8do_thing17h1234abcd5678ef90E:
     retq
 
-_ZN4demo5utils9do_other17h99999999E:
+_ZN4demo5utils8do_other17h9999999999999999E:
     retq
             "#
             .to_string(),
         };
 
-        let symbol = demo_symbol().with_mangled("_ZN4demo5utils8do_thing17h1234abcdE");
+        let symbol = demo_symbol().with_mangled("_ZN4demo5utils8do_thing17h1234abcd5678ef90E");
         let extracted =
             extract_asm(&[asm], &symbol, "x86_64-unknown-linux-gnu").expect("asm extracted");
-        assert!(extracted.contains("_ZN4demo5utils8do_thing17h1234abcdE:"));
+        assert!(extracted.contains("_ZN4demo5utils8do_thing17h1234abcd5678ef90E:"));
         assert!(!extracted.contains("do_other17h"));
+        assert!(extracted.contains("demo::utils::do_thing"));
     }
 
     #[test]
     fn errors_when_target_missing() {
         let asm = TargetedAssembly {
             target: "aarch64-unknown-linux-gnu".to_string(),
-            content: "_ZN4demo5utils8do_thing17h1234abcdE:\nret".to_string(),
+            content: "_ZN4demo5utils8do_thing17h1234abcd5678ef90E:\nret".to_string(),
         };
 
-        let symbol = demo_symbol().with_mangled("_ZN4demo5utils8do_thing17h1234abcdE");
+        let symbol = demo_symbol().with_mangled("_ZN4demo5utils8do_thing17h1234abcd5678ef90E");
         let err = extract_asm(&[asm], &symbol, "x86_64-unknown-linux-gnu").unwrap_err();
         assert!(err.to_string().contains("No assembly artifacts"));
     }
+
+    #[test]
+    fn extracts_expanded_item_with_derived_impls() {
+        let expanded = r#"#![feature(prelude_import)]
+#[prelude_import]
+use std::prelude::rust_2021::*;
+#[macro_use]
+extern crate std;
+pub mod shapes {
+    use std::fmt;
+    pub struct Point {
+        pub x: i32,
+        pub y: i32,
+    }
+    #[automatically_derived]
+    impl ::core::fmt::Debug for Point {
+        #[inline]
+        fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+            ::core::fmt::Formatter::debug_struct_field2_finish(f, "Point", "x",
+                &self.x, "y", &&self.y)
+        }
+    }
+    impl fmt::Display for Point {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_fmt(format_args!("({0}, {1})", self.x, self.y))
+        }
+    }
+    const OPEN: char = '{';
+    pub fn origin() -> Point { Point { x: 0, y: 0 } }
+}
+"#;
+        let shapes = ["shapes".to_string()];
+
+        let point = extract_expanded_item(expanded, &shapes, "Point").expect("item extracted");
+        assert!(point.starts_with("pub struct Point {\n    pub x: i32,"));
+        assert!(point.contains("impl ::core::fmt::Debug for Point {\n    #[inline]"));
+        assert!(point.contains("impl fmt::Display for Point"));
+        assert!(!point.contains("origin"));
+
+        let display = extract_expanded_item(expanded, &shapes, "impl Display for Point")
+            .expect("impl extracted");
+        assert!(display.starts_with("impl fmt::Display for Point {"));
+        assert!(!display.contains("Debug"));
+
+        let origin = extract_expanded_item(expanded, &shapes, "origin").expect("fn extracted");
+        assert_eq!(origin, "pub fn origin() -> Point { Point { x: 0, y: 0 } }");
+
+        assert!(extract_expanded_item(expanded, &[], "Point").is_err());
+    }
+
+    #[test]
+    fn extracts_unpretty_views_per_function() {
+        let sum = NormalizedSymbol::from_identity(&SymbolIdentity {
+            crate_name: "demo".to_string(),
+            module_path: vec!["utils".to_string(), "impl Pair".to_string()],
+            item_name: "sum".to_string(),
+            kind: SymbolKind::Method,
+        });
+
+        let hir = "\
+mod utils {
+    fn sum() -> u32 { 0 }
+    impl Pair {
+        fn sum(&self) -> u32 { self.0 as u32 + self.1 as u32 }
+    }
+}
+";
+        assert_eq!(
+            extract_source_fn(hir, &sum, "hir").expect("method extracted"),
+            "fn sum(&self) -> u32 { self.0 as u32 + self.1 as u32 }"
+        );
+
+        let thir = "\
+DefId(0:4 ~ demo[f992]::utils::sum):
+params: []
+DefId(0:10 ~ demo[f992]::utils::{impl#0}::sum):
+params: [
+    Param {
+        ty: &'{erased} utils::Pair
+    }
+]
+DefId(0:12 ~ demo[f992]::utils::{impl#1}::sum):
+params: [
+    Param {
+        ty: &'{erased} utils::Triple
+    }
+]
+";
+        let tree = extract_def_tree(thir, &sum, "thir-tree").expect("tree extracted");
+        assert!(tree.starts_with("DefId(0:10 ~ demo[f992]::utils::{impl#0}::sum):"));
+        assert!(!tree.contains("Triple"));
+
+        let cfg = "\
+digraph __crate__ {
+subgraph cluster_Mir_0_4 {
+    label=<fn sum() -&gt; u32<br align=\"left\"/>>;
+}
+subgraph cluster_Mir_0_10 {
+    label=<fn Pair::sum(_1: &amp;'{erased} utils::Pair) -&gt; u32<br align=\"left\"/>>;
+    bb0__0_10 -> bb1__0_10 [label=\"success\"];
+}
+}
+";
+        let graph = extract_mir_cfg(cfg, &sum).expect("cfg extracted");
+        assert!(graph.starts_with("digraph mir_cfg {\nsubgraph cluster_Mir_0_10 {"));
+        assert!(graph.ends_with("}\n}"));
+    }
 }
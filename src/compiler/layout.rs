@@ -0,0 +1,390 @@
+//! Memory layouts of structs and enums, from rustc's `-Zprint-type-sizes`
+//! output or rust-analyzer's `viewRecursiveMemoryLayout`.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::extract::strip_generics;
+use crate::analyzer::symbol::SymbolIdentity;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TypeLayout {
+    pub name: String,
+    pub size: u64,
+    pub align: u64,
+    /// Padding bytes between and after the fields.
+    pub padding: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discriminant_size: Option<u64>,
+    /// Whether an enum keeps its discriminant in a niche of a field rather
+    /// than a separate tag. Unknown for rust-analyzer layouts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub niche: Option<bool>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldLayout>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<VariantLayout>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldLayout {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_name: Option<String>,
+    pub offset: u64,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub align: Option<u64>,
+    /// Nested fields, as reported by rust-analyzer.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldLayout>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VariantLayout {
+    pub name: String,
+    pub size: u64,
+    pub padding: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldLayout>,
+}
+
+/// One `print-type-size` line: `field `.b`: 8 bytes, alignment: 8 bytes`
+/// becomes kind `field`, name `.b`, size 8 and the remaining properties.
+struct SizeEntry<'a> {
+    kind: &'a str,
+    name: Option<&'a str>,
+    size: u64,
+    properties: Vec<(&'a str, u64)>,
+}
+
+impl<'a> SizeEntry<'a> {
+    fn parse(text: &'a str) -> Option<Self> {
+        let (kind, name, rest) = match (text.find('`'), text.rfind("`: ")) {
+            (Some(open), Some(close)) if open < close => (
+                text[..open].trim().trim_end_matches(':'),
+                Some(&text[open + 1..close]),
+                &text[close + 3..],
+            ),
+            _ => {
+                let (kind, rest) = text.split_once(": ")?;
+                (kind.trim(), None, rest)
+            }
+        };
+
+        let mut values = rest.split(", ");
+        let size = bytes(values.next()?)?;
+        let properties = values
+            .filter_map(|value| {
+                let (key, value) = value.split_once(": ")?;
+                Some((key, bytes(value)?))
+            })
+            .collect();
+        Some(Self {
+            kind,
+            name,
+            size,
+            properties,
+        })
+    }
+
+    fn property(&self, key: &str) -> Option<u64> {
+        self.properties
+            .iter()
+            .find_map(|(name, value)| (*name == key).then_some(*value))
+    }
+}
+
+fn bytes(value: &str) -> Option<u64> {
+    value.trim().strip_suffix(" bytes")?.parse().ok()
+}
+
+/// Fields laid out one after another from `offset`, as `-Zprint-type-sizes`
+/// lists them in memory order with explicit padding in between.
+#[derive(Default)]
+struct FieldCursor {
+    offset: u64,
+    padding: u64,
+    fields: Vec<FieldLayout>,
+}
+
+impl FieldCursor {
+    fn push(&mut self, entry: &SizeEntry) {
+        match entry.kind {
+            "padding" | "end padding" => {
+                self.offset += entry.size;
+                self.padding += entry.size;
+            }
+            "field" => {
+                let offset = entry.property("offset").unwrap_or(self.offset);
+                self.fields.push(FieldLayout {
+                    name: entry
+                        .name
+                        .unwrap_or_default()
+                        .trim_start_matches('.')
+                        .to_string(),
+                    type_name: None,
+                    offset,
+                    size: entry.size,
+                    align: entry.property("alignment"),
+                    fields: Vec::new(),
+                });
+                self.offset = offset + entry.size;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Parse the `print-type-size` lines rustc prints for `-Zprint-type-sizes`.
+pub fn parse_type_sizes(output: &str) -> Vec<TypeLayout> {
+    let mut layouts: Vec<TypeLayout> = Vec::new();
+    let mut fields = FieldCursor::default();
+    let mut variant: Option<(VariantLayout, FieldCursor)> = None;
+
+    let finish_variant = |variant: &mut Option<(VariantLayout, FieldCursor)>,
+                          layouts: &mut Vec<TypeLayout>| {
+        if let Some((mut layout, cursor)) = variant.take()
+            && let Some(ty) = layouts.last_mut()
+        {
+            layout.padding = cursor.padding;
+            layout.fields = cursor.fields;
+            ty.variants.push(layout);
+        }
+    };
+    let finish_type = |fields: &mut FieldCursor, layouts: &mut Vec<TypeLayout>| {
+        let cursor = std::mem::take(fields);
+        if let Some(ty) = layouts.last_mut() {
+            ty.padding += cursor.padding;
+            ty.fields = cursor.fields;
+            if ty.variants.len() > 1 {
+                ty.niche = Some(ty.discriminant_size.is_none());
+            }
+        }
+    };
+
+    for line in output.lines() {
+        let Some(text) = line.strip_prefix("print-type-size ") else {
+            continue;
+        };
+        let depth = (text.len() - text.trim_start().len()) / 4;
+        let Some(entry) = SizeEntry::parse(text.trim_start()) else {
+            continue;
+        };
+
+        match (depth, entry.kind) {
+            (0, "type") => {
+                finish_variant(&mut variant, &mut layouts);
+                finish_type(&mut fields, &mut layouts);
+                layouts.push(TypeLayout {
+                    name: entry.name.unwrap_or_default().to_string(),
+                    size: entry.size,
+                    align: entry.property("alignment").unwrap_or(1),
+                    padding: 0,
+                    discriminant_size: None,
+                    niche: None,
+                    fields: Vec::new(),
+                    variants: Vec::new(),
+                });
+            }
+            (1, "discriminant") => {
+                if let Some(ty) = layouts.last_mut() {
+                    ty.discriminant_size = Some(entry.size);
+                }
+            }
+            (1, "variant") => {
+                finish_variant(&mut variant, &mut layouts);
+                let start = layouts
+                    .last()
+                    .and_then(|ty| ty.discriminant_size)
+                    .unwrap_or(0);
+                variant = Some((
+                    VariantLayout {
+                        name: entry.name.unwrap_or_default().to_string(),
+                        size: entry.size,
+                        padding: 0,
+                        fields: Vec::new(),
+                    },
+                    FieldCursor {
+                        offset: start,
+                        ..FieldCursor::default()
+                    },
+                ));
+            }
+            (1, _) => {
+                // Padding after the variants belongs to the enum itself.
+                finish_variant(&mut variant, &mut layouts);
+                fields.push(&entry);
+            }
+            (_, _) => {
+                if let Some((_, cursor)) = variant.as_mut() {
+                    cursor.push(&entry);
+                }
+            }
+        }
+    }
+    finish_variant(&mut variant, &mut layouts);
+    finish_type(&mut fields, &mut layouts);
+
+    layouts
+}
+
+/// Convert the `nodes` of rust-analyzer's `viewRecursiveMemoryLayout`, where
+/// the first node is the type itself and each node points at its children.
+pub fn from_analyzer_layout(layout: &Value) -> Option<TypeLayout> {
+    let nodes = layout["nodes"].as_array()?;
+    let root = nodes.first()?;
+    let fields = analyzer_children(nodes, root);
+
+    let size = root["size"].as_u64()?;
+    let mut spans: Vec<(u64, u64)> = fields
+        .iter()
+        .map(|field| (field.offset, field.offset + field.size))
+        .collect();
+    spans.sort_unstable();
+    let mut covered = 0;
+    let mut end = 0;
+    for (start, stop) in spans {
+        covered += stop.saturating_sub(start.max(end));
+        end = end.max(stop);
+    }
+
+    Some(TypeLayout {
+        name: root["typename"].as_str()?.to_string(),
+        size,
+        align: root["alignment"].as_u64()?,
+        padding: if fields.is_empty() {
+            0
+        } else {
+            size - covered.min(size)
+        },
+        discriminant_size: None,
+        niche: None,
+        fields,
+        variants: Vec::new(),
+    })
+}
+
+fn analyzer_children(nodes: &[Value], node: &Value) -> Vec<FieldLayout> {
+    let (Some(start), Some(len)) = (
+        node["children_start"].as_i64(),
+        node["children_len"].as_u64(),
+    ) else {
+        return Vec::new();
+    };
+    if start < 0 {
+        return Vec::new();
+    }
+
+    nodes
+        .iter()
+        .skip(start as usize)
+        .take(len as usize)
+        .map(|child| FieldLayout {
+            name: child["item_name"].as_str().unwrap_or_default().to_string(),
+            type_name: child["typename"].as_str().map(str::to_string),
+            offset: child["offset"].as_u64().unwrap_or_default(),
+            size: child["size"].as_u64().unwrap_or_default(),
+            align: child["alignment"].as_u64(),
+            fields: analyzer_children(nodes, child),
+        })
+        .collect()
+}
+
+/// Layouts of `identity`, including every instance of a generic type.
+/// `-Zprint-type-sizes` names local types by their path within the crate.
+pub fn select_layouts(layouts: Vec<TypeLayout>, identity: &SymbolIdentity) -> Vec<TypeLayout> {
+    let mut segments: Vec<&str> = identity.module_path.iter().map(String::as_str).collect();
+    if matches!(segments.first(), Some(&"lib" | &"main")) {
+        segments.remove(0);
+    }
+    segments.push(&identity.item_name);
+    let local_path = segments.join("::");
+    let crate_path = format!("{}::{local_path}", identity.crate_name);
+
+    let candidates: Vec<TypeLayout> = layouts
+        .into_iter()
+        .filter(|layout| {
+            strip_generics(&layout.name).rsplit("::").next() == Some(identity.item_name.as_str())
+        })
+        .collect();
+    let exact: Vec<TypeLayout> = candidates
+        .iter()
+        .filter(|layout| {
+            let path = strip_generics(&layout.name);
+            path == local_path || path == crate_path
+        })
+        .cloned()
+        .collect();
+
+    if exact.is_empty() { candidates } else { exact }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_analyzer_layout, parse_type_sizes, select_layouts};
+    use crate::analyzer::symbol::{SymbolIdentity, SymbolKind};
+    use serde_json::json;
+
+    #[test]
+    fn parses_print_type_sizes() {
+        let output = "\
+print-type-size type: `Shape`: 16 bytes, alignment: 8 bytes
+print-type-size     discriminant: 4 bytes
+print-type-size     variant `Rect`: 12 bytes
+print-type-size         field `.w`: 4 bytes
+print-type-size         field `.h`: 8 bytes
+print-type-size     variant `Empty`: 0 bytes
+print-type-size type: `std::option::Option<&u8>`: 8 bytes, alignment: 8 bytes
+print-type-size     variant `Some`: 8 bytes
+print-type-size         field `.0`: 8 bytes
+print-type-size     variant `None`: 0 bytes
+print-type-size type: `shapes::Pair<u16>`: 4 bytes, alignment: 2 bytes
+print-type-size     field `.0`: 2 bytes
+print-type-size     field `.1`: 1 bytes
+print-type-size     end padding: 1 bytes
+print-type-size type: `shapes::Pair<u8>`: 2 bytes, alignment: 1 bytes
+print-type-size     field `.0`: 1 bytes
+print-type-size     field `.1`: 1 bytes
+";
+        let layouts = parse_type_sizes(output);
+        assert_eq!(layouts.len(), 4);
+
+        let shape = &layouts[0];
+        assert_eq!(
+            (shape.discriminant_size, shape.niche),
+            (Some(4), Some(false))
+        );
+        assert_eq!(shape.variants[0].fields[1].name, "h");
+        assert_eq!(shape.variants[0].fields[1].offset, 8);
+        assert_eq!(layouts[1].niche, Some(true));
+        assert_eq!((layouts[2].padding, layouts[2].fields[1].offset), (1, 2));
+
+        let pair = SymbolIdentity {
+            crate_name: "demo".to_string(),
+            module_path: vec!["lib".to_string(), "shapes".to_string()],
+            item_name: "Pair".to_string(),
+            kind: SymbolKind::Unknown,
+        };
+        let names: Vec<String> = select_layouts(layouts, &pair)
+            .into_iter()
+            .map(|layout| layout.name)
+            .collect();
+        assert_eq!(names, ["shapes::Pair<u16>", "shapes::Pair<u8>"]);
+    }
+
+    #[test]
+    fn converts_analyzer_layout() {
+        let nodes = json!({"nodes": [
+            {"item_name": "Wasteful", "typename": "Wasteful", "size": 24, "alignment": 8, "offset": 0, "parent_idx": -1, "children_start": 1, "children_len": 2},
+            {"item_name": "a", "typename": "u8", "size": 1, "alignment": 1, "offset": 0, "parent_idx": 0, "children_start": -1, "children_len": 0},
+            {"item_name": "b", "typename": "u64", "size": 8, "alignment": 8, "offset": 8, "parent_idx": 0, "children_start": -1, "children_len": 0}
+        ]});
+        let layout = from_analyzer_layout(&nodes).expect("layout converted");
+        assert_eq!(
+            (layout.name.as_str(), layout.size, layout.padding),
+            ("Wasteful", 24, 15)
+        );
+        assert_eq!(layout.fields[1].type_name.as_deref(), Some("u64"));
+    }
+}
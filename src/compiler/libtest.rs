@@ -0,0 +1,317 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use tokio::fs;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Ignored,
+}
+
+/// Result of a single test, from libtest's JSON events or its text output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestOutcome {
+    pub name: String,
+    pub status: TestStatus,
+    /// Seconds, only reported by the JSON format.
+    pub exec_time: Option<f64>,
+    /// Captured output printed before any panic.
+    pub stdout: Option<String>,
+    pub panic_message: Option<String>,
+    pub ignore_reason: Option<String>,
+}
+
+impl TestOutcome {
+    fn new(name: &str, status: TestStatus) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            exec_time: None,
+            stdout: None,
+            panic_message: None,
+            ignore_reason: None,
+        }
+    }
+
+    fn set_output(&mut self, output: &str) {
+        let (stdout, panic_message) = split_panic(output);
+        self.stdout = stdout;
+        self.panic_message = panic_message;
+    }
+}
+
+/// Parse the events printed by `--format json` (one JSON object per line),
+/// skipping anything that is not a finished test.
+pub fn parse_json_events(stdout: &str) -> Vec<TestOutcome> {
+    stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line.trim()).ok())
+        .filter(|event| matches!(event["type"].as_str(), Some("test" | "bench")))
+        .filter_map(|event| {
+            let name = event["name"].as_str()?;
+            let status = match event["event"].as_str()? {
+                "ok" => TestStatus::Passed,
+                "failed" | "timeout" => TestStatus::Failed,
+                "ignored" => TestStatus::Ignored,
+                _ if event["type"] == "bench" => TestStatus::Passed,
+                _ => return None,
+            };
+
+            let mut outcome = TestOutcome::new(name, status);
+            outcome.exec_time = event["exec_time"].as_f64();
+            if let Some(output) = event["stdout"].as_str() {
+                outcome.set_output(output);
+            }
+            if let Some(message) = event["message"].as_str() {
+                match status {
+                    TestStatus::Ignored => outcome.ignore_reason = Some(message.to_string()),
+                    _ if outcome.panic_message.is_none() => {
+                        outcome.panic_message = Some(message.to_string())
+                    }
+                    _ => {}
+                }
+            }
+            Some(outcome)
+        })
+        .collect()
+}
+
+/// Parse libtest's default text output: `test name ... ok` lines followed by
+/// `---- name stdout ----` sections for failures.
+pub fn parse_text_output(stdout: &str) -> Vec<TestOutcome> {
+    let mut outcomes = Vec::new();
+    let mut failure: Option<(usize, Vec<&str>)> = None;
+
+    let finish = |failure: Option<(usize, Vec<&str>)>, outcomes: &mut Vec<TestOutcome>| {
+        if let Some((index, lines)) = failure {
+            outcomes[index].set_output(&lines.join("\n"));
+        }
+    };
+
+    for line in stdout.lines() {
+        if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|rest| rest.strip_suffix(" stdout ----"))
+        {
+            finish(failure.take(), &mut outcomes);
+            failure = outcomes
+                .iter()
+                .rposition(|outcome| outcome.name == name)
+                .map(|index| (index, Vec::new()));
+            continue;
+        }
+        if line == "failures:" || line.starts_with("test result: ") {
+            finish(failure.take(), &mut outcomes);
+            continue;
+        }
+        if let Some((_, lines)) = failure.as_mut() {
+            lines.push(line);
+            continue;
+        }
+
+        let Some((name, result)) = line
+            .strip_prefix("test ")
+            .and_then(|rest| rest.rsplit_once(" ... "))
+        else {
+            continue;
+        };
+        let outcome = match result.trim() {
+            "ok" => TestOutcome::new(name, TestStatus::Passed),
+            "FAILED" => TestOutcome::new(name, TestStatus::Failed),
+            result if result.starts_with("ignored") => {
+                let mut outcome = TestOutcome::new(name, TestStatus::Ignored);
+                outcome.ignore_reason = result
+                    .strip_prefix("ignored, ")
+                    .map(|reason| reason.to_string());
+                outcome
+            }
+            result if result.starts_with("bench:") => TestOutcome::new(name, TestStatus::Passed),
+            _ => continue,
+        };
+        outcomes.push(outcome);
+    }
+    finish(failure, &mut outcomes);
+    outcomes
+}
+
+/// Split captured test output into what the test printed and the panic
+/// message, dropping backtraces and the `RUST_BACKTRACE` hint.
+fn split_panic(output: &str) -> (Option<String>, Option<String>) {
+    let lines: Vec<&str> = output.lines().collect();
+    let panic_start = lines
+        .iter()
+        .position(|line| line.starts_with("thread '") && line.contains(" panicked at"));
+
+    let non_empty = |lines: &[&str]| {
+        let text = lines.join("\n").trim().to_string();
+        (!text.is_empty()).then_some(text)
+    };
+    match panic_start {
+        Some(start) => {
+            let panic_lines: Vec<&str> = lines[start..]
+                .iter()
+                .take_while(|line| !line.starts_with("note: ") && **line != "stack backtrace:")
+                .copied()
+                .collect();
+            let mut panic_message = non_empty(&panic_lines);
+            if let Some(message) = panic_message.as_mut()
+                && let Some(index) = message.find("panicked at")
+            {
+                *message = message[index..].to_string();
+            }
+            (non_empty(&lines[..start]), panic_message)
+        }
+        None => (non_empty(&lines), None),
+    }
+}
+
+/// Summary line, then failures with their panic message and output, then
+/// passed and ignored tests.
+pub fn render_test_report(outcomes: &[TestOutcome]) -> String {
+    let count = |status| {
+        outcomes
+            .iter()
+            .filter(|outcome| outcome.status == status)
+            .count()
+    };
+    let mut output = format!(
+        "{} passed, {} failed, {} ignored",
+        count(TestStatus::Passed),
+        count(TestStatus::Failed),
+        count(TestStatus::Ignored)
+    );
+
+    let mut ordered: Vec<&TestOutcome> = outcomes.iter().collect();
+    ordered.sort_by_key(|outcome| match outcome.status {
+        TestStatus::Failed => 0,
+        TestStatus::Passed => 1,
+        TestStatus::Ignored => 2,
+    });
+    for outcome in ordered {
+        let label = match outcome.status {
+            TestStatus::Passed => "ok",
+            TestStatus::Failed => "FAILED",
+            TestStatus::Ignored => "ignored",
+        };
+        output.push_str(&format!("\n{label} {}", outcome.name));
+        if let Some(seconds) = outcome.exec_time {
+            output.push_str(&format!(" ({seconds:.3}s)"));
+        }
+        if let Some(reason) = &outcome.ignore_reason {
+            output.push_str(&format!(": {reason}"));
+        }
+        if outcome.status != TestStatus::Failed {
+            continue;
+        }
+        if let Some(message) = &outcome.panic_message {
+            for line in message.lines() {
+                output.push_str(&format!("\n    {line}"));
+            }
+        }
+        if let Some(stdout) = &outcome.stdout {
+            output.push_str("\n    stdout:");
+            for line in stdout.lines() {
+                output.push_str(&format!("\n    | {line}"));
+            }
+        }
+    }
+    output
+}
+
+/// Failed tests of the last run and the options it used, so they can be
+/// rerun on their own.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FailedTests {
+    pub package: Option<String>,
+    pub features: Vec<String>,
+    pub names: Vec<String>,
+}
+
+impl FailedTests {
+    pub const FILE_NAME: &'static str = "last-failed-tests.json";
+
+    pub async fn load(target_dir: &Path) -> Result<Option<Self>> {
+        let path = target_dir.join(Self::FILE_NAME);
+        match fs::read_to_string(&path).await {
+            Ok(text) => Ok(Some(
+                serde_json::from_str(&text)
+                    .with_context(|| format!("parsing {}", path.display()))?,
+            )),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error).with_context(|| format!("reading {}", path.display())),
+        }
+    }
+
+    pub async fn save(&self, target_dir: &Path) -> Result<()> {
+        let path = target_dir.join(Self::FILE_NAME);
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .await
+            .with_context(|| format!("writing {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TestStatus, parse_json_events, parse_text_output, render_test_report};
+
+    #[test]
+    fn parses_text_output_with_failures() {
+        let stdout = "
+running 3 tests
+test tests::adds ... ok
+test tests::slow ... ignored, needs network
+test tests::divides ... FAILED
+
+failures:
+
+---- tests::divides stdout ----
+dividing 1 by 0
+thread 'tests::divides' panicked at src/lib.rs:10:5:
+attempt to divide by zero
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+failures:
+    tests::divides
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.00s
+";
+        let outcomes = parse_text_output(stdout);
+        assert_eq!(outcomes.len(), 3);
+        assert_eq!(outcomes[1].ignore_reason.as_deref(), Some("needs network"));
+        assert_eq!(outcomes[2].status, TestStatus::Failed);
+        assert_eq!(outcomes[2].stdout.as_deref(), Some("dividing 1 by 0"));
+        assert_eq!(
+            outcomes[2].panic_message.as_deref(),
+            Some("panicked at src/lib.rs:10:5:\nattempt to divide by zero")
+        );
+
+        assert_eq!(
+            render_test_report(&outcomes),
+            "1 passed, 1 failed, 1 ignored\nFAILED tests::divides\n    panicked at src/lib.rs:10:5:\n    attempt to divide by zero\n    stdout:\n    | dividing 1 by 0\nok tests::adds\nignored tests::slow: needs network"
+        );
+    }
+
+    #[test]
+    fn parses_json_events() {
+        let stdout = r#"{ "type": "suite", "event": "started", "test_count": 2 }
+{ "type": "test", "event": "started", "name": "tests::adds" }
+{ "type": "test", "name": "tests::adds", "event": "ok", "exec_time": 0.001 }
+{ "type": "test", "event": "started", "name": "tests::divides" }
+{ "type": "test", "name": "tests::divides", "event": "failed", "exec_time": 0.002, "stdout": "thread 'tests::divides' (4242) panicked at src/lib.rs:10:5:\nattempt to divide by zero\nstack backtrace:\n   0: rust_begin_unwind\n" }
+{ "type": "suite", "event": "failed", "passed": 1, "failed": 1, "ignored": 0, "measured": 0, "filtered_out": 0, "exec_time": 0.003 }
+"#;
+        let outcomes = parse_json_events(stdout);
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(outcomes[0].exec_time, Some(0.001));
+        assert_eq!(outcomes[1].status, TestStatus::Failed);
+        assert_eq!(outcomes[1].stdout, None);
+        assert_eq!(
+            outcomes[1].panic_message.as_deref(),
+            Some("panicked at src/lib.rs:10:5:\nattempt to divide by zero")
+        );
+    }
+}
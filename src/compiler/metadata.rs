@@ -0,0 +1,298 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{Arc, Mutex, OnceLock},
+    time::SystemTime,
+};
+use tokio::{fs, process::Command};
+
+use super::runner::run_with_timeout;
+use crate::inspection::InspectionLimits;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TargetKind {
+    Lib,
+    Bin,
+    Example,
+    Test,
+    Bench,
+}
+
+impl TargetKind {
+    fn from_metadata(kinds: &[Value]) -> Option<Self> {
+        kinds.iter().filter_map(Value::as_str).find_map(|kind| {
+            Some(match kind {
+                "lib" | "rlib" | "dylib" | "cdylib" | "staticlib" | "proc-macro" => TargetKind::Lib,
+                "bin" => TargetKind::Bin,
+                "example" => TargetKind::Example,
+                "test" => TargetKind::Test,
+                "bench" => TargetKind::Bench,
+                _ => return None,
+            })
+        })
+    }
+}
+
+/// A cargo target, selected with `--lib`, `--bin NAME` and friends.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CargoTarget {
+    pub kind: TargetKind,
+    pub name: String,
+}
+
+impl CargoTarget {
+    pub fn cargo_args(&self) -> Vec<String> {
+        let flag = match self.kind {
+            TargetKind::Lib => return vec!["--lib".to_string()],
+            TargetKind::Bin => "--bin",
+            TargetKind::Example => "--example",
+            TargetKind::Test => "--test",
+            TargetKind::Bench => "--bench",
+        };
+        vec![flag.to_string(), self.name.clone()]
+    }
+}
+
+/// Package and target that compile a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwningTarget {
+    pub package: String,
+    pub manifest_path: PathBuf,
+    pub target: CargoTarget,
+}
+
+/// Find the target whose crate root is `file`, or failing that the one whose
+/// root directory is the closest ancestor of it. Ties go to the library,
+/// since binaries next to it usually reach shared modules through it.
+pub fn owning_target(metadata: &Value, file: &Path) -> Option<OwningTarget> {
+    let packages = metadata["packages"].as_array()?;
+    let mut best: Option<((bool, usize, bool), OwningTarget)> = None;
+
+    for package in packages {
+        let (Some(package_name), Some(manifest_path), Some(targets)) = (
+            package["name"].as_str(),
+            package["manifest_path"].as_str(),
+            package["targets"].as_array(),
+        ) else {
+            continue;
+        };
+
+        for target in targets {
+            let (Some(name), Some(src_path), Some(kinds)) = (
+                target["name"].as_str(),
+                target["src_path"].as_str(),
+                target["kind"].as_array(),
+            ) else {
+                continue;
+            };
+            let Some(kind) = TargetKind::from_metadata(kinds) else {
+                continue;
+            };
+
+            let src_path = Path::new(src_path);
+            let Some(root_dir) = src_path.parent().filter(|dir| file.starts_with(dir)) else {
+                continue;
+            };
+            let rank = (
+                src_path == file,
+                root_dir.components().count(),
+                kind == TargetKind::Lib,
+            );
+            if best
+                .as_ref()
+                .is_some_and(|(best_rank, _)| *best_rank >= rank)
+            {
+                continue;
+            }
+            best = Some((
+                rank,
+                OwningTarget {
+                    package: package_name.to_string(),
+                    manifest_path: PathBuf::from(manifest_path),
+                    target: CargoTarget {
+                        kind,
+                        name: name.to_string(),
+                    },
+                },
+            ));
+        }
+    }
+
+    best.map(|(_, owner)| owner)
+}
+
+/// Modification time and length of a manifest file, `None` when it is missing.
+type ManifestStamp = Option<(SystemTime, u64)>;
+
+/// `cargo metadata` output with the manifest files it was read from, stamped
+/// as they were when it was read.
+#[derive(Clone)]
+struct CachedMetadata {
+    metadata: Arc<Value>,
+    manifests: Vec<(PathBuf, ManifestStamp)>,
+}
+
+/// `cargo metadata --no-deps` for the workspace at `workspace_root`. The
+/// output is reused until a `Cargo.toml` of the workspace or its `Cargo.lock`
+/// changes, so inspections served from the artifact cache stay cheap.
+pub async fn load_metadata(workspace_root: &Path, limits: &InspectionLimits) -> Result<Arc<Value>> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, CachedMetadata>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+
+    let cached = cache
+        .lock()
+        .expect("metadata cache poisoned")
+        .get(workspace_root)
+        .cloned();
+    if let Some(cached) = cached {
+        let mut fresh = true;
+        for (path, stamp) in &cached.manifests {
+            fresh &= manifest_stamp(path).await == *stamp;
+        }
+        if fresh {
+            return Ok(cached.metadata);
+        }
+    }
+
+    let metadata = Arc::new(run_cargo_metadata(workspace_root, limits).await?);
+    let mut manifests = Vec::new();
+    for path in manifest_files(workspace_root, &metadata) {
+        let stamp = manifest_stamp(&path).await;
+        manifests.push((path, stamp));
+    }
+    cache.lock().expect("metadata cache poisoned").insert(
+        workspace_root.to_path_buf(),
+        CachedMetadata {
+            metadata: metadata.clone(),
+            manifests,
+        },
+    );
+    Ok(metadata)
+}
+
+/// Files whose changes can change the metadata: the manifests of the
+/// workspace root and every member, and the lock file.
+fn manifest_files(workspace_root: &Path, metadata: &Value) -> Vec<PathBuf> {
+    let cargo_root = metadata["workspace_root"]
+        .as_str()
+        .map_or_else(|| workspace_root.to_path_buf(), PathBuf::from);
+    let mut files = vec![
+        workspace_root.join("Cargo.toml"),
+        cargo_root.join("Cargo.toml"),
+        cargo_root.join("Cargo.lock"),
+    ];
+    files.extend(
+        metadata["packages"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|package| package["manifest_path"].as_str())
+            .map(PathBuf::from),
+    );
+    files.sort();
+    files.dedup();
+    files
+}
+
+async fn manifest_stamp(path: &Path) -> ManifestStamp {
+    let metadata = fs::metadata(path).await.ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+async fn run_cargo_metadata(workspace_root: &Path, limits: &InspectionLimits) -> Result<Value> {
+    let mut command = Command::new("cargo");
+    command
+        .args([
+            "metadata",
+            "--format-version",
+            "1",
+            "--no-deps",
+            "--offline",
+        ])
+        .current_dir(workspace_root)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let (status, stdout, stderr) =
+        run_with_timeout(command, limits.timeout(), "running cargo metadata").await?;
+    if !status.success() {
+        anyhow::bail!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&stderr).trim()
+        );
+    }
+    serde_json::from_slice(&stdout).context("parsing cargo metadata output")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CargoTarget, TargetKind, manifest_files, owning_target};
+    use serde_json::json;
+    use std::path::Path;
+
+    #[test]
+    fn picks_the_closest_target_and_prefers_the_library() {
+        let metadata = json!({
+            "packages": [{
+                "name": "app",
+                "manifest_path": "/ws/app/Cargo.toml",
+                "targets": [
+                    {"name": "app", "kind": ["bin"], "src_path": "/ws/app/src/main.rs"},
+                    {"name": "app", "kind": ["lib"], "src_path": "/ws/app/src/lib.rs"},
+                    {"name": "tool", "kind": ["bin"], "src_path": "/ws/app/src/bin/tool/main.rs"},
+                    {"name": "demo", "kind": ["example"], "src_path": "/ws/app/examples/demo.rs"},
+                    {"name": "build-script-build", "kind": ["custom-build"], "src_path": "/ws/app/build.rs"}
+                ]
+            }]
+        });
+        let target_of =
+            |file: &str| owning_target(&metadata, Path::new(file)).map(|owner| owner.target);
+
+        assert_eq!(
+            target_of("/ws/app/src/main.rs"),
+            Some(CargoTarget {
+                kind: TargetKind::Bin,
+                name: "app".to_string()
+            })
+        );
+        assert_eq!(
+            target_of("/ws/app/src/parser/mod.rs").map(|target| target.cargo_args()),
+            Some(vec!["--lib".to_string()])
+        );
+        assert_eq!(
+            target_of("/ws/app/src/bin/tool/cli.rs").map(|target| target.cargo_args()),
+            Some(vec!["--bin".to_string(), "tool".to_string()])
+        );
+        assert_eq!(
+            target_of("/ws/app/examples/demo.rs").map(|target| target.kind),
+            Some(TargetKind::Example)
+        );
+        assert_eq!(target_of("/ws/app/build.rs"), None);
+        assert_eq!(target_of("/elsewhere/lib.rs"), None);
+    }
+
+    #[test]
+    fn watches_member_manifests_and_the_lock_file() {
+        let metadata = json!({
+            "workspace_root": "/ws",
+            "packages": [
+                {"name": "app", "manifest_path": "/ws/app/Cargo.toml"},
+                {"name": "core", "manifest_path": "/ws/core/Cargo.toml"}
+            ]
+        });
+        let files = manifest_files(Path::new("/ws/app"), &metadata);
+        assert_eq!(
+            files,
+            [
+                "/ws/Cargo.lock",
+                "/ws/Cargo.toml",
+                "/ws/app/Cargo.toml",
+                "/ws/core/Cargo.toml"
+            ]
+            .map(std::path::PathBuf::from)
+        );
+    }
+}
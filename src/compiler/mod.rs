@@ -1,4 +1,12 @@
+pub mod annotate;
+pub mod cache;
+pub mod demangle;
+pub mod diff;
 pub mod extract;
+pub mod layout;
+pub mod libtest;
+pub mod metadata;
 pub mod runner;
+pub mod size;
 
 pub use runner::*;
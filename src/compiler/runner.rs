@@ -1,11 +1,12 @@
+use super::{cache::ArtifactCache, metadata::CargoTarget};
 use crate::inspection::InspectionLimits;
 use anyhow::{Context, Result};
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, HashMap},
     fmt,
     path::{Path, PathBuf},
     process::Stdio,
-    time::Duration,
+    time::{Duration, SystemTime},
 };
 use tokio::{fs, io::AsyncReadExt, process::Command, time::timeout};
 
@@ -99,6 +100,31 @@ impl CompilerRunner {
             command_line.push(package);
         }
 
+        if let Some(cargo_target) = request.cargo_target {
+            let args = cargo_target.cargo_args();
+            command.args(&args);
+            command_line.extend(args);
+        }
+
+        if !request.features.is_empty() {
+            command.arg("--features");
+            command.arg(request.features.join(","));
+            command_line.push("--features".to_string());
+            command_line.push(request.features.join(","));
+        }
+
+        if request.no_default_features {
+            command.arg("--no-default-features");
+            command_line.push("--no-default-features".to_string());
+        }
+
+        if let Some(profile) = request.profile {
+            command.arg("--profile");
+            command.arg(&profile);
+            command_line.push("--profile".to_string());
+            command_line.push(profile);
+        }
+
         if let Some(target_triple) = request.target_triple {
             command.arg("--target");
             command.arg(&target_triple);
@@ -136,47 +162,12 @@ impl CompilerRunner {
         }
         command.env("CARGO_TARGET_DIR", &self.target_dir);
 
-        let mut child = command
-            .spawn()
-            .context("running cargo rustc with inspection settings")?;
-
-        let mut stdout = child
-            .stdout
-            .take()
-            .ok_or_else(|| anyhow::anyhow!("failed to capture compiler stdout"))?;
-        let mut stderr = child
-            .stderr
-            .take()
-            .ok_or_else(|| anyhow::anyhow!("failed to capture compiler stderr"))?;
-
-        let stdout_task = tokio::spawn(async move {
-            let mut buf = Vec::new();
-            stdout.read_to_end(&mut buf).await?;
-            Ok::<_, anyhow::Error>(buf)
-        });
-        let stderr_task = tokio::spawn(async move {
-            let mut buf = Vec::new();
-            stderr.read_to_end(&mut buf).await?;
-            Ok::<_, anyhow::Error>(buf)
-        });
-
-        let status = match timeout(limits.timeout(), child.wait()).await {
-            Ok(result) => result.context("running cargo rustc with inspection settings")?,
-            Err(_) => {
-                let _ = child.kill().await;
-                let _ = child.wait().await;
-                return Err(RunnerError::Timeout(limits.timeout()).into());
-            }
-        };
-
-        let stdout = stdout_task
-            .await
-            .context("joining compiler stdout task")?
-            .context("reading compiler stdout")?;
-        let stderr = stderr_task
-            .await
-            .context("joining compiler stderr task")?
-            .context("reading compiler stderr")?;
+        let (status, stdout, stderr) = run_with_timeout(
+            command,
+            limits.timeout(),
+            "running cargo rustc with inspection settings",
+        )
+        .await?;
 
         let after = collect_files(&self.target_dir).await.unwrap_or_default();
         let artifacts = diff_paths(before, after, &self.target_dir);
@@ -186,16 +177,180 @@ impl CompilerRunner {
             stdout: String::from_utf8_lossy(&stdout).into_owned(),
             stderr: String::from_utf8_lossy(&stderr).into_owned(),
             artifacts,
+            artifact_dir: self.target_dir.clone(),
+            command: command_line,
+        })
+    }
+
+    /// Drop cargo's fingerprints of the workspace member `package` so the next
+    /// run invokes rustc even though the build is fresh. Dependencies keep
+    /// theirs and are not rebuilt.
+    pub async fn invalidate(&self, package: &str) -> Result<()> {
+        let mut stack = vec![self.target_dir.clone()];
+        let cache_dir = self.target_dir.join(ArtifactCache::DIR_NAME);
+
+        while let Some(dir) = stack.pop() {
+            let Ok(mut entries) = fs::read_dir(&dir).await else {
+                continue;
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if !entry.file_type().await?.is_dir() || path == cache_dir {
+                    continue;
+                }
+                if entry.file_name() != ".fingerprint" {
+                    if entry.file_name() != "incremental" {
+                        stack.push(path);
+                    }
+                    continue;
+                }
+
+                let mut fingerprints = fs::read_dir(&path).await?;
+                while let Some(fingerprint) = fingerprints.next_entry().await? {
+                    if is_fingerprint_of(&fingerprint.file_name().to_string_lossy(), package) {
+                        fs::remove_dir_all(fingerprint.path())
+                            .await
+                            .with_context(|| {
+                                format!("removing {}", fingerprint.path().display())
+                            })?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Execute `cargo test` in the same isolated target directory, running
+    /// every test binary even when an earlier one fails.
+    pub async fn run_tests(
+        &self,
+        request: TestRequest,
+        limits: &InspectionLimits,
+    ) -> Result<RunResult> {
+        fs::create_dir_all(&self.target_dir)
+            .await
+            .with_context(|| format!("creating target dir {}", self.target_dir.display()))?;
+
+        let mut args = vec![
+            "test".to_string(),
+            "--offline".to_string(),
+            "--no-fail-fast".to_string(),
+        ];
+        if let Some(manifest_path) = request.manifest_path {
+            args.push("--manifest-path".to_string());
+            args.push(manifest_path.display().to_string());
+        }
+        if let Some(package) = request.package {
+            args.push("--package".to_string());
+            args.push(package);
+        }
+        if !request.features.is_empty() {
+            args.push("--features".to_string());
+            args.push(request.features.join(","));
+        }
+
+        args.push("--".to_string());
+        args.extend(request.filters);
+        if request.exact {
+            args.push("--exact".to_string());
+        }
+        if request.json_output {
+            args.extend(
+                [
+                    "-Z",
+                    "unstable-options",
+                    "--format",
+                    "json",
+                    "--report-time",
+                ]
+                .map(str::to_string),
+            );
+        }
+
+        let mut command = Command::new("cargo");
+        command.args(&args);
+        command.stdout(Stdio::piped());
+        command.stderr(Stdio::piped());
+        for (key, value) in request.env {
+            command.env(key, value);
+        }
+        command.env("CARGO_TARGET_DIR", &self.target_dir);
+
+        let (status, stdout, stderr) =
+            run_with_timeout(command, limits.timeout(), "running cargo test").await?;
+
+        let mut command_line = vec!["cargo".to_string()];
+        command_line.extend(args);
+        Ok(RunResult {
+            status,
+            stdout: String::from_utf8_lossy(&stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&stderr).into_owned(),
+            artifacts: Vec::new(),
+            artifact_dir: self.target_dir.clone(),
             command: command_line,
         })
     }
 }
 
+/// Spawn `command`, collect its output and kill it once `limit` elapses.
+pub(super) async fn run_with_timeout(
+    mut command: Command,
+    limit: Duration,
+    action: &'static str,
+) -> Result<(std::process::ExitStatus, Vec<u8>, Vec<u8>)> {
+    let mut child = command.spawn().context(action)?;
+
+    let mut stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| anyhow::anyhow!("failed to capture compiler stdout"))?;
+    let mut stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| anyhow::anyhow!("failed to capture compiler stderr"))?;
+
+    let stdout_task = tokio::spawn(async move {
+        let mut buf = Vec::new();
+        stdout.read_to_end(&mut buf).await?;
+        Ok::<_, anyhow::Error>(buf)
+    });
+    let stderr_task = tokio::spawn(async move {
+        let mut buf = Vec::new();
+        stderr.read_to_end(&mut buf).await?;
+        Ok::<_, anyhow::Error>(buf)
+    });
+
+    let status = match timeout(limit, child.wait()).await {
+        Ok(result) => result.context(action)?,
+        Err(_) => {
+            let _ = child.kill().await;
+            let _ = child.wait().await;
+            return Err(RunnerError::Timeout(limit).into());
+        }
+    };
+
+    let stdout = stdout_task
+        .await
+        .context("joining compiler stdout task")?
+        .context("reading compiler stdout")?;
+    let stderr = stderr_task
+        .await
+        .context("joining compiler stderr task")?
+        .context("reading compiler stderr")?;
+
+    Ok((status, stdout, stderr))
+}
+
 /// Parameters for a compiler run.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Hash)]
 pub struct RunRequest {
     pub manifest_path: Option<PathBuf>,
     pub package: Option<String>,
+    /// Target within the package; required when it has more than one.
+    pub cargo_target: Option<CargoTarget>,
+    pub features: Vec<String>,
+    pub no_default_features: bool,
+    pub profile: Option<String>,
     pub target_triple: Option<String>,
     pub opt_level: Option<String>,
     pub emit: Option<String>,
@@ -204,19 +359,65 @@ pub struct RunRequest {
     pub env: BTreeMap<String, String>,
 }
 
-/// Result of invoking `cargo rustc`.
+/// Parameters for a `cargo test` run.
+#[derive(Debug, Clone, Default)]
+pub struct TestRequest {
+    pub manifest_path: Option<PathBuf>,
+    pub package: Option<String>,
+    pub features: Vec<String>,
+    /// Test name filters; a test runs when it matches any of them.
+    pub filters: Vec<String>,
+    pub exact: bool,
+    /// Ask libtest for JSON events, which requires a nightly toolchain.
+    pub json_output: bool,
+    pub env: BTreeMap<String, String>,
+}
+
+/// Result of invoking `cargo rustc` or `cargo test`.
 #[derive(Debug, Clone)]
 pub struct RunResult {
     pub status: std::process::ExitStatus,
     pub stdout: String,
     pub stderr: String,
     pub artifacts: Vec<PathBuf>,
+    /// Directory the artifacts live under: the target dir, or the cache entry
+    /// they were restored from.
+    pub artifact_dir: PathBuf,
     pub command: Vec<String>,
 }
 
-async fn collect_files(root: &Path) -> Result<HashSet<PathBuf>> {
+impl RunResult {
+    /// Whether cargo ran rustc on `package` (on anything when `None`), rather
+    /// than finding the build fresh.
+    pub fn compiled(&self, package: Option<&str>) -> bool {
+        self.stderr.lines().any(|line| {
+            let Some(crate_line) = line.trim_start().strip_prefix("Compiling ") else {
+                return false;
+            };
+            package.is_none_or(|package| {
+                crate_line
+                    .strip_prefix(package)
+                    .is_some_and(|rest| rest.starts_with(" v"))
+            })
+        })
+    }
+}
+
+/// Whether a `.fingerprint` entry belongs to `package`. Entries are named
+/// `<package>-<16 hex digit hash>`, so `tokio` must not match
+/// `tokio-macros-<hash>`.
+fn is_fingerprint_of(name: &str, package: &str) -> bool {
+    name.strip_prefix(package)
+        .and_then(|rest| rest.strip_prefix('-'))
+        .is_some_and(|hash| hash.len() == 16 && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// Files under `root` with their modification times, leaving out the
+/// artifact cache and rustc's incremental working copies of the outputs.
+async fn collect_files(root: &Path) -> Result<HashMap<PathBuf, Option<SystemTime>>> {
     let mut stack = vec![root.to_path_buf()];
-    let mut files = HashSet::new();
+    let mut files = HashMap::new();
+    let cache_dir = root.join(ArtifactCache::DIR_NAME);
 
     while let Some(path) = stack.pop() {
         let mut entries = match fs::read_dir(&path).await {
@@ -227,9 +428,12 @@ async fn collect_files(root: &Path) -> Result<HashSet<PathBuf>> {
         while let Some(entry) = entries.next_entry().await? {
             let entry_path = entry.path();
             if entry.file_type().await?.is_dir() {
-                stack.push(entry_path);
+                if entry_path != cache_dir && entry.file_name() != "incremental" {
+                    stack.push(entry_path);
+                }
             } else if let Ok(relative) = entry_path.strip_prefix(root) {
-                files.insert(relative.to_path_buf());
+                let modified = entry.metadata().await?.modified().ok();
+                files.insert(relative.to_path_buf(), modified);
             }
         }
     }
@@ -237,9 +441,30 @@ async fn collect_files(root: &Path) -> Result<HashSet<PathBuf>> {
     Ok(files)
 }
 
-fn diff_paths(before: HashSet<PathBuf>, after: HashSet<PathBuf>, root: &Path) -> Vec<PathBuf> {
+/// Files that are new in `after` or were rewritten since `before`, as a
+/// rebuild with unchanged output paths overwrites earlier artifacts.
+fn diff_paths(
+    before: HashMap<PathBuf, Option<SystemTime>>,
+    after: HashMap<PathBuf, Option<SystemTime>>,
+    root: &Path,
+) -> Vec<PathBuf> {
     after
-        .difference(&before)
-        .map(|rel| root.join(rel))
+        .into_iter()
+        .filter(|(rel, modified)| before.get(rel) != Some(modified))
+        .map(|(rel, _)| root.join(rel))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::is_fingerprint_of;
+
+    #[test]
+    fn matches_only_the_named_package_fingerprints() {
+        assert!(is_fingerprint_of("tokio-0123456789abcdef", "tokio"));
+        assert!(is_fingerprint_of("my-app-0123456789abcdef", "my-app"));
+        assert!(!is_fingerprint_of("tokio-macros-0123456789abcdef", "tokio"));
+        assert!(!is_fingerprint_of("tokio-0123", "tokio"));
+        assert!(!is_fingerprint_of("tokio_util-0123456789abcdef", "tokio"));
+    }
+}
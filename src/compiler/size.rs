@@ -0,0 +1,616 @@
+//! Binary size attribution: the function symbols of ELF objects and
+//! executables, demangled and summed by function and by crate.
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+};
+use tokio::fs;
+
+use super::demangle::demangle;
+
+/// Crate of symbols that are not Rust, such as libc or compiler builtins.
+const UNKNOWN_CRATE: &str = "[unknown]";
+
+/// A defined function symbol from an ELF symbol table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElfSymbol {
+    pub name: String,
+    pub size: u64,
+}
+
+/// Function symbols and executable section sizes of an ELF file.
+#[derive(Debug, Clone, Default)]
+pub struct ElfSizes {
+    pub symbols: Vec<ElfSymbol>,
+    /// Total size of the executable sections (`.text` and friends).
+    pub text_size: u64,
+}
+
+/// Read the symbol table (or the dynamic one, for stripped binaries) of a
+/// 32- or 64-bit ELF file of either byte order.
+pub fn parse_elf(data: &[u8]) -> Result<ElfSizes> {
+    if !data.starts_with(b"\x7fELF") {
+        bail!("not an ELF file");
+    }
+    let elf = Elf {
+        data,
+        is_64: match data.get(4) {
+            Some(1) => false,
+            Some(2) => true,
+            _ => bail!("unknown ELF class"),
+        },
+        big_endian: match data.get(5) {
+            Some(1) => false,
+            Some(2) => true,
+            _ => bail!("unknown ELF byte order"),
+        },
+    };
+
+    let (offset, entry_size, count) = if elf.is_64 {
+        (elf.u64(0x28)?, elf.u16(0x3a)?, elf.u16(0x3c)?)
+    } else {
+        (elf.u32(0x20)? as u64, elf.u16(0x2e)?, elf.u16(0x30)?)
+    };
+    let sections: Vec<Section> = (0..count as u64)
+        .map(|index| elf.section(offset_of(offset, index, entry_size as u64)?))
+        .collect::<Result<_>>()?;
+
+    const SHF_EXECINSTR: u64 = 0x4;
+    const SHT_NOBITS: u32 = 8;
+    let text_size = sections
+        .iter()
+        .filter(|section| section.flags & SHF_EXECINSTR != 0 && section.kind != SHT_NOBITS)
+        .fold(0u64, |total, section| total.saturating_add(section.size));
+
+    const SHT_SYMTAB: u32 = 2;
+    const SHT_DYNSYM: u32 = 11;
+    let Some(table) = sections
+        .iter()
+        .find(|section| section.kind == SHT_SYMTAB)
+        .or_else(|| sections.iter().find(|section| section.kind == SHT_DYNSYM))
+    else {
+        return Ok(ElfSizes {
+            symbols: Vec::new(),
+            text_size,
+        });
+    };
+    let strings = sections
+        .get(table.link as usize)
+        .context("symbol table links to a missing string table")?;
+
+    const STT_FUNC: u8 = 2;
+    const SHN_UNDEF: u16 = 0;
+    let entry_size = if elf.is_64 { 24 } else { 16 };
+    let mut symbols = Vec::new();
+    // Aliases share an address; count each function once.
+    let mut seen = HashSet::new();
+    for index in 1..table.size / entry_size {
+        let at = elf.entry(offset_of(table.offset, index, entry_size)?, entry_size)?;
+        let (name, info, section, value, size) = if elf.is_64 {
+            (
+                elf.u32(at)?,
+                elf.u8(at + 4)?,
+                elf.u16(at + 6)?,
+                elf.u64(at + 8)?,
+                elf.u64(at + 16)?,
+            )
+        } else {
+            (
+                elf.u32(at)?,
+                elf.u8(at + 12)?,
+                elf.u16(at + 14)?,
+                elf.u32(at + 4)? as u64,
+                elf.u32(at + 8)? as u64,
+            )
+        };
+        if info & 0xf != STT_FUNC || section == SHN_UNDEF || size == 0 {
+            continue;
+        }
+        if !seen.insert((section, value)) {
+            continue;
+        }
+        symbols.push(ElfSymbol {
+            name: elf.string(strings, name)?,
+            size,
+        });
+    }
+
+    Ok(ElfSizes { symbols, text_size })
+}
+
+struct Elf<'a> {
+    data: &'a [u8],
+    is_64: bool,
+    big_endian: bool,
+}
+
+struct Section {
+    kind: u32,
+    flags: u64,
+    offset: u64,
+    size: u64,
+    link: u32,
+}
+
+impl Elf<'_> {
+    fn bytes<const N: usize>(&self, at: u64) -> Result<[u8; N]> {
+        let start = usize::try_from(at)?;
+        let mut bytes: [u8; N] = start
+            .checked_add(N)
+            .and_then(|end| self.data.get(start..end))
+            .context("ELF file is truncated")?
+            .try_into()?;
+        if !self.big_endian {
+            bytes.reverse();
+        }
+        Ok(bytes)
+    }
+
+    fn u8(&self, at: u64) -> Result<u8> {
+        Ok(self.bytes::<1>(at)?[0])
+    }
+
+    fn u16(&self, at: u64) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.bytes(at)?))
+    }
+
+    fn u32(&self, at: u64) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.bytes(at)?))
+    }
+
+    fn u64(&self, at: u64) -> Result<u64> {
+        Ok(u64::from_be_bytes(self.bytes(at)?))
+    }
+
+    /// Check that an entry of `size` bytes at `at` lies inside the file, so
+    /// its fields can be read at `at + field` without overflowing.
+    fn entry(&self, at: u64, size: u64) -> Result<u64> {
+        at.checked_add(size)
+            .filter(|&end| end <= self.data.len() as u64)
+            .map(|_| at)
+            .context("ELF file is truncated")
+    }
+
+    fn section(&self, at: u64) -> Result<Section> {
+        let at = self.entry(at, if self.is_64 { 64 } else { 40 })?;
+        Ok(if self.is_64 {
+            Section {
+                kind: self.u32(at + 4)?,
+                flags: self.u64(at + 8)?,
+                offset: self.u64(at + 24)?,
+                size: self.u64(at + 32)?,
+                link: self.u32(at + 40)?,
+            }
+        } else {
+            Section {
+                kind: self.u32(at + 4)?,
+                flags: self.u32(at + 8)? as u64,
+                offset: self.u32(at + 16)? as u64,
+                size: self.u32(at + 20)? as u64,
+                link: self.u32(at + 24)?,
+            }
+        })
+    }
+
+    fn string(&self, table: &Section, offset: u32) -> Result<String> {
+        let start = table
+            .offset
+            .checked_add(offset as u64)
+            .context("ELF file is truncated")?;
+        let start = usize::try_from(start)?;
+        let bytes = self.data.get(start..).context("ELF file is truncated")?;
+        let end = bytes
+            .iter()
+            .position(|&byte| byte == 0)
+            .context("unterminated symbol name")?;
+        Ok(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    }
+}
+
+/// `base + index * stride` for offsets read from the file, which may be bogus.
+fn offset_of(base: u64, index: u64, stride: u64) -> Result<u64> {
+    index
+        .checked_mul(stride)
+        .and_then(|offset| base.checked_add(offset))
+        .context("ELF file is truncated")
+}
+
+/// Function and crate sizes of a build, largest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeReport {
+    pub artifacts: Vec<String>,
+    pub file_size: u64,
+    pub text_size: u64,
+    /// Bytes of `text_size` covered by function symbols.
+    pub function_size: u64,
+    pub functions: Vec<FunctionSize>,
+    pub crates: Vec<CrateSize>,
+    /// Entries dropped from `functions` and `crates` by [`SizeReport::top`].
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub omitted_functions: usize,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub omitted_crates: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionSize {
+    pub name: String,
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateSize {
+    pub name: String,
+    pub size: u64,
+    pub functions: usize,
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+impl SizeReport {
+    /// Directory name of stored reports inside the inspection target dir.
+    pub const DIR_NAME: &'static str = "size-reports";
+
+    /// Aggregate the symbols of `artifacts`, given as (path, file size,
+    /// parsed sizes).
+    pub fn new(artifacts: Vec<(String, u64, ElfSizes)>) -> Self {
+        let mut functions: BTreeMap<String, FunctionSize> = BTreeMap::new();
+        let mut report = Self::default();
+
+        for (path, file_size, sizes) in artifacts {
+            report.artifacts.push(path);
+            report.file_size += file_size;
+            report.text_size += sizes.text_size;
+            for symbol in sizes.symbols {
+                let (name, crate_name) = match demangle(&symbol.name) {
+                    Some(demangled) => {
+                        let crate_name = crate_of(&demangled);
+                        (demangled, crate_name)
+                    }
+                    None => (symbol.name, UNKNOWN_CRATE.to_string()),
+                };
+                report.function_size += symbol.size;
+                functions
+                    .entry(name.clone())
+                    .or_insert(FunctionSize {
+                        name,
+                        crate_name,
+                        size: 0,
+                    })
+                    .size += symbol.size;
+            }
+        }
+
+        let mut crates: BTreeMap<String, CrateSize> = BTreeMap::new();
+        for function in functions.values() {
+            let entry = crates
+                .entry(function.crate_name.clone())
+                .or_insert(CrateSize {
+                    name: function.crate_name.clone(),
+                    size: 0,
+                    functions: 0,
+                });
+            entry.size += function.size;
+            entry.functions += 1;
+        }
+
+        report.functions = functions.into_values().collect();
+        report
+            .functions
+            .sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+        report.crates = crates.into_values().collect();
+        report
+            .crates
+            .sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+        report
+    }
+
+    /// Keep the `count` largest functions and crates.
+    pub fn top(mut self, count: usize) -> Self {
+        self.omitted_functions += self.functions.len().saturating_sub(count);
+        self.omitted_crates += self.crates.len().saturating_sub(count);
+        self.functions.truncate(count);
+        self.crates.truncate(count);
+        self
+    }
+
+    /// Changes from `baseline` to this report, largest first.
+    pub fn diff(&self, baseline: &SizeReport) -> SizeDiff {
+        let functions = size_changes(
+            baseline.functions.iter().map(|f| (&f.name, f.size)),
+            self.functions.iter().map(|f| (&f.name, f.size)),
+        );
+        let crates = size_changes(
+            baseline.crates.iter().map(|c| (&c.name, c.size)),
+            self.crates.iter().map(|c| (&c.name, c.size)),
+        );
+        SizeDiff {
+            file_size: SizeChange::new("file", baseline.file_size, self.file_size),
+            text_size: SizeChange::new("text", baseline.text_size, self.text_size),
+            functions,
+            crates,
+        }
+    }
+
+    /// Where a report named `name` is stored under the target dir.
+    pub fn path(target_dir: &Path, name: &str) -> Result<PathBuf> {
+        let valid = !name.is_empty()
+            && !name.starts_with('.')
+            && name
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.'));
+        if !valid {
+            bail!("Report name `{name}` may only use letters, digits, `-`, `_` and `.`");
+        }
+        Ok(target_dir.join(Self::DIR_NAME).join(format!("{name}.json")))
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("creating {}", parent.display()))?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .await
+            .with_context(|| format!("writing {}", path.display()))
+    }
+
+    pub async fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .await
+            .with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("parsing {}", path.display()))
+    }
+}
+
+/// Crate a demangled symbol belongs to: that of the self type for
+/// `<Type as Trait>::item` paths, or of the trait when the self type is not
+/// a path (`<u8 as demo::Trait>`, `<&T as ...>`).
+fn crate_of(demangled: &str) -> String {
+    let leading_path = |text: &str| {
+        let name: String = text
+            .chars()
+            .take_while(|&ch| ch == '_' || ch.is_alphanumeric())
+            .collect();
+        (!name.is_empty() && text[name.len()..].starts_with("::")).then_some(name)
+    };
+
+    if let Some(inner) = demangled.strip_prefix('<') {
+        return leading_path(inner)
+            .or_else(|| {
+                let (_, trait_path) = inner.split_once(" as ")?;
+                leading_path(trait_path)
+            })
+            .unwrap_or_else(|| UNKNOWN_CRATE.to_string());
+    }
+    leading_path(demangled).unwrap_or_else(|| UNKNOWN_CRATE.to_string())
+}
+
+/// Size changes between two reports. Entries that did not change are left
+/// out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeDiff {
+    pub file_size: SizeChange,
+    pub text_size: SizeChange,
+    pub functions: Vec<SizeChange>,
+    pub crates: Vec<SizeChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeChange {
+    pub name: String,
+    pub before: u64,
+    pub after: u64,
+    pub delta: i64,
+}
+
+impl SizeChange {
+    fn new(name: &str, before: u64, after: u64) -> Self {
+        Self {
+            name: name.to_string(),
+            before,
+            after,
+            delta: after as i64 - before as i64,
+        }
+    }
+}
+
+impl SizeDiff {
+    /// Keep the `count` largest function and crate changes.
+    pub fn top(mut self, count: usize) -> Self {
+        self.functions.truncate(count);
+        self.crates.truncate(count);
+        self
+    }
+}
+
+fn size_changes<'a>(
+    before: impl Iterator<Item = (&'a String, u64)>,
+    after: impl Iterator<Item = (&'a String, u64)>,
+) -> Vec<SizeChange> {
+    let mut sizes: BTreeMap<&str, (u64, u64)> = BTreeMap::new();
+    for (name, size) in before {
+        sizes.entry(name).or_default().0 += size;
+    }
+    for (name, size) in after {
+        sizes.entry(name).or_default().1 += size;
+    }
+
+    let mut changes: Vec<SizeChange> = sizes
+        .into_iter()
+        .filter(|(_, (before, after))| before != after)
+        .map(|(name, (before, after))| SizeChange::new(name, before, after))
+        .collect();
+    changes.sort_by(|a, b| {
+        b.delta
+            .unsigned_abs()
+            .cmp(&a.delta.unsigned_abs())
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ElfSizes, ElfSymbol, SizeReport, parse_elf};
+
+    /// A little-endian ELF64 object with `.text`, `.symtab` and `.strtab`.
+    fn elf64(symbols: &[(&str, u8, u16, u64, u64)]) -> Vec<u8> {
+        let mut strtab = vec![0u8];
+        let mut symtab = vec![0u8; 24];
+        for &(name, info, section, value, size) in symbols {
+            symtab.extend((strtab.len() as u32).to_le_bytes());
+            strtab.extend(name.as_bytes());
+            strtab.push(0);
+            symtab.push(info);
+            symtab.push(0);
+            symtab.extend(section.to_le_bytes());
+            symtab.extend(value.to_le_bytes());
+            symtab.extend(size.to_le_bytes());
+        }
+
+        let mut data = vec![0u8; 64];
+        data[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        let symtab_offset = data.len() as u64;
+        data.extend(&symtab);
+        let strtab_offset = data.len() as u64;
+        data.extend(&strtab);
+        let section_offset = data.len() as u64;
+        data[0x28..0x30].copy_from_slice(&section_offset.to_le_bytes());
+        data[0x3a..0x3c].copy_from_slice(&64u16.to_le_bytes());
+        data[0x3c..0x3e].copy_from_slice(&4u16.to_le_bytes());
+
+        // null, .text (64 bytes, executable), .symtab -> 3, .strtab
+        let sections: [(u32, u64, u64, u64, u32); 4] = [
+            (0, 0, 0, 0, 0),
+            (1, 0x6, 0, 64, 0),
+            (2, 0, symtab_offset, symtab.len() as u64, 3),
+            (3, 0, strtab_offset, strtab.len() as u64, 0),
+        ];
+        for (kind, flags, offset, size, link) in sections {
+            let mut header = vec![0u8; 64];
+            header[4..8].copy_from_slice(&kind.to_le_bytes());
+            header[8..16].copy_from_slice(&flags.to_le_bytes());
+            header[24..32].copy_from_slice(&offset.to_le_bytes());
+            header[32..40].copy_from_slice(&size.to_le_bytes());
+            header[40..44].copy_from_slice(&link.to_le_bytes());
+            data.extend(header);
+        }
+        data
+    }
+
+    #[test]
+    fn parses_elf_function_symbols() {
+        let data = elf64(&[
+            ("_ZN4demo3run17h0123456789abcdefE", 0x12, 1, 0, 40),
+            ("run_alias", 0x12, 1, 0, 40),
+            ("DATA", 0x11, 1, 48, 8),
+            ("memcpy", 0x12, 0, 0, 0),
+            ("helper", 0x02, 1, 40, 8),
+        ]);
+        let sizes = parse_elf(&data).expect("ELF parsed");
+        assert_eq!(sizes.text_size, 64);
+        assert_eq!(
+            sizes.symbols,
+            [
+                ElfSymbol {
+                    name: "_ZN4demo3run17h0123456789abcdefE".to_string(),
+                    size: 40
+                },
+                ElfSymbol {
+                    name: "helper".to_string(),
+                    size: 8
+                },
+            ]
+        );
+        assert!(parse_elf(b"!<arch>\n").is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_and_bogus_offsets() {
+        let data = elf64(&[("run", 0x12, 1, 0, 40)]);
+        let error = |data: &[u8]| parse_elf(data).unwrap_err().to_string();
+        assert_eq!(error(&data[..data.len() - 1]), "ELF file is truncated");
+
+        // A section header offset near u64::MAX.
+        let mut bogus = data.clone();
+        bogus[0x28..0x30].copy_from_slice(&(u64::MAX - 8).to_le_bytes());
+        assert_eq!(error(&bogus), "ELF file is truncated");
+
+        // A symbol table offset and a string offset that overflow.
+        let symtab_header = data.len() - 2 * 64;
+        let mut bogus = data.clone();
+        bogus[symtab_header + 24..symtab_header + 32].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(error(&bogus), "ELF file is truncated");
+        let strtab_header = data.len() - 64;
+        let mut bogus = data;
+        bogus[strtab_header + 24..strtab_header + 32]
+            .copy_from_slice(&(u64::MAX - 1).to_le_bytes());
+        assert_eq!(error(&bogus), "ELF file is truncated");
+    }
+
+    #[test]
+    fn aggregates_and_diffs_reports() {
+        let symbol = |name: &str, size| ElfSymbol {
+            name: name.to_string(),
+            size,
+        };
+        let build = |symbols| {
+            SizeReport::new(vec![(
+                "demo.o".to_string(),
+                1000,
+                ElfSizes {
+                    symbols,
+                    text_size: 500,
+                },
+            )])
+        };
+
+        let before = build(vec![
+            symbol("_ZN4demo3run17h0123456789abcdefE", 100),
+            symbol("_ZN4core3fmt5write17h0123456789abcdefE", 300),
+            symbol("memcpy", 20),
+        ]);
+        let after = build(vec![
+            symbol("_ZN4demo3run17h0123456789abcdefE", 160),
+            symbol(
+                "_RNvXCs1234_4demoNtB2_5PointNtNtCs5678_4core3fmt5Debug3fmt",
+                50,
+            ),
+            symbol("_ZN4core3fmt5write17h0123456789abcdefE", 300),
+            symbol("memcpy", 20),
+        ]);
+
+        let crates: Vec<(&str, u64)> = after
+            .crates
+            .iter()
+            .map(|c| (c.name.as_str(), c.size))
+            .collect();
+        assert_eq!(crates, [("core", 300), ("demo", 210), ("[unknown]", 20)]);
+        assert_eq!(after.functions[1].name, "demo::run");
+        assert_eq!(after.clone().top(1).omitted_functions, 3);
+
+        let diff = after.diff(&before);
+        let functions: Vec<(&str, i64)> = diff
+            .functions
+            .iter()
+            .map(|f| (f.name.as_str(), f.delta))
+            .collect();
+        assert_eq!(
+            functions,
+            [
+                ("demo::run", 60),
+                ("<demo::Point as core::fmt::Debug>::fmt", 50)
+            ]
+        );
+        assert_eq!(diff.crates[0].delta, 110);
+    }
+}
@@ -9,24 +9,24 @@ use std::{
 };
 use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
 
+use crate::compiler::size::{SizeDiff, SizeReport};
+
 pub const DEFAULT_TARGET_DIR: &str = "target/mcp-inspections";
 const DEFAULT_TIMEOUT_SECONDS: u64 = 60;
+/// Test runs build and run every test binary, so they get longer than a
+/// single inspection.
+pub const DEFAULT_TEST_TIMEOUT_SECONDS: u64 = 600;
 const DEFAULT_MAX_OUTPUT_BYTES: usize = 2 * 1024 * 1024;
 const DEFAULT_MAX_OUTPUT_LINES: usize = 20_000;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum GatingMode {
+    #[default]
     Strict,
     Lenient,
 }
 
-impl Default for GatingMode {
-    fn default() -> Self {
-        GatingMode::Strict
-    }
-}
-
 impl FromStr for GatingMode {
     type Err = ();
 
@@ -102,6 +102,29 @@ pub struct InspectionProvenance {
     pub command: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub truncation: Option<TruncationSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheProvenance>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cargo_target: Option<CargoTargetProvenance>,
+}
+
+/// Artifact cache entry an inspection was served from or stored to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheProvenance {
+    pub key: String,
+    /// Whether the compiler run was skipped in favor of the cached output.
+    pub hit: bool,
+}
+
+/// How the cargo package and target of a compiler run were chosen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "selection")]
+pub enum CargoTargetProvenance {
+    /// The target whose sources contain the inspected file.
+    Owner { package: String, target: String },
+    /// No target could be found for the file, so cargo built the default
+    /// target of the workspace root, which may be a different crate.
+    DefaultFallback { reason: String },
 }
 
 impl InspectionProvenance {
@@ -114,6 +137,16 @@ impl InspectionProvenance {
         self.truncation = truncation;
         self
     }
+
+    pub fn with_cache(mut self, cache: Option<CacheProvenance>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    pub fn with_cargo_target(mut self, cargo_target: CargoTargetProvenance) -> Self {
+        self.cargo_target = Some(cargo_target);
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -128,6 +161,35 @@ pub struct InspectionResult {
     pub provenance: InspectionProvenance,
 }
 
+/// Unified diff of one view rendered by two builds, after normalizing
+/// hashes, labels and metadata ids.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InspectionDiff {
+    pub view: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    pub identical: bool,
+    pub diff: String,
+    pub truncated: bool,
+    #[serde(default)]
+    pub diagnostics: Vec<String>,
+    pub before: InspectionProvenance,
+    pub after: InspectionProvenance,
+}
+
+/// Size attribution of a build, optionally against a stored baseline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SizeReportResult {
+    pub report: SizeReport,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<SizeDiff>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saved_to: Option<String>,
+    #[serde(default)]
+    pub diagnostics: Vec<String>,
+    pub provenance: InspectionProvenance,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InspectionCapabilities {
     pub toolchain_channel: ToolchainChannel,
@@ -146,6 +208,8 @@ pub struct InspectionView {
     pub requires_nightly: bool,
     pub emit: Option<&'static str>,
     pub unpretty: Option<&'static str>,
+    /// rust-analyzer request that renders the view without compiling.
+    pub analyzer_request: Option<&'static str>,
 }
 
 impl InspectionView {
@@ -157,6 +221,7 @@ impl InspectionView {
                 requires_nightly: false,
                 emit: None,
                 unpretty: None,
+                analyzer_request: None,
             },
             InspectionView {
                 name: "llvm-ir",
@@ -164,6 +229,7 @@ impl InspectionView {
                 requires_nightly: false,
                 emit: Some("llvm-ir"),
                 unpretty: None,
+                analyzer_request: None,
             },
             InspectionView {
                 name: "asm",
@@ -171,6 +237,7 @@ impl InspectionView {
                 requires_nightly: false,
                 emit: Some("asm"),
                 unpretty: None,
+                analyzer_request: None,
             },
             InspectionView {
                 name: "mir",
@@ -178,6 +245,95 @@ impl InspectionView {
                 requires_nightly: true,
                 emit: None,
                 unpretty: Some("mir"),
+                analyzer_request: None,
+            },
+            InspectionView {
+                name: "expanded",
+                description: "Macro-expanded source of a function",
+                requires_nightly: true,
+                emit: None,
+                unpretty: Some("expanded"),
+                analyzer_request: None,
+            },
+            InspectionView {
+                name: "hir",
+                description: "HIR of a function, pretty-printed as source",
+                requires_nightly: true,
+                emit: None,
+                unpretty: Some("hir"),
+                analyzer_request: None,
+            },
+            InspectionView {
+                name: "hir-tree",
+                description: "HIR tree of a function",
+                requires_nightly: true,
+                emit: None,
+                unpretty: Some("hir-tree"),
+                analyzer_request: None,
+            },
+            InspectionView {
+                name: "thir-tree",
+                description: "THIR tree of a function body",
+                requires_nightly: true,
+                emit: None,
+                unpretty: Some("thir-tree"),
+                analyzer_request: None,
+            },
+            InspectionView {
+                name: "mir-cfg",
+                description: "MIR control-flow graph of a function in Graphviz dot format",
+                requires_nightly: true,
+                emit: None,
+                unpretty: Some("mir-cfg"),
+                analyzer_request: None,
+            },
+            InspectionView {
+                name: "layout",
+                description: "Size, alignment, field offsets, niche and padding of a type",
+                requires_nightly: false,
+                emit: None,
+                unpretty: None,
+                analyzer_request: None,
+            },
+            InspectionView {
+                name: "ra-syntax",
+                description: "rust-analyzer syntax tree of a file",
+                requires_nightly: false,
+                emit: None,
+                unpretty: None,
+                analyzer_request: Some("rust-analyzer/syntaxTree"),
+            },
+            InspectionView {
+                name: "ra-hir",
+                description: "rust-analyzer HIR of the function at a position",
+                requires_nightly: false,
+                emit: None,
+                unpretty: None,
+                analyzer_request: Some("rust-analyzer/viewHir"),
+            },
+            InspectionView {
+                name: "ra-mir",
+                description: "rust-analyzer MIR of the function at a position",
+                requires_nightly: false,
+                emit: None,
+                unpretty: None,
+                analyzer_request: Some("rust-analyzer/viewMir"),
+            },
+            InspectionView {
+                name: "ra-item-tree",
+                description: "rust-analyzer item tree of a file",
+                requires_nightly: false,
+                emit: None,
+                unpretty: None,
+                analyzer_request: Some("rust-analyzer/viewItemTree"),
+            },
+            InspectionView {
+                name: "crate-graph",
+                description: "Workspace crate graph in Graphviz dot format",
+                requires_nightly: false,
+                emit: None,
+                unpretty: None,
+                analyzer_request: Some("rust-analyzer/viewCrateGraph"),
             },
         ]
     }
@@ -204,7 +360,7 @@ pub fn is_view_advertised(
 }
 
 pub fn is_view_runnable(view: &InspectionView, channel: ToolchainChannel) -> bool {
-    !(view.requires_nightly && !channel.is_nightly_like())
+    !view.requires_nightly || channel.is_nightly_like()
 }
 
 #[derive(Clone)]
@@ -247,6 +403,11 @@ impl InspectionContext {
         self
     }
 
+    pub fn with_timeout_seconds(mut self, timeout_seconds: u64) -> Self {
+        self.limits.timeout_seconds = timeout_seconds;
+        self
+    }
+
     pub fn limits(&self) -> &InspectionLimits {
         &self.limits
     }
@@ -266,7 +427,7 @@ impl InspectionContext {
     pub fn target_dir(&self) -> PathBuf {
         self.env
             .get("CARGO_TARGET_DIR")
-            .map(|value| PathBuf::from(value))
+            .map(PathBuf::from)
             .unwrap_or_else(|| PathBuf::from(DEFAULT_TARGET_DIR))
     }
 
@@ -292,6 +453,8 @@ impl InspectionContext {
             rust_analyzer_version: self.rust_analyzer_version.clone(),
             command: None,
             truncation: None,
+            cache: None,
+            cargo_target: None,
         }
     }
 }
@@ -401,7 +564,7 @@ pub fn truncate_with_limits(
     text: &str,
     limits: &InspectionLimits,
 ) -> (String, bool, Option<TruncationSummary>) {
-    let original_bytes = text.as_bytes().len();
+    let original_bytes = text.len();
     let original_lines = text.lines().count();
 
     if original_bytes <= limits.max_output_bytes && original_lines <= limits.max_output_lines {
@@ -414,7 +577,7 @@ pub fn truncate_with_limits(
 
     for line in text.lines() {
         let line_with_newline = format!("{line}\n");
-        let next_bytes = kept_bytes + line_with_newline.as_bytes().len();
+        let next_bytes = kept_bytes + line_with_newline.len();
         let next_lines = kept_lines + 1;
 
         if next_bytes > limits.max_output_bytes || next_lines > limits.max_output_lines {
@@ -3,7 +3,7 @@ use rmcp::{
     ServerHandler,
     handler::server::{router::tool::ToolRouter, tool::Parameters},
     model::{ErrorData as McpError, *},
-    tool, tool_handler, tool_router,
+    tool, tool_handler,
 };
 use serde::Serialize;
 use serde_json::{Value, json};
@@ -16,24 +16,45 @@ use tokio::{fs, sync::Mutex};
 
 use crate::analyzer::{
     RustAnalyzerClient,
+    protocol::Position,
     symbol::{SymbolIdentity, SymbolKind, identity_from_definition},
 };
 use crate::compiler::{
     CompilerRunner, RunRequest, RunResult, RunnerError,
-    extract::{NormalizedSymbol, TargetedAssembly, extract_asm, extract_llvm_ir, extract_mir},
+    annotate::{AnnotateOptions, SourceLines, annotate_asm, annotate_llvm_ir},
+    cache::ArtifactCache,
+    diff::{normalize_codegen, unified_diff},
+    extract::{
+        NormalizedSymbol, TargetedAssembly, asm_comment, extract_asm, extract_def_tree,
+        extract_expanded_item, extract_llvm_ir, extract_mir, extract_mir_cfg, extract_source_fn,
+    },
+    layout::{from_analyzer_layout, parse_type_sizes, select_layouts},
+    metadata::{OwningTarget, load_metadata, owning_target},
+    size::{SizeReport, parse_elf},
 };
 use crate::inspection::{
-    GatingMode, InspectionCapabilities, InspectionContext, InspectionLimits, InspectionResult,
-    InspectionView, TruncationSummary, is_view_advertised, is_view_runnable, truncate_with_limits,
+    CacheProvenance, CargoTargetProvenance, DEFAULT_TEST_TIMEOUT_SECONDS, GatingMode,
+    InspectionCapabilities, InspectionContext, InspectionDiff, InspectionLimits,
+    InspectionProvenance, InspectionResult, InspectionView, SizeReportResult, TruncationSummary,
+    is_view_advertised, is_view_runnable, truncate_with_limits,
 };
 use crate::server::parameters::*;
-use crate::tools::{execute_tool, get_tools};
+use crate::tools::{self, ToolDefinition, ToolResult, run_tests_impl};
 
 struct ResolvedDefinition {
     symbol: Option<SymbolIdentity>,
     text: String,
 }
 
+/// Output of an inspection view before it is truncated to the limits.
+struct RenderedInspection {
+    view: String,
+    symbol: Option<String>,
+    text: String,
+    diagnostics: Vec<String>,
+    provenance: InspectionProvenance,
+}
+
 #[derive(Clone)]
 pub struct RustMcpServer {
     analyzer: Arc<Mutex<RustAnalyzerClient>>,
@@ -47,29 +68,69 @@ impl Default for RustMcpServer {
     }
 }
 
-#[tool_router]
 impl RustMcpServer {
     pub fn new() -> Self {
+        Self::with_analyzer(RustAnalyzerClient::new())
+    }
+
+    /// A server whose analyzer tools run on `analyzer`.
+    pub fn with_analyzer(analyzer: RustAnalyzerClient) -> Self {
         let workspace_root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
         Self {
-            analyzer: Arc::new(Mutex::new(RustAnalyzerClient::new())),
+            analyzer: Arc::new(Mutex::new(analyzer)),
             tool_router: Self::tool_router(),
             inspection: InspectionContext::new(workspace_root),
         }
     }
 
+    /// Hands the analyzer back, leaving a fresh client in its place.
+    pub async fn take_analyzer(&self) -> RustAnalyzerClient {
+        std::mem::take(&mut *self.analyzer.lock().await)
+    }
+
     pub async fn start(&mut self) -> Result<()> {
         let mut analyzer = self.analyzer.lock().await;
         analyzer.start().await
     }
 
-    pub fn list_tools(&self) -> Vec<crate::tools::ToolDefinition> {
-        get_tools()
+    /// Every tool registered with the MCP router, sorted by name.
+    pub fn list_tools(&self) -> Vec<ToolDefinition> {
+        Self::definitions(&self.tool_router)
     }
 
-    pub async fn call_tool(&mut self, name: &str, args: Value) -> Result<crate::tools::ToolResult> {
-        let mut analyzer = self.analyzer.lock().await;
-        execute_tool(name, args, &mut analyzer).await
+    /// The tools `list_tools` returns, without a server instance.
+    pub fn tool_definitions() -> Vec<ToolDefinition> {
+        Self::definitions(&Self::tool_router())
+    }
+
+    fn definitions(router: &ToolRouter<Self>) -> Vec<ToolDefinition> {
+        let mut tools: Vec<ToolDefinition> = router
+            .list_all()
+            .into_iter()
+            .map(ToolDefinition::from)
+            .collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        tools
+    }
+
+    /// Calls a tool by name, running the same `#[tool]` method the MCP router
+    /// would with the arguments decoded into its parameter struct.
+    pub async fn call_tool(&mut self, name: &str, args: Value) -> Result<ToolResult> {
+        let args = if args.is_null() { json!({}) } else { args };
+        let result = self
+            .dispatch_tool(name, args)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e.message))?;
+
+        let content = result
+            .content
+            .into_iter()
+            .map(|content| match serde_json::to_value(content)? {
+                Value::Object(map) => Ok(map),
+                other => Err(anyhow::anyhow!("Unexpected tool content: {other}")),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ToolResult { content })
     }
 
     #[tool(description = "Discover supported inspection presets and limits")]
@@ -122,6 +183,12 @@ impl RustMcpServer {
             symbol_name,
             opt_level,
             target,
+            features,
+            no_default_features,
+            profile,
+            instance,
+            annotate_source,
+            strip_directives,
             gating_mode,
         }): Parameters<InspectParams>,
     ) -> Result<CallToolResult, McpError> {
@@ -134,8 +201,16 @@ impl RustMcpServer {
                 Some(line),
                 Some(character),
                 symbol_name,
-                opt_level,
-                target,
+                instance,
+                annotate_options(annotate_source, strip_directives),
+                RunRequest {
+                    opt_level,
+                    target_triple: target,
+                    features: features.unwrap_or_default(),
+                    no_default_features: no_default_features.unwrap_or(false),
+                    profile,
+                    ..RunRequest::default()
+                },
             )
             .await?;
 
@@ -166,14 +241,14 @@ impl RustMcpServer {
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("find_definition", args, &mut analyzer).await {
+        match tools::find_definition_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
                     "No definition found",
@@ -201,14 +276,14 @@ impl RustMcpServer {
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("find_references", args, &mut analyzer).await {
+        match tools::find_references_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
                     "No references found",
@@ -220,27 +295,35 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Get compiler diagnostics for a file")]
-    async fn get_diagnostics(
+    #[tool(
+        description = "Find all implementations of a trait, type or method at a given position, with snippets and symbol paths"
+    )]
+    async fn find_implementations(
         &self,
-        Parameters(GetDiagnosticsParams { file_path }): Parameters<GetDiagnosticsParams>,
+        Parameters(FindImplementationsParams {
+            file_path,
+            line,
+            character,
+        }): Parameters<FindImplementationsParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
-            "file_path": file_path
+            "file_path": file_path,
+            "line": line,
+            "character": character
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("get_diagnostics", args, &mut analyzer).await {
+        match tools::find_implementations_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
-                    "No diagnostics found",
+                    "Implementations retrieved successfully",
                 )]))
             }
             Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
@@ -249,27 +332,35 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Search for symbols in the workspace")]
-    async fn workspace_symbols(
+    #[tool(
+        description = "Find the type definitions of the expression or binding at a given position, with snippets and symbol paths"
+    )]
+    async fn find_type_definition(
         &self,
-        Parameters(WorkspaceSymbolsParams { query }): Parameters<WorkspaceSymbolsParams>,
+        Parameters(FindTypeDefinitionParams {
+            file_path,
+            line,
+            character,
+        }): Parameters<FindTypeDefinitionParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
-            "query": query
+            "file_path": file_path,
+            "line": line,
+            "character": character
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("workspace_symbols", args, &mut analyzer).await {
+        match tools::find_type_definition_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
-                    "No symbols found",
+                    "Type definitions retrieved successfully",
                 )]))
             }
             Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
@@ -278,35 +369,35 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Rename a symbol with scope awareness")]
-    async fn rename_symbol(
+    #[tool(
+        description = "Find all declarations of the symbol at a given position, with snippets and symbol paths"
+    )]
+    async fn find_declaration(
         &self,
-        Parameters(RenameSymbolParams {
+        Parameters(FindDeclarationParams {
             file_path,
             line,
             character,
-            new_name,
-        }): Parameters<RenameSymbolParams>,
+        }): Parameters<FindDeclarationParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
             "file_path": file_path,
             "line": line,
-            "character": character,
-            "new_name": new_name
+            "character": character
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("rename_symbol", args, &mut analyzer).await {
+        match tools::find_declaration_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
-                    "Rename operation completed",
+                    "Declarations retrieved successfully",
                 )]))
             }
             Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
@@ -315,27 +406,27 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Apply rustfmt formatting to a file")]
-    async fn format_code(
+    #[tool(description = "Get compiler diagnostics for a file")]
+    async fn get_diagnostics(
         &self,
-        Parameters(FormatCodeParams { file_path }): Parameters<FormatCodeParams>,
+        Parameters(GetDiagnosticsParams { file_path }): Parameters<GetDiagnosticsParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
             "file_path": file_path
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("format_code", args, &mut analyzer).await {
+        match tools::get_diagnostics_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
-                    "Format operation completed",
+                    "No diagnostics found",
                 )]))
             }
             Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
@@ -344,27 +435,39 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Parse and analyze Cargo.toml file")]
-    async fn analyze_manifest(
+    #[tool(
+        description = "Hover information at a position split into signature, type and docs; give end_line/end_character to get the type of a selected expression"
+    )]
+    async fn hover(
         &self,
-        Parameters(AnalyzeManifestParams { manifest_path }): Parameters<AnalyzeManifestParams>,
+        Parameters(HoverParams {
+            file_path,
+            line,
+            character,
+            end_line,
+            end_character,
+        }): Parameters<HoverParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
-            "manifest_path": manifest_path
+            "file_path": file_path,
+            "line": line,
+            "character": character,
+            "end_line": end_line,
+            "end_character": end_character
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("analyze_manifest", args, &mut analyzer).await {
+        match tools::hover_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
-                    "Analysis completed",
+                    "Hover retrieved successfully",
                 )]))
             }
             Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
@@ -373,27 +476,35 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Execute cargo check and parse errors")]
-    async fn run_cargo_check(
+    #[tool(
+        description = "Signature help at a call site: every candidate signature, the active parameter and parameter docs"
+    )]
+    async fn signature_help(
         &self,
-        Parameters(RunCargoCheckParams { workspace_path }): Parameters<RunCargoCheckParams>,
+        Parameters(SignatureHelpParams {
+            file_path,
+            line,
+            character,
+        }): Parameters<SignatureHelpParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
-            "workspace_path": workspace_path
+            "file_path": file_path,
+            "line": line,
+            "character": character
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("run_cargo_check", args, &mut analyzer).await {
+        match tools::signature_help_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
-                    "Cargo check completed",
+                    "Signature help retrieved successfully",
                 )]))
             }
             Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
@@ -402,39 +513,39 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Extract selected code into a new function")]
-    async fn extract_function(
+    #[tool(
+        description = "Completion candidates at a position with kinds, detail signatures and auto-import edits; filter by prefix and cap with max_results"
+    )]
+    async fn complete_at(
         &self,
-        Parameters(ExtractFunctionParams {
+        Parameters(CompleteAtParams {
             file_path,
-            start_line,
-            start_character,
-            end_line,
-            end_character,
-            function_name,
-        }): Parameters<ExtractFunctionParams>,
+            line,
+            character,
+            prefix,
+            max_results,
+        }): Parameters<CompleteAtParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
             "file_path": file_path,
-            "start_line": start_line,
-            "start_character": start_character,
-            "end_line": end_line,
-            "end_character": end_character,
-            "function_name": function_name
+            "line": line,
+            "character": character,
+            "prefix": prefix,
+            "max_results": max_results
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("extract_function", args, &mut analyzer).await {
+        match tools::complete_at_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
-                    "Function extracted successfully",
+                    "Completions retrieved successfully",
                 )]))
             }
             Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
@@ -443,35 +554,35 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Generate a struct with specified fields and derives")]
-    async fn generate_struct(
+    #[tool(
+        description = "Render a file (or a line range) with inferred types, parameter names, chaining and lifetime hints inlined as /*...*/ annotations"
+    )]
+    async fn inlay_hints(
         &self,
-        Parameters(GenerateStructParams {
-            struct_name,
-            fields,
-            derives,
+        Parameters(InlayHintsParams {
             file_path,
-        }): Parameters<GenerateStructParams>,
+            start_line,
+            end_line,
+        }): Parameters<InlayHintsParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
-            "struct_name": struct_name,
-            "fields": fields,
-            "derives": derives,
-            "file_path": file_path
+            "file_path": file_path,
+            "start_line": start_line,
+            "end_line": end_line
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("generate_struct", args, &mut analyzer).await {
+        match tools::inlay_hints_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
-                    "Struct generated successfully",
+                    "Inlay hints retrieved successfully",
                 )]))
             }
             Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
@@ -480,35 +591,27 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Generate an enum with specified variants and derives")]
-    async fn generate_enum(
+    #[tool(description = "Search for symbols in the workspace")]
+    async fn workspace_symbols(
         &self,
-        Parameters(GenerateEnumParams {
-            enum_name,
-            variants,
-            derives,
-            file_path,
-        }): Parameters<GenerateEnumParams>,
+        Parameters(WorkspaceSymbolsParams { query }): Parameters<WorkspaceSymbolsParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
-            "enum_name": enum_name,
-            "variants": variants,
-            "derives": derives,
-            "file_path": file_path
+            "query": query
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("generate_enum", args, &mut analyzer).await {
+        match tools::workspace_symbols_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
-                    "Enum generated successfully",
+                    "No symbols found",
                 )]))
             }
             Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
@@ -517,33 +620,35 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Generate a trait implementation for a struct")]
-    async fn generate_trait_impl(
+    #[tool(
+        description = "Outline of a file as a nested item tree with kinds, visibility, ranges and signatures, filterable by kind and visibility"
+    )]
+    async fn document_outline(
         &self,
-        Parameters(GenerateTraitImplParams {
-            trait_name,
-            struct_name,
+        Parameters(DocumentOutlineParams {
             file_path,
-        }): Parameters<GenerateTraitImplParams>,
+            kinds,
+            visibility,
+        }): Parameters<DocumentOutlineParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
-            "trait_name": trait_name,
-            "struct_name": struct_name,
-            "file_path": file_path
+            "file_path": file_path,
+            "kinds": kinds,
+            "visibility": visibility
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("generate_trait_impl", args, &mut analyzer).await {
+        match tools::document_outline_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
-                    "Trait implementation generated successfully",
+                    "Document outline retrieved successfully",
                 )]))
             }
             Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
@@ -552,33 +657,41 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Generate unit tests for a function")]
-    async fn generate_tests(
+    #[tool(
+        description = "Show callers (incoming) or callees (outgoing) of a function as a tree with symbol paths and call-site ranges, limited by depth and node count"
+    )]
+    async fn call_hierarchy(
         &self,
-        Parameters(GenerateTestsParams {
-            target_function,
+        Parameters(CallHierarchyParams {
             file_path,
-            test_cases,
-        }): Parameters<GenerateTestsParams>,
+            line,
+            character,
+            direction,
+            max_depth,
+            max_nodes,
+        }): Parameters<CallHierarchyParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
-            "target_function": target_function,
             "file_path": file_path,
-            "test_cases": test_cases
+            "line": line,
+            "character": character,
+            "direction": direction,
+            "max_depth": max_depth,
+            "max_nodes": max_nodes
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("generate_tests", args, &mut analyzer).await {
+        match tools::call_hierarchy_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
-                    "Tests generated successfully",
+                    "Call hierarchy retrieved successfully",
                 )]))
             }
             Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
@@ -587,33 +700,35 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Inline a function call at specified position")]
-    async fn inline_function(
+    #[tool(description = "Rename a symbol with scope awareness")]
+    async fn rename_symbol(
         &self,
-        Parameters(InlineFunctionParams {
+        Parameters(RenameSymbolParams {
             file_path,
             line,
             character,
-        }): Parameters<InlineFunctionParams>,
+            new_name,
+        }): Parameters<RenameSymbolParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
             "file_path": file_path,
             "line": line,
-            "character": character
+            "character": character,
+            "new_name": new_name
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("inline_function", args, &mut analyzer).await {
+        match tools::rename_symbol_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
-                    "Function inlined successfully",
+                    "Rename operation completed",
                 )]))
             }
             Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
@@ -622,35 +737,27 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Change the signature of a function")]
-    async fn change_signature(
+    #[tool(description = "Apply rustfmt formatting to a file")]
+    async fn format_code(
         &self,
-        Parameters(ChangeSignatureParams {
-            file_path,
-            line,
-            character,
-            new_signature,
-        }): Parameters<ChangeSignatureParams>,
+        Parameters(FormatCodeParams { file_path }): Parameters<FormatCodeParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
-            "file_path": file_path,
-            "line": line,
-            "character": character,
-            "new_signature": new_signature
+            "file_path": file_path
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("change_signature", args, &mut analyzer).await {
+        match tools::format_code_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
-                    "Signature changed successfully",
+                    "Format operation completed",
                 )]))
             }
             Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
@@ -659,27 +766,27 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Organize and sort import statements in a file")]
-    async fn organize_imports(
+    #[tool(description = "Parse and analyze Cargo.toml file")]
+    async fn analyze_manifest(
         &self,
-        Parameters(OrganizeImportsParams { file_path }): Parameters<OrganizeImportsParams>,
+        Parameters(AnalyzeManifestParams { manifest_path }): Parameters<AnalyzeManifestParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
-            "file_path": file_path
+            "manifest_path": manifest_path
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("organize_imports", args, &mut analyzer).await {
+        match tools::analyze_manifest_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
-                    "Imports organized successfully",
+                    "Analysis completed",
                 )]))
             }
             Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
@@ -688,29 +795,27 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Apply clippy lint suggestions to improve code quality")]
-    async fn apply_clippy_suggestions(
+    #[tool(description = "Execute cargo check and parse errors")]
+    async fn run_cargo_check(
         &self,
-        Parameters(ApplyClippySuggestionsParams { file_path }): Parameters<
-            ApplyClippySuggestionsParams,
-        >,
+        Parameters(RunCargoCheckParams { workspace_path }): Parameters<RunCargoCheckParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
-            "file_path": file_path
+            "workspace_path": workspace_path
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("apply_clippy_suggestions", args, &mut analyzer).await {
+        match tools::run_cargo_check_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
-                    "Clippy suggestions applied successfully",
+                    "Cargo check completed",
                 )]))
             }
             Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
@@ -719,27 +824,35 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Validate and suggest lifetime annotations")]
-    async fn validate_lifetimes(
+    #[tool(
+        description = "List tests, benches, binaries and doctests in a file, or those containing a position, with the cargo commands that run them"
+    )]
+    async fn list_runnables(
         &self,
-        Parameters(ValidateLifetimesParams { file_path }): Parameters<ValidateLifetimesParams>,
+        Parameters(ListRunnablesParams {
+            file_path,
+            line,
+            character,
+        }): Parameters<ListRunnablesParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
-            "file_path": file_path
+            "file_path": file_path,
+            "line": line,
+            "character": character
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("validate_lifetimes", args, &mut analyzer).await {
+        match tools::list_runnables_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
-                    "Lifetimes validated successfully",
+                    "Runnables listed successfully",
                 )]))
             }
             Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
@@ -748,14 +861,16 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Get type hierarchy for a symbol at specified position")]
-    async fn get_type_hierarchy(
+    #[tool(
+        description = "Find the tests that exercise the function at a position, with the cargo commands that run them"
+    )]
+    async fn related_tests(
         &self,
-        Parameters(GetTypeHierarchyParams {
+        Parameters(RelatedTestsParams {
             file_path,
             line,
             character,
-        }): Parameters<GetTypeHierarchyParams>,
+        }): Parameters<RelatedTestsParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
             "file_path": file_path,
@@ -764,17 +879,17 @@ impl RustMcpServer {
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("get_type_hierarchy", args, &mut analyzer).await {
+        match tools::related_tests_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
-                    "Type hierarchy retrieved successfully",
+                    "Related tests found successfully",
                 )]))
             }
             Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
@@ -783,28 +898,498 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Suggest crate dependencies based on code patterns")]
-    async fn suggest_dependencies(
+    #[tool(
+        description = "Run cargo test with an optional package, test name filter and features, returning per-test pass/fail/ignored results with panic messages and captured output; rerun_failed reruns only the tests that failed last time; timeout_seconds defaults to 600"
+    )]
+    async fn run_tests(
         &self,
-        Parameters(SuggestDependenciesParams {
-            query,
-            workspace_path,
-        }): Parameters<SuggestDependenciesParams>,
+        Parameters(RunTestsParams {
+            package,
+            filter,
+            features,
+            exact,
+            rerun_failed,
+            timeout_seconds,
+        }): Parameters<RunTestsParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
-            "query": query,
-            "workspace_path": workspace_path
+            "package": package,
+            "filter": filter,
+            "features": features,
+            "exact": exact,
+            "rerun_failed": rerun_failed
         });
 
-        let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("suggest_dependencies", args, &mut analyzer).await {
+        let context = self
+            .inspection_context(None)
+            .with_timeout_seconds(timeout_seconds.unwrap_or(DEFAULT_TEST_TIMEOUT_SECONDS));
+        match run_tests_impl(args, &context).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
+                }
+                Ok(CallToolResult::success(vec![Content::text(
+                    "Tests completed",
+                )]))
+            }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Error: {e}"
+            ))])),
+        }
+    }
+
+    #[tool(description = "Extract selected code into a new function")]
+    async fn extract_function(
+        &self,
+        Parameters(ExtractFunctionParams {
+            file_path,
+            start_line,
+            start_character,
+            end_line,
+            end_character,
+            function_name,
+        }): Parameters<ExtractFunctionParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let args = serde_json::json!({
+            "file_path": file_path,
+            "start_line": start_line,
+            "start_character": start_character,
+            "end_line": end_line,
+            "end_character": end_character,
+            "function_name": function_name
+        });
+
+        let mut analyzer = self.analyzer.lock().await;
+        match tools::extract_function_impl(args, &mut analyzer).await {
+            Ok(result) => {
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
+                }
+                Ok(CallToolResult::success(vec![Content::text(
+                    "Function extracted successfully",
+                )]))
+            }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Error: {e}"
+            ))])),
+        }
+    }
+
+    #[tool(description = "Generate a struct with specified fields and derives")]
+    async fn generate_struct(
+        &self,
+        Parameters(GenerateStructParams {
+            struct_name,
+            fields,
+            derives,
+            file_path,
+        }): Parameters<GenerateStructParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let args = serde_json::json!({
+            "struct_name": struct_name,
+            "fields": fields,
+            "derives": derives,
+            "file_path": file_path
+        });
+
+        let mut analyzer = self.analyzer.lock().await;
+        match tools::generate_struct_impl(args, &mut analyzer).await {
+            Ok(result) => {
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
+                }
+                Ok(CallToolResult::success(vec![Content::text(
+                    "Struct generated successfully",
+                )]))
+            }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Error: {e}"
+            ))])),
+        }
+    }
+
+    #[tool(description = "Generate an enum with specified variants and derives")]
+    async fn generate_enum(
+        &self,
+        Parameters(GenerateEnumParams {
+            enum_name,
+            variants,
+            derives,
+            file_path,
+        }): Parameters<GenerateEnumParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let args = serde_json::json!({
+            "enum_name": enum_name,
+            "variants": variants,
+            "derives": derives,
+            "file_path": file_path
+        });
+
+        let mut analyzer = self.analyzer.lock().await;
+        match tools::generate_enum_impl(args, &mut analyzer).await {
+            Ok(result) => {
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
+                }
+                Ok(CallToolResult::success(vec![Content::text(
+                    "Enum generated successfully",
+                )]))
+            }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Error: {e}"
+            ))])),
+        }
+    }
+
+    #[tool(description = "Generate a trait implementation for a struct")]
+    async fn generate_trait_impl(
+        &self,
+        Parameters(GenerateTraitImplParams {
+            trait_name,
+            struct_name,
+            file_path,
+        }): Parameters<GenerateTraitImplParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let args = serde_json::json!({
+            "trait_name": trait_name,
+            "struct_name": struct_name,
+            "file_path": file_path
+        });
+
+        let mut analyzer = self.analyzer.lock().await;
+        match tools::generate_trait_impl_impl(args, &mut analyzer).await {
+            Ok(result) => {
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
+                }
+                Ok(CallToolResult::success(vec![Content::text(
+                    "Trait implementation generated successfully",
+                )]))
+            }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Error: {e}"
+            ))])),
+        }
+    }
+
+    #[tool(description = "Generate unit tests for a function")]
+    async fn generate_tests(
+        &self,
+        Parameters(GenerateTestsParams {
+            target_function,
+            file_path,
+            test_cases,
+        }): Parameters<GenerateTestsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let args = serde_json::json!({
+            "target_function": target_function,
+            "file_path": file_path,
+            "test_cases": test_cases
+        });
+
+        let mut analyzer = self.analyzer.lock().await;
+        match tools::generate_tests_impl(args, &mut analyzer).await {
+            Ok(result) => {
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
+                }
+                Ok(CallToolResult::success(vec![Content::text(
+                    "Tests generated successfully",
+                )]))
+            }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Error: {e}"
+            ))])),
+        }
+    }
+
+    #[tool(description = "Inline a function call at specified position")]
+    async fn inline_function(
+        &self,
+        Parameters(InlineFunctionParams {
+            file_path,
+            line,
+            character,
+        }): Parameters<InlineFunctionParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let args = serde_json::json!({
+            "file_path": file_path,
+            "line": line,
+            "character": character
+        });
+
+        let mut analyzer = self.analyzer.lock().await;
+        match tools::inline_function_impl(args, &mut analyzer).await {
+            Ok(result) => {
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
+                }
+                Ok(CallToolResult::success(vec![Content::text(
+                    "Function inlined successfully",
+                )]))
+            }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Error: {e}"
+            ))])),
+        }
+    }
+
+    #[tool(description = "Change the signature of a function")]
+    async fn change_signature(
+        &self,
+        Parameters(ChangeSignatureParams {
+            file_path,
+            line,
+            character,
+            new_signature,
+        }): Parameters<ChangeSignatureParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let args = serde_json::json!({
+            "file_path": file_path,
+            "line": line,
+            "character": character,
+            "new_signature": new_signature
+        });
+
+        let mut analyzer = self.analyzer.lock().await;
+        match tools::change_signature_impl(args, &mut analyzer).await {
+            Ok(result) => {
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
+                }
+                Ok(CallToolResult::success(vec![Content::text(
+                    "Signature changed successfully",
+                )]))
+            }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Error: {e}"
+            ))])),
+        }
+    }
+
+    #[tool(description = "Organize and sort import statements in a file")]
+    async fn organize_imports(
+        &self,
+        Parameters(OrganizeImportsParams { file_path }): Parameters<OrganizeImportsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let args = serde_json::json!({
+            "file_path": file_path
+        });
+
+        let mut analyzer = self.analyzer.lock().await;
+        match tools::organize_imports_impl(args, &mut analyzer).await {
+            Ok(result) => {
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
+                }
+                Ok(CallToolResult::success(vec![Content::text(
+                    "Imports organized successfully",
+                )]))
+            }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Error: {e}"
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "Structural search and replace with a rule like `foo($a, $b) ==>> bar($b, $a)` over a file, a set of files or the workspace; parse_only validates the rule and search_only lists matches without editing"
+    )]
+    async fn ssr(
+        &self,
+        Parameters(SsrParams {
+            rule,
+            scope,
+            file_path,
+            files,
+            parse_only,
+            search_only,
+        }): Parameters<SsrParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let args = serde_json::json!({
+            "rule": rule,
+            "scope": scope,
+            "file_path": file_path,
+            "files": files,
+            "parse_only": parse_only,
+            "search_only": search_only
+        });
+
+        let mut analyzer = self.analyzer.lock().await;
+        match tools::ssr_impl(args, &mut analyzer).await {
+            Ok(result) => {
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
+                }
+                Ok(CallToolResult::success(vec![Content::text(
+                    "Structural replace completed successfully",
+                )]))
+            }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Error: {e}"
+            ))])),
+        }
+    }
+
+    #[tool(description = "Apply clippy lint suggestions to improve code quality")]
+    async fn apply_clippy_suggestions(
+        &self,
+        Parameters(ApplyClippySuggestionsParams { file_path }): Parameters<
+            ApplyClippySuggestionsParams,
+        >,
+    ) -> Result<CallToolResult, McpError> {
+        let args = serde_json::json!({
+            "file_path": file_path
+        });
+
+        let mut analyzer = self.analyzer.lock().await;
+        match tools::apply_clippy_suggestions_impl(args, &mut analyzer).await {
+            Ok(result) => {
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
+                }
+                Ok(CallToolResult::success(vec![Content::text(
+                    "Clippy suggestions applied successfully",
+                )]))
+            }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Error: {e}"
+            ))])),
+        }
+    }
+
+    #[tool(description = "Validate and suggest lifetime annotations")]
+    async fn validate_lifetimes(
+        &self,
+        Parameters(ValidateLifetimesParams { file_path }): Parameters<ValidateLifetimesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let args = serde_json::json!({
+            "file_path": file_path
+        });
+
+        let mut analyzer = self.analyzer.lock().await;
+        match tools::validate_lifetimes_impl(args, &mut analyzer).await {
+            Ok(result) => {
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
+                }
+                Ok(CallToolResult::success(vec![Content::text(
+                    "Lifetimes validated successfully",
+                )]))
+            }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Error: {e}"
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "Get the type hierarchy at a position: supertraits and implementors for a trait, implemented traits (including derives and the crate's own blanket impls) for a type, as a tree with locations"
+    )]
+    async fn get_type_hierarchy(
+        &self,
+        Parameters(GetTypeHierarchyParams {
+            file_path,
+            line,
+            character,
+        }): Parameters<GetTypeHierarchyParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let args = serde_json::json!({
+            "file_path": file_path,
+            "line": line,
+            "character": character
+        });
+
+        let mut analyzer = self.analyzer.lock().await;
+        match tools::get_type_hierarchy_impl(args, &mut analyzer).await {
+            Ok(result) => {
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
+                }
+                Ok(CallToolResult::success(vec![Content::text(
+                    "Type hierarchy retrieved successfully",
+                )]))
+            }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Error: {e}"
+            ))])),
+        }
+    }
+
+    #[tool(description = "Suggest crate dependencies based on code patterns")]
+    async fn suggest_dependencies(
+        &self,
+        Parameters(SuggestDependenciesParams {
+            query,
+            workspace_path,
+        }): Parameters<SuggestDependenciesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let args = serde_json::json!({
+            "query": query,
+            "workspace_path": workspace_path
+        });
+
+        let mut analyzer = self.analyzer.lock().await;
+        match tools::suggest_dependencies_impl(args, &mut analyzer).await {
+            Ok(result) => {
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
                     "Dependencies suggested successfully",
@@ -816,30 +1401,34 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Create a new Rust module with optional visibility")]
+    #[tool(
+        description = "Create a new Rust module file, declare it in the parent module and optionally re-export it"
+    )]
     async fn create_module(
         &self,
         Parameters(CreateModuleParams {
             module_name,
             module_path,
             is_public,
+            reexport,
         }): Parameters<CreateModuleParams>,
     ) -> Result<CallToolResult, McpError> {
         let args = serde_json::json!({
             "module_name": module_name,
             "module_path": module_path,
-            "is_public": is_public
+            "is_public": is_public,
+            "reexport": reexport
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("create_module", args, &mut analyzer).await {
+        match tools::create_module_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
                     "Module created successfully",
@@ -851,7 +1440,9 @@ impl RustMcpServer {
         }
     }
 
-    #[tool(description = "Move code items from one file to another")]
+    #[tool(
+        description = "Move items and their impls to another file, creating the module if needed and fixing imports and references across the crate"
+    )]
     async fn move_items(
         &self,
         Parameters(MoveItemsParams {
@@ -867,14 +1458,14 @@ impl RustMcpServer {
         });
 
         let mut analyzer = self.analyzer.lock().await;
-        match execute_tool("move_items", args, &mut analyzer).await {
+        match tools::move_items_impl(args, &mut analyzer).await {
             Ok(result) => {
-                if let Some(content) = result.content.first() {
-                    if let Some(text) = content.get("text") {
-                        return Ok(CallToolResult::success(vec![Content::text(
-                            text.as_str().unwrap_or("No result"),
-                        )]));
-                    }
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
                     "Items moved successfully",
@@ -886,6 +1477,55 @@ impl RustMcpServer {
         }
     }
 
+    #[tool(
+        description = "Expand the macro call, attribute or derive at a position via rust-analyzer; set whole_item to expand the enclosing item with rustc -Zunpretty=expanded (nightly)"
+    )]
+    async fn expand_macro(
+        &self,
+        Parameters(ExpandMacroParams {
+            file_path,
+            line,
+            character,
+            whole_item,
+        }): Parameters<ExpandMacroParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if whole_item.unwrap_or(false) {
+            let text = match self
+                .expand_item_with_rustc(&file_path, line, character)
+                .await
+            {
+                Ok(text) => text,
+                Err(e) => format!("Error: {}", e.message),
+            };
+            return Ok(CallToolResult::success(vec![Content::text(text)]));
+        }
+
+        let args = serde_json::json!({
+            "file_path": file_path,
+            "line": line,
+            "character": character
+        });
+
+        let mut analyzer = self.analyzer.lock().await;
+        match tools::expand_macro_impl(args, &mut analyzer).await {
+            Ok(result) => {
+                if let Some(content) = result.content.first()
+                    && let Some(text) = content.get("text")
+                {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        text.as_str().unwrap_or("No result"),
+                    )]));
+                }
+                Ok(CallToolResult::success(vec![Content::text(
+                    "Macro expanded successfully",
+                )]))
+            }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Error: {e}"
+            ))])),
+        }
+    }
+
     #[tool(description = "Inspect MIR for a symbol or position")]
     async fn inspect_mir(
         &self,
@@ -896,6 +1536,9 @@ impl RustMcpServer {
             symbol_name,
             opt_level,
             target,
+            features,
+            no_default_features,
+            profile,
         }): Parameters<InspectMirParams>,
     ) -> Result<CallToolResult, McpError> {
         let context = self.inspection_context(None);
@@ -907,15 +1550,25 @@ impl RustMcpServer {
                 line,
                 character,
                 symbol_name,
-                opt_level,
-                target,
+                None,
+                AnnotateOptions::default(),
+                RunRequest {
+                    opt_level,
+                    target_triple: target,
+                    features: features.unwrap_or_default(),
+                    no_default_features: no_default_features.unwrap_or(false),
+                    profile,
+                    ..RunRequest::default()
+                },
             )
             .await?;
 
         Ok(CallToolResult::success(vec![json_content(result)?]))
     }
 
-    #[tool(description = "Inspect LLVM IR for a symbol or position")]
+    #[tool(
+        description = "Inspect LLVM IR for a symbol or position. Passing `instance` builds with -Csymbol-mangling-version=v0 so generic instances keep their type arguments"
+    )]
     async fn inspect_llvm_ir(
         &self,
         Parameters(InspectLlvmIrParams {
@@ -925,6 +1578,12 @@ impl RustMcpServer {
             symbol_name,
             opt_level,
             target,
+            features,
+            no_default_features,
+            profile,
+            instance,
+            annotate_source,
+            strip_directives,
         }): Parameters<InspectLlvmIrParams>,
     ) -> Result<CallToolResult, McpError> {
         let context = self.inspection_context(None);
@@ -936,43 +1595,273 @@ impl RustMcpServer {
                 line,
                 character,
                 symbol_name,
-                opt_level,
-                target,
+                instance,
+                annotate_options(annotate_source, strip_directives),
+                RunRequest {
+                    opt_level,
+                    target_triple: target,
+                    features: features.unwrap_or_default(),
+                    no_default_features: no_default_features.unwrap_or(false),
+                    profile,
+                    ..RunRequest::default()
+                },
+            )
+            .await?;
+
+        Ok(CallToolResult::success(vec![json_content(result)?]))
+    }
+
+    #[tool(
+        description = "Inspect assembly for a symbol or position. Passing `instance` builds with -Csymbol-mangling-version=v0 so generic instances keep their type arguments"
+    )]
+    async fn inspect_asm(
+        &self,
+        Parameters(InspectAsmParams {
+            file_path,
+            line,
+            character,
+            symbol_name,
+            opt_level,
+            target,
+            features,
+            no_default_features,
+            profile,
+            instance,
+            annotate_source,
+            strip_directives,
+        }): Parameters<InspectAsmParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let context = self.inspection_context(None);
+        let result = self
+            .perform_inspection(
+                &context,
+                "asm",
+                &file_path,
+                line,
+                character,
+                symbol_name,
+                instance,
+                annotate_options(annotate_source, strip_directives),
+                RunRequest {
+                    opt_level,
+                    target_triple: target,
+                    features: features.unwrap_or_default(),
+                    no_default_features: no_default_features.unwrap_or(false),
+                    profile,
+                    ..RunRequest::default()
+                },
             )
             .await?;
 
         Ok(CallToolResult::success(vec![json_content(result)?]))
     }
 
-    #[tool(description = "Inspect assembly for a symbol or position")]
-    async fn inspect_asm(
+    #[tool(
+        description = "Diff an inspection view between two builds, e.g. two opt levels or targets"
+    )]
+    async fn inspect_diff(
+        &self,
+        Parameters(InspectDiffParams {
+            view,
+            file_path,
+            line,
+            character,
+            symbol_name,
+            instance,
+            no_default_features,
+            profile,
+            before,
+            after,
+        }): Parameters<InspectDiffParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let context = self.inspection_context(None);
+        let build = |side: InspectDiffSide| RunRequest {
+            opt_level: side.opt_level,
+            target_triple: side.target,
+            features: side.features.unwrap_or_default(),
+            no_default_features: no_default_features.unwrap_or(false),
+            profile: profile.clone(),
+            additional_rustc_args: side.rustc_args.unwrap_or_default(),
+            ..RunRequest::default()
+        };
+        let (before, after) = (build(before), build(after));
+        let labels = [
+            format!("before ({})", describe_build(&before)),
+            format!("after ({})", describe_build(&after)),
+        ];
+
+        let render = |build| {
+            self.render_inspection(
+                &context,
+                &view,
+                &file_path,
+                Some(line),
+                Some(character),
+                symbol_name.clone(),
+                instance.clone(),
+                AnnotateOptions::default(),
+                build,
+            )
+        };
+        let before = render(before).await?;
+        let after = render(after).await?;
+
+        let diff = unified_diff(
+            &normalize_codegen(&before.text),
+            &normalize_codegen(&after.text),
+            &labels[0],
+            &labels[1],
+            3,
+        );
+        let mut diagnostics: Vec<String> = [(&labels[0], &before), (&labels[1], &after)]
+            .into_iter()
+            .flat_map(|(label, side)| {
+                side.diagnostics
+                    .iter()
+                    .map(move |diagnostic| format!("{label}: {diagnostic}"))
+            })
+            .collect();
+        let (diff, truncated, truncation) = truncate_with_limits(&diff, context.limits());
+        if let Some(summary) = &truncation {
+            diagnostics.push(truncation_note(summary));
+        }
+
+        let result = InspectionDiff {
+            view: before.view,
+            symbol: before.symbol.or(after.symbol),
+            identical: diff.is_empty(),
+            diff,
+            truncated,
+            diagnostics,
+            before: before.provenance,
+            after: after.provenance.with_truncation(truncation),
+        };
+        Ok(CallToolResult::success(vec![json_content(result)?]))
+    }
+
+    #[tool(
+        description = "Attribute the size of a release build to functions and crates from its ELF symbol table, optionally diffed against a stored report"
+    )]
+    async fn size_report(
         &self,
-        Parameters(InspectAsmParams {
+        Parameters(SizeReportParams {
             file_path,
-            line,
-            character,
-            symbol_name,
+            linked,
             opt_level,
             target,
-        }): Parameters<InspectAsmParams>,
+            features,
+            no_default_features,
+            profile,
+            top,
+            save_as,
+            compare_to,
+        }): Parameters<SizeReportParams>,
     ) -> Result<CallToolResult, McpError> {
         let context = self.inspection_context(None);
-        let result = self
-            .perform_inspection(
+        let invalid =
+            |e: anyhow::Error| mcp_error(ErrorCode::INVALID_PARAMS, format!("{e:#}"), None);
+        let save_path = save_as
+            .map(|name| SizeReport::path(&context.target_dir(), &name))
+            .transpose()
+            .map_err(invalid)?;
+        let baseline = match compare_to {
+            Some(name) => {
+                let path = SizeReport::path(&context.target_dir(), &name).map_err(invalid)?;
+                Some(SizeReport::load(&path).await.map_err(|e| {
+                    mcp_error(
+                        ErrorCode::RESOURCE_NOT_FOUND,
+                        format!("No stored size report `{name}`: {e:#}"),
+                        None,
+                    )
+                })?)
+            }
+            None => None,
+        };
+
+        let linked = linked.unwrap_or(false);
+        let build = RunRequest {
+            opt_level,
+            target_triple: target,
+            features: features.unwrap_or_default(),
+            no_default_features: no_default_features.unwrap_or(false),
+            profile: profile.or_else(|| Some("release".to_string())),
+            ..RunRequest::default()
+        };
+
+        let mut provenance = context.provenance();
+        let workspace_guard = context.lock_workspace().await;
+        provenance.workspace_locked = true;
+        let (run_result, cache, cargo_target) = self
+            .run_compiler(
                 &context,
-                "asm",
                 &file_path,
-                line,
-                character,
-                symbol_name,
-                opt_level,
-                target,
+                build,
+                (!linked).then_some("obj"),
+                None,
             )
             .await?;
+        drop(workspace_guard);
+        provenance = provenance
+            .with_command(run_result.command.join(" "))
+            .with_cache(cache)
+            .with_cargo_target(cargo_target);
+        let diagnostics: Vec<String> = stderr_diagnostic(&run_result.stderr, context.limits())
+            .into_iter()
+            .collect();
+
+        let mut artifacts = Vec::new();
+        for path in size_artifacts(&run_result.artifacts, &run_result.artifact_dir, linked) {
+            let data = fs::read(&path).await.map_err(|e| {
+                mcp_error(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read artifact {}: {e}", path.display()),
+                    None,
+                )
+            })?;
+            // Cargo bookkeeping files sit among the linked binary candidates.
+            if let Ok(sizes) = parse_elf(&data) {
+                artifacts.push((path.display().to_string(), data.len() as u64, sizes));
+                if linked {
+                    break;
+                }
+            }
+        }
+        if artifacts.is_empty() {
+            return Err(mcp_error(
+                ErrorCode::RESOURCE_NOT_FOUND,
+                if linked {
+                    "No linked ELF binary was produced; `linked` needs a bin target built for an ELF platform"
+                } else {
+                    "No ELF object was produced; size reports read ELF symbol tables, so the target must be an ELF platform"
+                },
+                Some(json!({ "artifacts": run_result.artifacts })),
+            ));
+        }
 
+        let report = SizeReport::new(artifacts);
+        let saved_to = match save_path {
+            Some(path) => {
+                report
+                    .save(&path)
+                    .await
+                    .map_err(|e| mcp_error(ErrorCode::INTERNAL_ERROR, format!("{e:#}"), None))?;
+                Some(path.display().to_string())
+            }
+            None => None,
+        };
+
+        let top = top.unwrap_or(25);
+        let result = SizeReportResult {
+            diff: baseline.map(|baseline| report.diff(&baseline).top(top)),
+            report: report.top(top),
+            saved_to,
+            diagnostics,
+            provenance,
+        };
         Ok(CallToolResult::success(vec![json_content(result)?]))
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn perform_inspection(
         &self,
         context: &InspectionContext,
@@ -981,9 +1870,59 @@ impl RustMcpServer {
         line: Option<u32>,
         character: Option<u32>,
         symbol_name: Option<String>,
-        opt_level: Option<String>,
-        target: Option<String>,
+        instance: Option<String>,
+        annotate: AnnotateOptions,
+        build: RunRequest,
     ) -> Result<InspectionResult, McpError> {
+        let rendered = self
+            .render_inspection(
+                context,
+                view_name,
+                file_path,
+                line,
+                character,
+                symbol_name,
+                instance,
+                annotate,
+                build,
+            )
+            .await?;
+
+        let mut diagnostics = rendered.diagnostics;
+        let (text, truncated, truncation) = truncate_with_limits(&rendered.text, context.limits());
+        if let Some(summary) = &truncation {
+            diagnostics.push(truncation_note(summary));
+        }
+
+        Ok(InspectionResult {
+            view: rendered.view,
+            symbol: rendered.symbol,
+            text,
+            truncated,
+            diagnostics,
+            provenance: rendered.provenance.with_truncation(truncation),
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn render_inspection(
+        &self,
+        context: &InspectionContext,
+        view_name: &str,
+        file_path: &str,
+        line: Option<u32>,
+        character: Option<u32>,
+        symbol_name: Option<String>,
+        instance: Option<String>,
+        annotate: AnnotateOptions,
+        mut build: RunRequest,
+    ) -> Result<RenderedInspection, McpError> {
+        let target = build.target_triple.clone();
+        if annotate.source_lines {
+            build
+                .additional_rustc_args
+                .push("-Cdebuginfo=1".to_string());
+        }
         let Some(view) = InspectionView::find(view_name) else {
             return Err(mcp_error(
                 ErrorCode::INVALID_PARAMS,
@@ -991,6 +1930,16 @@ impl RustMcpServer {
                 None,
             ));
         };
+        if instance.is_some() && !matches!(view.name, "llvm-ir" | "asm") {
+            return Err(mcp_error(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "View `{}` has one generic body per item; `instance` only applies to `llvm-ir` and `asm`",
+                    view.name
+                ),
+                None,
+            ));
+        }
 
         if !is_view_advertised(&view, context.toolchain_channel(), context.gating_mode()) {
             return Err(mcp_error(
@@ -1008,11 +1957,10 @@ impl RustMcpServer {
         let mut provenance = context.provenance();
 
         if !is_view_runnable(&view, context.toolchain_channel()) {
-            return Ok(InspectionResult {
+            return Ok(RenderedInspection {
                 view: view.name.to_string(),
                 symbol: None,
                 text: String::new(),
-                truncated: false,
                 diagnostics: vec![format!(
                     "View `{}` requires a nightly toolchain (detected {:?})",
                     view.name,
@@ -1027,6 +1975,23 @@ impl RustMcpServer {
 
         let mut diagnostics = Vec::new();
         let (output_text, symbol_name_out) = match view.name {
+            _ if let Some(method) = view.analyzer_request => {
+                let position = line
+                    .zip(character)
+                    .map(|(line, character)| Position { line, character });
+                let mut analyzer = self.analyzer.lock().await;
+                let output = analyzer
+                    .analyzer_view(method, file_path, position)
+                    .await
+                    .map_err(|e| {
+                        mcp_error(
+                            ErrorCode::INTERNAL_ERROR,
+                            format!("Failed to render view `{}`: {e}", view.name),
+                            None,
+                        )
+                    })?;
+                (output, None)
+            }
             "def" => {
                 let resolved = self
                     .resolve_definition(file_path, line, character, symbol_name)
@@ -1037,6 +2002,81 @@ impl RustMcpServer {
                     resolved.symbol.map(|sym| sym.item_name.clone()),
                 )
             }
+            "layout" if context.toolchain_channel().is_nightly_like() => {
+                let identity = self
+                    .resolve_definition(file_path, line, character, symbol_name)
+                    .await?
+                    .symbol
+                    .ok_or_else(|| {
+                        mcp_error(ErrorCode::RESOURCE_NOT_FOUND, "No type at position", None)
+                    })?;
+
+                build
+                    .additional_rustc_args
+                    .push("-Zprint-type-sizes".to_string());
+                let (run_result, cache, cargo_target) = self
+                    .run_compiler(context, file_path, build, None, None)
+                    .await?;
+                provenance = provenance
+                    .with_command(run_result.command.join(" "))
+                    .with_cache(cache)
+                    .with_cargo_target(cargo_target);
+                diagnostics.extend(stderr_diagnostic(&run_result.stderr, context.limits()));
+
+                let layouts = select_layouts(parse_type_sizes(&run_result.stdout), &identity);
+                if layouts.is_empty() {
+                    return Err(mcp_error(
+                        ErrorCode::RESOURCE_NOT_FOUND,
+                        format!(
+                            "No layout printed for `{}`; rustc only prints types that are used in codegen",
+                            identity.item_name
+                        ),
+                        None,
+                    ));
+                }
+
+                (
+                    serde_json::to_string_pretty(&layouts).unwrap_or_default(),
+                    Some(identity.item_name),
+                )
+            }
+            "layout" => {
+                let position = line
+                    .zip(character)
+                    .map(|(line, character)| Position { line, character });
+                let mut analyzer = self.analyzer.lock().await;
+                let output = analyzer
+                    .analyzer_view(
+                        "rust-analyzer/viewRecursiveMemoryLayout",
+                        file_path,
+                        position,
+                    )
+                    .await
+                    .map_err(|e| {
+                        mcp_error(
+                            ErrorCode::INTERNAL_ERROR,
+                            format!("Failed to render view `layout`: {e}"),
+                            None,
+                        )
+                    })?;
+                let layout = serde_json::from_str(&output)
+                    .ok()
+                    .as_ref()
+                    .and_then(from_analyzer_layout)
+                    .ok_or_else(|| {
+                        mcp_error(
+                            ErrorCode::RESOURCE_NOT_FOUND,
+                            "rust-analyzer reported no layout; place the position on a struct, enum or union",
+                            None,
+                        )
+                    })?;
+
+                let name = layout.name.clone();
+                (
+                    serde_json::to_string_pretty(&layout).unwrap_or_default(),
+                    Some(name),
+                )
+            }
             _ => {
                 let mut symbol = self
                     .resolve_normalized_symbol(
@@ -1047,22 +2087,24 @@ impl RustMcpServer {
                         target.clone(),
                     )
                     .await?;
+                if let Some(instance) = instance {
+                    symbol = symbol.with_instance(instance);
+                    // Legacy symbols drop the type arguments an instance is
+                    // picked by; v0 keeps them.
+                    build
+                        .additional_rustc_args
+                        .push("-Csymbol-mangling-version=v0".to_string());
+                }
 
-                let run_result = self
-                    .run_compiler(context, opt_level, target.clone(), view.emit, view.unpretty)
+                let (run_result, cache, cargo_target) = self
+                    .run_compiler(context, file_path, build, view.emit, view.unpretty)
                     .await?;
-                provenance = provenance.with_command(run_result.command.join(" "));
-
-                if !run_result.stderr.trim().is_empty() {
-                    let (stderr, truncated_stderr, _) =
-                        truncate_with_limits(&run_result.stderr, context.limits());
-                    let prefix = if truncated_stderr {
-                        "Compiler stderr (truncated):\n"
-                    } else {
-                        "Compiler stderr:\n"
-                    };
-                    diagnostics.push(format!("{prefix}{stderr}"));
-                }
+                provenance = provenance
+                    .with_command(run_result.command.join(" "))
+                    .with_cache(cache)
+                    .with_cargo_target(cargo_target);
+
+                diagnostics.extend(stderr_diagnostic(&run_result.stderr, context.limits()));
 
                 let output = match view.name {
                     "mir" => {
@@ -1075,6 +2117,30 @@ impl RustMcpServer {
                             )
                         })?
                     }
+                    "expanded" | "hir" => extract_source_fn(&run_result.stdout, &symbol, view.name)
+                        .map_err(|e| {
+                            mcp_error(
+                                ErrorCode::RESOURCE_NOT_FOUND,
+                                format!("Unable to locate {} for symbol: {e}", view.name),
+                                None,
+                            )
+                        })?,
+                    "hir-tree" | "thir-tree" => {
+                        extract_def_tree(&run_result.stdout, &symbol, view.name).map_err(|e| {
+                            mcp_error(
+                                ErrorCode::RESOURCE_NOT_FOUND,
+                                format!("Unable to locate {} for symbol: {e}", view.name),
+                                None,
+                            )
+                        })?
+                    }
+                    "mir-cfg" => extract_mir_cfg(&run_result.stdout, &symbol).map_err(|e| {
+                        mcp_error(
+                            ErrorCode::RESOURCE_NOT_FOUND,
+                            format!("Unable to locate MIR CFG for symbol: {e}"),
+                            None,
+                        )
+                    })?,
                     "llvm-ir" => {
                         let llvm_outputs =
                             read_artifacts(&run_result.artifacts, &["ll"], context.limits())
@@ -1087,17 +2153,25 @@ impl RustMcpServer {
                             ));
                         }
 
-                        extract_llvm_ir(&llvm_outputs, &symbol).map_err(|e| {
+                        let block = extract_llvm_ir(&llvm_outputs, &symbol).map_err(|e| {
                             mcp_error(
                                 ErrorCode::RESOURCE_NOT_FOUND,
                                 format!("Unable to locate LLVM IR for symbol: {e}"),
                                 None,
                             )
-                        })?
+                        })?;
+                        if annotate.is_enabled() {
+                            let modules: Vec<&str> =
+                                llvm_outputs.iter().map(String::as_str).collect();
+                            annotate_llvm_ir(&block, &modules, annotate, &mut read_sources())
+                        } else {
+                            block
+                        }
                     }
                     "asm" => {
                         let assemblies = load_assembly_artifacts(
                             &run_result.artifacts,
+                            &run_result.artifact_dir,
                             target.as_ref(),
                             context.limits(),
                         )
@@ -1116,13 +2190,30 @@ impl RustMcpServer {
                             .unwrap_or_else(|| "host".to_string());
                         symbol = symbol.with_target(target_triple.clone());
 
-                        extract_asm(&assemblies, &symbol, &target_triple).map_err(|e| {
-                            mcp_error(
-                                ErrorCode::RESOURCE_NOT_FOUND,
-                                format!("Unable to locate assembly for symbol: {e}"),
-                                None,
+                        let block =
+                            extract_asm(&assemblies, &symbol, &target_triple).map_err(|e| {
+                                mcp_error(
+                                    ErrorCode::RESOURCE_NOT_FOUND,
+                                    format!("Unable to locate assembly for symbol: {e}"),
+                                    None,
+                                )
+                            })?;
+                        if annotate.is_enabled() {
+                            let sources: Vec<&str> = assemblies
+                                .iter()
+                                .filter(|asm| asm.target == target_triple)
+                                .map(|asm| asm.content.as_str())
+                                .collect();
+                            annotate_asm(
+                                &block,
+                                &sources,
+                                asm_comment(&target_triple),
+                                annotate,
+                                &mut read_sources(),
                             )
-                        })?
+                        } else {
+                            block
+                        }
                     }
                     _ => {
                         return Err(mcp_error(
@@ -1139,18 +2230,12 @@ impl RustMcpServer {
 
         drop(workspace_guard);
 
-        let (text, truncated, truncation) = truncate_with_limits(&output_text, context.limits());
-        if let Some(summary) = &truncation {
-            diagnostics.push(truncation_note(summary));
-        }
-
-        Ok(InspectionResult {
+        Ok(RenderedInspection {
             view: view.name.to_string(),
             symbol: symbol_name_out,
-            text,
-            truncated,
+            text: output_text,
             diagnostics,
-            provenance: provenance.with_truncation(truncation),
+            provenance,
         })
     }
 
@@ -1267,54 +2352,363 @@ impl RustMcpServer {
         Ok(normalized)
     }
 
+    /// Expand every macro in the item enclosing a position with
+    /// `-Zunpretty=expanded`, built for the target that owns the file.
+    async fn expand_item_with_rustc(
+        &self,
+        file_path: &str,
+        line: u32,
+        character: u32,
+    ) -> Result<String, McpError> {
+        let identity = {
+            let mut analyzer = self.analyzer.lock().await;
+            analyzer.enclosing_item(file_path, line, character).await
+        }
+        .map_err(|e| mcp_error(ErrorCode::INTERNAL_ERROR, format!("{e:#}"), None))?
+        .ok_or_else(|| {
+            mcp_error(
+                ErrorCode::RESOURCE_NOT_FOUND,
+                format!("No item encloses {}:{}", line + 1, character + 1),
+                None,
+            )
+        })?;
+
+        let context = self.inspection_context(None);
+        if !context.toolchain_channel().is_nightly_like() {
+            return Err(mcp_error(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "Whole-item expansion uses -Zunpretty=expanded, which requires a nightly toolchain (detected {:?}); expand individual macro calls instead",
+                    context.toolchain_channel()
+                ),
+                None,
+            ));
+        }
+
+        let (run, _, _) = {
+            let _workspace_guard = context.lock_workspace().await;
+            self.run_compiler(
+                &context,
+                file_path,
+                RunRequest::default(),
+                None,
+                Some("expanded"),
+            )
+            .await?
+        };
+
+        // Paths derived from the crate root file start with `lib` or `main`.
+        let mut module_path = identity.module_path.as_slice();
+        if let [root, rest @ ..] = module_path
+            && (root == "lib" || root == "main")
+        {
+            module_path = rest;
+        }
+        let item = extract_expanded_item(&run.stdout, module_path, &identity.item_name)
+            .map_err(|e| mcp_error(ErrorCode::RESOURCE_NOT_FOUND, format!("{e:#}"), None))?;
+
+        let (item, _, truncation) = truncate_with_limits(&item, context.limits());
+        let mut text = format!(
+            "// `{}` expanded by `{}`\n{item}",
+            identity.qualified_path(),
+            run.command.join(" ")
+        );
+        if let Some(summary) = &truncation {
+            text.push_str(&format!("\n// {}", truncation_note(summary)));
+        }
+        Ok(text)
+    }
+
     async fn run_compiler(
         &self,
         context: &InspectionContext,
-        opt_level: Option<String>,
-        target: Option<String>,
+        file_path: &str,
+        build: RunRequest,
         emit: Option<&str>,
         unpretty: Option<&str>,
-    ) -> Result<RunResult, McpError> {
-        let runner = CompilerRunner::with_target_dir(context.target_dir());
-        let request = RunRequest {
-            manifest_path: None,
-            package: None,
-            target_triple: target,
-            opt_level,
+    ) -> Result<(RunResult, Option<CacheProvenance>, CargoTargetProvenance), McpError> {
+        let target_dir = context.target_dir();
+        let runner = CompilerRunner::with_target_dir(&target_dir);
+        let mut request = RunRequest {
             emit: emit.map(|emit| emit.to_string()),
             unpretty: unpretty.map(|unpretty| unpretty.to_string()),
-            additional_rustc_args: Vec::new(),
             env: context.env().clone(),
+            ..build
+        };
+        let cargo_target = match infer_owning_target(context, file_path).await {
+            Ok(owner) => {
+                let selection = CargoTargetProvenance::Owner {
+                    package: owner.package.clone(),
+                    target: owner.target.cargo_args().join(" "),
+                };
+                request.manifest_path = Some(owner.manifest_path);
+                request.package = Some(owner.package);
+                request.cargo_target = Some(owner.target);
+                selection
+            }
+            Err(reason) => CargoTargetProvenance::DefaultFallback { reason },
         };
 
-        let result = runner.run(request, context.limits()).await.map_err(|e| {
-            if let Some(runner_error) = e.downcast_ref::<RunnerError>() {
-                match runner_error {
-                    RunnerError::Timeout(duration) => mcp_error(
+        // A fingerprint that cannot be computed only costs the cache.
+        let cache = ArtifactCache::new(&target_dir);
+        let key = ArtifactCache::key(
+            context.workspace_root(),
+            &target_dir,
+            &request,
+            context.provenance().rustc_verbose_version.as_deref(),
+        )
+        .await
+        .ok();
+        if let Some(key) = &key
+            && let Ok(Some(cached)) = cache.load(key).await
+        {
+            let provenance = CacheProvenance {
+                key: key.clone(),
+                hit: true,
+            };
+            return Ok((cached, Some(provenance), cargo_target));
+        }
+
+        let package = request.package.clone();
+        let mut result = run_checked(&runner, request.clone(), context.limits()).await?;
+        // A build cargo finds fresh skips rustc, so it emits nothing; drop the
+        // fingerprint and build once more to get the output.
+        if !result.compiled(package.as_deref()) {
+            let package = match &cargo_target {
+                CargoTargetProvenance::Owner { package, .. } => package,
+                CargoTargetProvenance::DefaultFallback { reason } => {
+                    return Err(mcp_error(
                         ErrorCode::INTERNAL_ERROR,
                         format!(
-                            "Compiler run timed out after {} seconds. Try narrowing the request or limiting emitted artifacts.",
-                            duration.as_secs()
+                            "The build is fresh, so rustc emitted nothing, and there is no package to rebuild: {reason}"
                         ),
-                        Some(json!({
-                            "timeout_seconds": duration.as_secs()
-                        })),
-                    ),
+                        None,
+                    ));
                 }
-            } else {
-                mcp_error(
+            };
+            runner
+                .invalidate(package)
+                .await
+                .map_err(|e| mcp_error(ErrorCode::INTERNAL_ERROR, format!("{e:#}"), None))?;
+            result = run_checked(&runner, request, context.limits()).await?;
+        }
+
+        let stored = match key {
+            Some(key) if result.compiled(package.as_deref()) => cache
+                .store(&key, &result)
+                .await
+                .ok()
+                .map(|()| CacheProvenance { key, hit: false }),
+            _ => None,
+        };
+        Ok((result, stored, cargo_target))
+    }
+}
+
+/// Run the compiler, turning timeouts and failed builds into tool errors.
+async fn run_checked(
+    runner: &CompilerRunner,
+    request: RunRequest,
+    limits: &InspectionLimits,
+) -> Result<RunResult, McpError> {
+    let result = runner.run(request, limits).await.map_err(|e| {
+        if let Some(runner_error) = e.downcast_ref::<RunnerError>() {
+            match runner_error {
+                RunnerError::Timeout(duration) => mcp_error(
                     ErrorCode::INTERNAL_ERROR,
-                    format!("{e:#}"),
-                    None,
-                )
+                    format!(
+                        "Compiler run timed out after {} seconds. Try narrowing the request or limiting emitted artifacts.",
+                        duration.as_secs()
+                    ),
+                    Some(json!({
+                        "timeout_seconds": duration.as_secs()
+                    })),
+                ),
             }
-        })?;
+        } else {
+            mcp_error(ErrorCode::INTERNAL_ERROR, format!("{e:#}"), None)
+        }
+    })?;
+
+    if !result.status.success() {
+        return Err(compiler_failure_error(&result));
+    }
+    Ok(result)
+}
 
-        if !result.status.success() {
-            return Err(compiler_failure_error(&result));
+/// Registers the given `#[tool]` methods, each with the `<name>_tool_attr`
+/// function `#[tool]` generates for it. The one list generates both
+/// `RustMcpServer::tool_router`, which serves the tools over MCP, and
+/// `RustMcpServer::dispatch_tool`, which runs them for `call_tool`, so the
+/// two always agree.
+macro_rules! register_tools {
+    ($($tool:ident => $attr:ident),* $(,)?) => {
+        impl RustMcpServer {
+            fn tool_router() -> ToolRouter<Self> {
+                ToolRouter::<Self>::new()
+                    $(.with_route((Self::$attr(), Self::$tool)))*
+            }
+
+            async fn dispatch_tool(&self, name: &str, args: Value) -> Result<CallToolResult, McpError> {
+                match name {
+                    $(stringify!($tool) => {
+                        let params = serde_json::from_value(args).map_err(|e| {
+                            mcp_error(
+                                ErrorCode::INVALID_PARAMS,
+                                format!("Invalid arguments for {name}: {e}"),
+                                None,
+                            )
+                        })?;
+                        self.$tool(Parameters(params)).await
+                    })*
+                    _ => Err(mcp_error(
+                        ErrorCode::INVALID_PARAMS,
+                        format!("Unknown tool: {name}"),
+                        None,
+                    )),
+                }
+            }
         }
+    };
+}
+
+register_tools!(
+    capabilities => capabilities_tool_attr,
+    inspect => inspect_tool_attr,
+    find_definition => find_definition_tool_attr,
+    find_references => find_references_tool_attr,
+    find_implementations => find_implementations_tool_attr,
+    find_type_definition => find_type_definition_tool_attr,
+    find_declaration => find_declaration_tool_attr,
+    get_diagnostics => get_diagnostics_tool_attr,
+    hover => hover_tool_attr,
+    signature_help => signature_help_tool_attr,
+    complete_at => complete_at_tool_attr,
+    inlay_hints => inlay_hints_tool_attr,
+    workspace_symbols => workspace_symbols_tool_attr,
+    document_outline => document_outline_tool_attr,
+    call_hierarchy => call_hierarchy_tool_attr,
+    rename_symbol => rename_symbol_tool_attr,
+    format_code => format_code_tool_attr,
+    analyze_manifest => analyze_manifest_tool_attr,
+    run_cargo_check => run_cargo_check_tool_attr,
+    list_runnables => list_runnables_tool_attr,
+    related_tests => related_tests_tool_attr,
+    run_tests => run_tests_tool_attr,
+    extract_function => extract_function_tool_attr,
+    generate_struct => generate_struct_tool_attr,
+    generate_enum => generate_enum_tool_attr,
+    generate_trait_impl => generate_trait_impl_tool_attr,
+    generate_tests => generate_tests_tool_attr,
+    inline_function => inline_function_tool_attr,
+    change_signature => change_signature_tool_attr,
+    organize_imports => organize_imports_tool_attr,
+    ssr => ssr_tool_attr,
+    apply_clippy_suggestions => apply_clippy_suggestions_tool_attr,
+    validate_lifetimes => validate_lifetimes_tool_attr,
+    get_type_hierarchy => get_type_hierarchy_tool_attr,
+    suggest_dependencies => suggest_dependencies_tool_attr,
+    create_module => create_module_tool_attr,
+    move_items => move_items_tool_attr,
+    expand_macro => expand_macro_tool_attr,
+    inspect_mir => inspect_mir_tool_attr,
+    inspect_llvm_ir => inspect_llvm_ir_tool_attr,
+    inspect_asm => inspect_asm_tool_attr,
+    inspect_diff => inspect_diff_tool_attr,
+    size_report => size_report_tool_attr,
+);
+
+/// Package and target that own `file_path` according to `cargo metadata`,
+/// or why none was found. Without one the compiler runs on the workspace
+/// root's default target.
+async fn infer_owning_target(
+    context: &InspectionContext,
+    file_path: &str,
+) -> Result<OwningTarget, String> {
+    let file = context.workspace_root().join(file_path);
+    let metadata = load_metadata(context.workspace_root(), context.limits())
+        .await
+        .map_err(|e| format!("{e:#}"))?;
+    owning_target(&metadata, &file)
+        .ok_or_else(|| format!("no target of the workspace contains {}", file.display()))
+}
+
+/// Artifacts a size report reads: the object files of the build, or the
+/// candidates for its linked binary, with the copy cargo places outside
+/// `deps` first.
+fn size_artifacts(paths: &[PathBuf], artifact_dir: &Path, linked: bool) -> Vec<PathBuf> {
+    if !linked {
+        return paths
+            .iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "o"))
+            .cloned()
+            .collect();
+    }
+
+    let mut binaries: Vec<&PathBuf> = paths
+        .iter()
+        .filter(|path| {
+            let relative = path.strip_prefix(artifact_dir).unwrap_or(path);
+            let in_build = relative.components().any(|c| {
+                let name = c.as_os_str().to_string_lossy();
+                name == "build" || name.starts_with('.')
+            });
+            let ext = path.extension().and_then(|ext| ext.to_str());
+            !in_build && matches!(ext, None | Some("exe"))
+        })
+        .collect();
+    binaries.sort_by_key(|path| path.parent().is_some_and(|parent| parent.ends_with("deps")));
+    binaries.into_iter().cloned().collect()
+}
+
+/// Compiler stderr as a diagnostic, truncated to the limits.
+fn stderr_diagnostic(stderr: &str, limits: &InspectionLimits) -> Option<String> {
+    if stderr.trim().is_empty() {
+        return None;
+    }
+    let (stderr, truncated, _) = truncate_with_limits(stderr, limits);
+    let prefix = if truncated {
+        "Compiler stderr (truncated):\n"
+    } else {
+        "Compiler stderr:\n"
+    };
+    Some(format!("{prefix}{stderr}"))
+}
+
+/// How to rewrite extracted IR or assembly, from the tools' optional flags.
+fn annotate_options(
+    annotate_source: Option<bool>,
+    strip_directives: Option<bool>,
+) -> AnnotateOptions {
+    AnnotateOptions {
+        source_lines: annotate_source.unwrap_or(false),
+        strip_directives: strip_directives.unwrap_or(false),
+    }
+}
 
-        Ok(result)
+/// Source files named by debuginfo, read as they are first annotated.
+fn read_sources() -> SourceLines<impl FnMut(&Path) -> Option<String>> {
+    SourceLines::new(|path: &Path| std::fs::read_to_string(path).ok())
+}
+
+/// The settings of a build that an inspection diff varies, e.g.
+/// `opt-level=3 target=aarch64-unknown-linux-gnu`.
+fn describe_build(build: &RunRequest) -> String {
+    let mut settings = Vec::new();
+    if let Some(opt_level) = &build.opt_level {
+        settings.push(format!("opt-level={opt_level}"));
+    }
+    if let Some(target) = &build.target_triple {
+        settings.push(format!("target={target}"));
+    }
+    if !build.features.is_empty() {
+        settings.push(format!("features={}", build.features.join(",")));
+    }
+    settings.extend(build.additional_rustc_args.iter().cloned());
+    if settings.is_empty() {
+        "defaults".to_string()
+    } else {
+        settings.join(" ")
     }
 }
 
@@ -1439,6 +2833,7 @@ async fn read_artifacts(
 
 async fn load_assembly_artifacts(
     paths: &[PathBuf],
+    artifact_dir: &Path,
     target_hint: Option<&String>,
     limits: &InspectionLimits,
 ) -> Result<Vec<TargetedAssembly>, McpError> {
@@ -1465,7 +2860,7 @@ async fn load_assembly_artifacts(
 
         enforce_artifact_limit(path, content.len(), limits)?;
 
-        let target = infer_target_from_path(path)
+        let target = infer_target_from_path(path, artifact_dir)
             .or_else(|| target_hint.cloned())
             .unwrap_or_else(|| "unknown".to_string());
 
@@ -1475,20 +2870,21 @@ async fn load_assembly_artifacts(
     Ok(assemblies)
 }
 
-fn infer_target_from_path(path: &Path) -> Option<String> {
-    let mut components = path.components().peekable();
-    while let Some(component) = components.next() {
-        if component.as_os_str() == "mcp-inspections" {
-            if let Some(next) = components.next() {
-                let comp = next.as_os_str().to_string_lossy().into_owned();
-                if comp == "debug" || comp == "release" {
-                    return None;
-                }
-                return Some(comp);
-            }
-        }
-    }
-    None
+/// The target triple of a cross-compiled artifact, from its location under
+/// `artifact_dir`: `<triple>/<profile>/deps/..` rather than `<profile>/deps/..`.
+fn infer_target_from_path(path: &Path, artifact_dir: &Path) -> Option<String> {
+    let components: Vec<String> = path
+        .strip_prefix(artifact_dir)
+        .ok()?
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let is_output_dir = |index: usize| {
+        components.get(index).is_some_and(|name| {
+            matches!(name.as_str(), "deps" | "incremental" | "examples" | "build")
+        })
+    };
+    (!is_output_dir(1) && is_output_dir(2)).then(|| components[0].clone())
 }
 
 #[tool_handler]
@@ -1504,3 +2900,79 @@ impl ServerHandler for RustMcpServer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{RustAnalyzerClient, RustMcpServer, infer_target_from_path};
+    use serde_json::json;
+    use std::path::Path;
+
+    #[tokio::test]
+    async fn dispatches_every_listed_tool() {
+        let server = RustMcpServer::new();
+        for tool in server.list_tools() {
+            let error = server
+                .dispatch_tool(&tool.name, json!(42))
+                .await
+                .expect_err("a number is not a parameter object");
+            assert!(
+                error.message.starts_with("Invalid arguments"),
+                "{}: {}",
+                tool.name,
+                error.message
+            );
+        }
+    }
+
+    #[tokio::test]
+    #[allow(deprecated)]
+    async fn serves_the_deprecated_tool_functions_from_the_router() {
+        let names = |tools: Vec<crate::tools::ToolDefinition>| {
+            tools.into_iter().map(|tool| tool.name).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(crate::tools::get_tools()),
+            names(RustMcpServer::new().list_tools())
+        );
+
+        let mut analyzer = RustAnalyzerClient::new();
+        let error = crate::tools::execute_tool("find_definition", json!(42), &mut analyzer)
+            .await
+            .err()
+            .expect("a number is not a parameter object");
+        assert!(
+            error.to_string().starts_with("Invalid arguments"),
+            "{error}"
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_an_instance_for_mir() {
+        let server = RustMcpServer::new();
+        let error = server
+            .dispatch_tool(
+                "inspect_mir",
+                json!({"file_path": "src/lib.rs", "symbol_name": "id", "instance": "u8"}),
+            )
+            .await
+            .expect_err("MIR has no instances");
+        assert!(
+            error.message.contains("unknown field `instance`"),
+            "{}",
+            error.message
+        );
+    }
+
+    #[test]
+    fn infers_target_from_artifact_layout() {
+        let cached = Path::new("target/mcp-inspections/inspection-cache/0123abcd");
+        let target_of = |path: &str| infer_target_from_path(&cached.join(path), cached);
+
+        assert_eq!(
+            target_of("aarch64-unknown-linux-gnu/release/deps/demo-1f2e.s").as_deref(),
+            Some("aarch64-unknown-linux-gnu")
+        );
+        assert_eq!(target_of("debug/deps/demo-1f2e.s"), None);
+        assert_eq!(target_of("custom-profile/deps/demo-1f2e.s"), None);
+    }
+}
@@ -15,16 +15,86 @@ pub struct FindReferencesParams {
     pub character: u32,
 }
 
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindImplementationsParams {
+    pub file_path: String,
+    pub line: u32,
+    pub character: u32,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindTypeDefinitionParams {
+    pub file_path: String,
+    pub line: u32,
+    pub character: u32,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindDeclarationParams {
+    pub file_path: String,
+    pub line: u32,
+    pub character: u32,
+}
+
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct GetDiagnosticsParams {
     pub file_path: String,
 }
 
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct HoverParams {
+    pub file_path: String,
+    pub line: u32,
+    pub character: u32,
+    pub end_line: Option<u32>,
+    pub end_character: Option<u32>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct SignatureHelpParams {
+    pub file_path: String,
+    pub line: u32,
+    pub character: u32,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct CompleteAtParams {
+    pub file_path: String,
+    pub line: u32,
+    pub character: u32,
+    pub prefix: Option<String>,
+    pub max_results: Option<u32>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct InlayHintsParams {
+    pub file_path: String,
+    pub start_line: Option<u32>,
+    pub end_line: Option<u32>,
+}
+
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct WorkspaceSymbolsParams {
     pub query: String,
 }
 
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct DocumentOutlineParams {
+    pub file_path: String,
+    pub kinds: Option<Vec<String>>,
+    pub visibility: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct CallHierarchyParams {
+    pub file_path: String,
+    pub line: u32,
+    pub character: u32,
+    pub direction: Option<String>,
+    pub max_depth: Option<u32>,
+    pub max_nodes: Option<u32>,
+}
+
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct RenameSymbolParams {
     pub file_path: String,
@@ -48,6 +118,31 @@ pub struct RunCargoCheckParams {
     pub workspace_path: String,
 }
 
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ListRunnablesParams {
+    pub file_path: String,
+    pub line: Option<u32>,
+    pub character: Option<u32>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct RelatedTestsParams {
+    pub file_path: String,
+    pub line: u32,
+    pub character: u32,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct RunTestsParams {
+    pub package: Option<String>,
+    pub filter: Option<String>,
+    pub features: Option<Vec<String>>,
+    pub exact: Option<bool>,
+    pub rerun_failed: Option<bool>,
+    /// Seconds to allow the build and test run, 600 by default.
+    pub timeout_seconds: Option<u64>,
+}
+
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct ExtractFunctionParams {
     pub file_path: String,
@@ -108,6 +203,16 @@ pub struct OrganizeImportsParams {
     pub file_path: String,
 }
 
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct SsrParams {
+    pub rule: String,
+    pub scope: Option<String>,
+    pub file_path: Option<String>,
+    pub files: Option<Vec<String>>,
+    pub parse_only: Option<bool>,
+    pub search_only: Option<bool>,
+}
+
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct ApplyClippySuggestionsParams {
     pub file_path: String,
@@ -136,6 +241,7 @@ pub struct CreateModuleParams {
     pub module_name: String,
     pub module_path: String,
     pub is_public: bool,
+    pub reexport: Option<bool>,
 }
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
@@ -146,6 +252,17 @@ pub struct MoveItemsParams {
 }
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ExpandMacroParams {
+    pub file_path: String,
+    pub line: u32,
+    pub character: u32,
+    pub whole_item: Option<bool>,
+}
+
+/// Has no `instance`: MIR has one generic body per item, not one per
+/// instance, so passing it is rejected rather than ignored.
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct InspectMirParams {
     pub file_path: String,
     pub line: Option<u32>,
@@ -153,6 +270,11 @@ pub struct InspectMirParams {
     pub symbol_name: Option<String>,
     pub opt_level: Option<String>,
     pub target: Option<String>,
+    /// Features to enable on the package that owns `file_path`.
+    pub features: Option<Vec<String>>,
+    pub no_default_features: Option<bool>,
+    /// Cargo profile to build with, e.g. `release`.
+    pub profile: Option<String>,
 }
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
@@ -163,6 +285,18 @@ pub struct InspectLlvmIrParams {
     pub symbol_name: Option<String>,
     pub opt_level: Option<String>,
     pub target: Option<String>,
+    /// Features to enable on the package that owns `file_path`.
+    pub features: Option<Vec<String>>,
+    pub no_default_features: Option<bool>,
+    /// Cargo profile to build with, e.g. `release`.
+    pub profile: Option<String>,
+    /// Generic instance to show, e.g. `Vec<u8>`, or `all`.
+    pub instance: Option<String>,
+    /// Comment instructions with the source lines they came from. Builds
+    /// with `-Cdebuginfo=1`, so the build is cached apart from one without.
+    pub annotate_source: Option<bool>,
+    /// Drop assembler directives, unused labels and debug metadata.
+    pub strip_directives: Option<bool>,
 }
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
@@ -173,6 +307,18 @@ pub struct InspectAsmParams {
     pub symbol_name: Option<String>,
     pub opt_level: Option<String>,
     pub target: Option<String>,
+    /// Features to enable on the package that owns `file_path`.
+    pub features: Option<Vec<String>>,
+    pub no_default_features: Option<bool>,
+    /// Cargo profile to build with, e.g. `release`.
+    pub profile: Option<String>,
+    /// Generic instance to show, e.g. `Vec<u8>`, or `all`.
+    pub instance: Option<String>,
+    /// Comment instructions with the source lines they came from. Builds
+    /// with `-Cdebuginfo=1`, so the build is cached apart from one without.
+    pub annotate_source: Option<bool>,
+    /// Drop assembler directives, unused labels and debug metadata.
+    pub strip_directives: Option<bool>,
 }
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
@@ -184,9 +330,69 @@ pub struct InspectParams {
     pub symbol_name: Option<String>,
     pub opt_level: Option<String>,
     pub target: Option<String>,
+    /// Features to enable on the package that owns `file_path`.
+    pub features: Option<Vec<String>>,
+    pub no_default_features: Option<bool>,
+    /// Cargo profile to build with, e.g. `release`.
+    pub profile: Option<String>,
+    /// Generic instance to show for `llvm-ir` and `asm`, e.g. `Vec<u8>`, or `all`.
+    pub instance: Option<String>,
+    /// Comment instructions with the source lines they came from. Builds
+    /// with `-Cdebuginfo=1`, so the build is cached apart from one without.
+    pub annotate_source: Option<bool>,
+    /// Drop assembler directives, unused labels and debug metadata.
+    pub strip_directives: Option<bool>,
     pub gating_mode: Option<String>,
 }
 
+/// Build settings for one side of an inspection diff.
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct InspectDiffSide {
+    pub opt_level: Option<String>,
+    pub target: Option<String>,
+    /// Features to enable on the package that owns `file_path`.
+    pub features: Option<Vec<String>>,
+    /// Extra rustc flags, e.g. `-Ctarget-cpu=native`.
+    pub rustc_args: Option<Vec<String>>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct InspectDiffParams {
+    pub view: String,
+    pub file_path: String,
+    pub line: u32,
+    pub character: u32,
+    pub symbol_name: Option<String>,
+    /// Generic instance to show for `llvm-ir` and `asm`, e.g. `Vec<u8>`, or `all`.
+    pub instance: Option<String>,
+    pub no_default_features: Option<bool>,
+    /// Cargo profile to build both sides with, e.g. `release`.
+    pub profile: Option<String>,
+    pub before: InspectDiffSide,
+    pub after: InspectDiffSide,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct SizeReportParams {
+    /// Any file of the package and target to measure.
+    pub file_path: String,
+    /// Measure the linked binary of a bin target instead of its object file.
+    pub linked: Option<bool>,
+    pub opt_level: Option<String>,
+    pub target: Option<String>,
+    /// Features to enable on the package that owns `file_path`.
+    pub features: Option<Vec<String>>,
+    pub no_default_features: Option<bool>,
+    /// Cargo profile to build with; defaults to `release`.
+    pub profile: Option<String>,
+    /// Number of functions and crates to list; defaults to 25.
+    pub top: Option<usize>,
+    /// Store the full report under this name for later comparisons.
+    pub save_as: Option<String>,
+    /// Name of a stored report to diff this one against.
+    pub compare_to: Option<String>,
+}
+
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct CapabilitiesParams {
     pub gating_mode: Option<String>,
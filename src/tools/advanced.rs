@@ -1,6 +1,6 @@
 use crate::analyzer::RustAnalyzerClient;
 use crate::tools::types::ToolResult;
-use anyhow::Result;
+use anyhow::{Result, bail};
 use serde_json::{Value, json};
 
 pub async fn get_type_hierarchy_impl(
@@ -81,9 +81,13 @@ pub async fn create_module_impl(
         .get("is_public")
         .and_then(|v| v.as_bool())
         .unwrap_or(false);
+    let reexport = args
+        .get("reexport")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
 
     let result = analyzer
-        .create_module(module_name, module_path, is_public)
+        .create_module(module_name, module_path, is_public, reexport)
         .await?;
 
     Ok(ToolResult {
@@ -135,3 +139,48 @@ pub async fn move_items_impl(args: Value, analyzer: &mut RustAnalyzerClient) ->
         ],
     })
 }
+
+pub async fn expand_macro_impl(
+    args: Value,
+    analyzer: &mut RustAnalyzerClient,
+) -> Result<ToolResult> {
+    let file_path = args
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing file_path parameter"))?;
+    let line = args
+        .get("line")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("Missing line parameter"))?;
+    let character = args
+        .get("character")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("Missing character parameter"))?;
+
+    let result = match analyzer
+        .expand_macro(file_path, line as u32, character as u32)
+        .await?
+    {
+        Some(expanded) => format!(
+            "// Recursive expansion of {}!\n{}",
+            expanded.name, expanded.expansion
+        ),
+        None => bail!(
+            "No macro call, attribute or derive at {}:{}; set whole_item to expand the enclosing item with rustc",
+            line + 1,
+            character + 1
+        ),
+    };
+
+    Ok(ToolResult {
+        content: vec![
+            json!({
+                "type": "text",
+                "text": result
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ],
+    })
+}
@@ -1,4 +1,7 @@
-use crate::analyzer::RustAnalyzerClient;
+use crate::analyzer::completion::DEFAULT_MAX_COMPLETIONS;
+use crate::analyzer::protocol::{Position, Range};
+use crate::analyzer::{GotoKind, RustAnalyzerClient};
+use crate::inspection::InspectionLimits;
 use crate::tools::types::ToolResult;
 use anyhow::Result;
 use serde_json::{Value, json};
@@ -97,3 +100,235 @@ pub async fn get_diagnostics_impl(
         ],
     })
 }
+
+pub async fn hover_impl(args: Value, analyzer: &mut RustAnalyzerClient) -> Result<ToolResult> {
+    let file_path = args
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing file_path parameter"))?;
+    let line = args
+        .get("line")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("Missing line parameter"))?;
+    let character = args
+        .get("character")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("Missing character parameter"))?;
+    let end_line = args.get("end_line").and_then(|v| v.as_u64());
+    let end_character = args.get("end_character").and_then(|v| v.as_u64());
+    let end = match (end_line, end_character) {
+        (Some(line), Some(character)) => Some(Position {
+            line: line as u32,
+            character: character as u32,
+        }),
+        (None, None) => None,
+        _ => {
+            return Err(anyhow::anyhow!(
+                "end_line and end_character must be given together"
+            ));
+        }
+    };
+
+    let result = analyzer
+        .hover(file_path, line as u32, character as u32, end)
+        .await?;
+
+    Ok(ToolResult {
+        content: vec![
+            json!({
+                "type": "text",
+                "text": result
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ],
+    })
+}
+
+pub async fn inlay_hints_impl(
+    args: Value,
+    analyzer: &mut RustAnalyzerClient,
+) -> Result<ToolResult> {
+    let file_path = args
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing file_path parameter"))?;
+    let start_line = args.get("start_line").and_then(|v| v.as_u64());
+    let end_line = args.get("end_line").and_then(|v| v.as_u64());
+    let range = match (start_line, end_line) {
+        (None, None) => None,
+        (start, end) => {
+            let start = start.unwrap_or(0) as u32;
+            let end = end.map(|line| line as u32).unwrap_or(u32::MAX - 1);
+            if end < start {
+                return Err(anyhow::anyhow!("end_line must not be before start_line"));
+            }
+            Some(Range {
+                start: Position {
+                    line: start,
+                    character: 0,
+                },
+                end: Position {
+                    line: end,
+                    character: 0,
+                },
+            })
+        }
+    };
+
+    let result = analyzer
+        .inlay_hints(file_path, range, &InspectionLimits::default())
+        .await?;
+
+    Ok(ToolResult {
+        content: vec![
+            json!({
+                "type": "text",
+                "text": result
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ],
+    })
+}
+
+pub async fn find_implementations_impl(
+    args: Value,
+    analyzer: &mut RustAnalyzerClient,
+) -> Result<ToolResult> {
+    goto_locations_impl(GotoKind::Implementation, args, analyzer).await
+}
+
+pub async fn find_type_definition_impl(
+    args: Value,
+    analyzer: &mut RustAnalyzerClient,
+) -> Result<ToolResult> {
+    goto_locations_impl(GotoKind::TypeDefinition, args, analyzer).await
+}
+
+pub async fn find_declaration_impl(
+    args: Value,
+    analyzer: &mut RustAnalyzerClient,
+) -> Result<ToolResult> {
+    goto_locations_impl(GotoKind::Declaration, args, analyzer).await
+}
+
+async fn goto_locations_impl(
+    kind: GotoKind,
+    args: Value,
+    analyzer: &mut RustAnalyzerClient,
+) -> Result<ToolResult> {
+    let file_path = args
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing file_path parameter"))?;
+    let line = args
+        .get("line")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("Missing line parameter"))?;
+    let character = args
+        .get("character")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("Missing character parameter"))?;
+
+    let result = analyzer
+        .goto_locations(kind, file_path, line as u32, character as u32)
+        .await?;
+
+    Ok(ToolResult {
+        content: vec![
+            json!({
+                "type": "text",
+                "text": result
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ],
+    })
+}
+
+pub async fn signature_help_impl(
+    args: Value,
+    analyzer: &mut RustAnalyzerClient,
+) -> Result<ToolResult> {
+    let file_path = args
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing file_path parameter"))?;
+    let line = args
+        .get("line")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("Missing line parameter"))?;
+    let character = args
+        .get("character")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("Missing character parameter"))?;
+
+    let result = analyzer
+        .signature_help(file_path, line as u32, character as u32)
+        .await?;
+
+    Ok(ToolResult {
+        content: vec![
+            json!({
+                "type": "text",
+                "text": result
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ],
+    })
+}
+
+pub async fn complete_at_impl(
+    args: Value,
+    analyzer: &mut RustAnalyzerClient,
+) -> Result<ToolResult> {
+    let file_path = args
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing file_path parameter"))?;
+    let line = args
+        .get("line")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("Missing line parameter"))?;
+    let character = args
+        .get("character")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("Missing character parameter"))?;
+    let prefix = args
+        .get("prefix")
+        .and_then(|v| v.as_str())
+        .filter(|prefix| !prefix.is_empty());
+    let max_results = args
+        .get("max_results")
+        .and_then(|v| v.as_u64())
+        .map(|max| max as usize)
+        .unwrap_or(DEFAULT_MAX_COMPLETIONS);
+
+    let result = analyzer
+        .complete_at(
+            file_path,
+            line as u32,
+            character as u32,
+            prefix,
+            max_results,
+        )
+        .await?;
+
+    Ok(ToolResult {
+        content: vec![
+            json!({
+                "type": "text",
+                "text": result
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ],
+    })
+}
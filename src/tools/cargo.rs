@@ -1,6 +1,12 @@
 use crate::analyzer::RustAnalyzerClient;
+use crate::analyzer::protocol::Position;
+use crate::compiler::libtest::{
+    FailedTests, TestStatus, parse_json_events, parse_text_output, render_test_report,
+};
+use crate::compiler::{CompilerRunner, TestRequest};
+use crate::inspection::{InspectionContext, truncate_with_limits};
 use crate::tools::types::ToolResult;
-use anyhow::Result;
+use anyhow::{Result, bail};
 use serde_json::{Value, json};
 
 pub async fn analyze_manifest_impl(
@@ -52,3 +58,175 @@ pub async fn run_cargo_check_impl(
         ],
     })
 }
+
+pub async fn list_runnables_impl(
+    args: Value,
+    analyzer: &mut RustAnalyzerClient,
+) -> Result<ToolResult> {
+    let file_path = args
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing file_path parameter"))?;
+    let line = args.get("line").and_then(|v| v.as_u64());
+    let character = args.get("character").and_then(|v| v.as_u64());
+    let position = match (line, character) {
+        (Some(line), character) => Some(Position {
+            line: line as u32,
+            character: character.unwrap_or(0) as u32,
+        }),
+        (None, Some(_)) => return Err(anyhow::anyhow!("character requires line")),
+        (None, None) => None,
+    };
+
+    let result = analyzer.list_runnables(file_path, position).await?;
+
+    Ok(ToolResult {
+        content: vec![
+            json!({
+                "type": "text",
+                "text": result
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ],
+    })
+}
+
+pub async fn related_tests_impl(
+    args: Value,
+    analyzer: &mut RustAnalyzerClient,
+) -> Result<ToolResult> {
+    let file_path = args
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing file_path parameter"))?;
+    let line = args
+        .get("line")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("Missing line parameter"))?;
+    let character = args
+        .get("character")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("Missing character parameter"))?;
+
+    let result = analyzer
+        .related_tests(file_path, line as u32, character as u32)
+        .await?;
+
+    Ok(ToolResult {
+        content: vec![
+            json!({
+                "type": "text",
+                "text": result
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ],
+    })
+}
+
+pub async fn run_tests_impl(args: Value, context: &InspectionContext) -> Result<ToolResult> {
+    let package = args
+        .get("package")
+        .and_then(|v| v.as_str())
+        .map(str::to_string);
+    let filter = args
+        .get("filter")
+        .and_then(|v| v.as_str())
+        .map(str::to_string);
+    let features: Option<Vec<String>> =
+        args.get("features")
+            .and_then(|v| v.as_array())
+            .map(|features| {
+                features
+                    .iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect()
+            });
+    let exact = args.get("exact").and_then(|v| v.as_bool()).unwrap_or(false);
+    let rerun_failed = args
+        .get("rerun_failed")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let target_dir = context.target_dir();
+    let mut request = TestRequest {
+        package,
+        features: features.clone().unwrap_or_default(),
+        filters: filter.into_iter().collect(),
+        exact,
+        json_output: context.toolchain_channel().is_nightly_like(),
+        env: context.env().clone(),
+        ..TestRequest::default()
+    };
+    // Backtraces would bury the panic messages.
+    request
+        .env
+        .entry("RUST_BACKTRACE".to_string())
+        .or_insert_with(|| "0".to_string());
+    if rerun_failed {
+        let Some(failed) = FailedTests::load(&target_dir)
+            .await?
+            .filter(|failed| !failed.names.is_empty())
+        else {
+            bail!("No failed tests recorded from a previous run_tests call");
+        };
+        request.package = request.package.or(failed.package);
+        request.features = features.unwrap_or(failed.features);
+        request.filters = failed.names;
+        request.exact = true;
+    }
+    let (package, features) = (request.package.clone(), request.features.clone());
+    let json_output = request.json_output;
+
+    let run = {
+        let _workspace_guard = context.lock_workspace().await;
+        CompilerRunner::with_target_dir(&target_dir)
+            .run_tests(request, context.limits())
+            .await?
+    };
+    let command = run.command.join(" ");
+
+    let outcomes = if json_output {
+        parse_json_events(&run.stdout)
+    } else {
+        parse_text_output(&run.stdout)
+    };
+    if outcomes.is_empty() && !run.status.success() {
+        let (stderr, _, _) = truncate_with_limits(run.stderr.trim(), context.limits());
+        bail!("`{command}` failed before running any tests:\n{stderr}");
+    }
+
+    FailedTests {
+        package,
+        features,
+        names: outcomes
+            .iter()
+            .filter(|outcome| outcome.status == TestStatus::Failed)
+            .map(|outcome| outcome.name.clone())
+            .collect(),
+    }
+    .save(&target_dir)
+    .await?;
+
+    let report = format!(
+        "`{command}` ({})\n{}",
+        run.status,
+        render_test_report(&outcomes)
+    );
+    let (result, _, _) = truncate_with_limits(&report, context.limits());
+
+    Ok(ToolResult {
+        content: vec![
+            json!({
+                "type": "text",
+                "text": result
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ],
+    })
+}
@@ -1,4 +1,6 @@
 use crate::analyzer::RustAnalyzerClient;
+use crate::analyzer::calls::{CallDirection, DEFAULT_MAX_DEPTH, DEFAULT_MAX_NODES};
+use crate::analyzer::outline::VisibilityFilter;
 use crate::tools::types::ToolResult;
 use anyhow::Result;
 use serde_json::{Value, json};
@@ -27,3 +29,102 @@ pub async fn workspace_symbols_impl(
         ],
     })
 }
+
+pub async fn call_hierarchy_impl(
+    args: Value,
+    analyzer: &mut RustAnalyzerClient,
+) -> Result<ToolResult> {
+    let file_path = args
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing file_path parameter"))?;
+    let line = args
+        .get("line")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("Missing line parameter"))?;
+    let character = args
+        .get("character")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("Missing character parameter"))?;
+    let direction: CallDirection = args
+        .get("direction")
+        .and_then(|v| v.as_str())
+        .unwrap_or("incoming")
+        .parse()?;
+    let max_depth = args
+        .get("max_depth")
+        .and_then(|v| v.as_u64())
+        .map(|depth| depth as usize)
+        .unwrap_or(DEFAULT_MAX_DEPTH);
+    let max_nodes = args
+        .get("max_nodes")
+        .and_then(|v| v.as_u64())
+        .map(|nodes| nodes as usize)
+        .unwrap_or(DEFAULT_MAX_NODES);
+
+    let result = analyzer
+        .call_hierarchy(
+            file_path,
+            line as u32,
+            character as u32,
+            direction,
+            max_depth,
+            max_nodes,
+        )
+        .await?;
+
+    Ok(ToolResult {
+        content: vec![
+            json!({
+                "type": "text",
+                "text": result
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ],
+    })
+}
+
+pub async fn document_outline_impl(
+    args: Value,
+    analyzer: &mut RustAnalyzerClient,
+) -> Result<ToolResult> {
+    let file_path = args
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing file_path parameter"))?;
+    let kinds: Vec<String> = match args.get("kinds") {
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| anyhow::anyhow!("kinds must be an array of strings"))
+            })
+            .collect::<Result<_>>()?,
+        Some(Value::Null) | None => Vec::new(),
+        Some(_) => return Err(anyhow::anyhow!("kinds must be an array of strings")),
+    };
+    let visibility = args
+        .get("visibility")
+        .and_then(|v| v.as_str())
+        .map(str::parse::<VisibilityFilter>)
+        .transpose()?;
+
+    let result = analyzer
+        .document_outline(file_path, &kinds, visibility)
+        .await?;
+
+    Ok(ToolResult {
+        content: vec![
+            json!({
+                "type": "text",
+                "text": result
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ],
+    })
+}
@@ -1,4 +1,5 @@
 use crate::analyzer::RustAnalyzerClient;
+use crate::analyzer::ssr::{SsrMode, SsrScope};
 use crate::tools::types::ToolResult;
 use anyhow::Result;
 use serde_json::{Value, json};
@@ -191,3 +192,55 @@ pub async fn organize_imports_impl(
         ],
     })
 }
+
+pub async fn ssr_impl(args: Value, analyzer: &mut RustAnalyzerClient) -> Result<ToolResult> {
+    let rule = args
+        .get("rule")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing rule parameter"))?;
+    let file_path = args.get("file_path").and_then(|v| v.as_str());
+    let files: Vec<String> = args
+        .get("files")
+        .and_then(|v| v.as_array())
+        .map(|files| {
+            files
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    let scope = args
+        .get("scope")
+        .and_then(|v| v.as_str())
+        .unwrap_or(if files.is_empty() { "file" } else { "files" });
+    let scope = SsrScope::parse(scope, file_path, files)?;
+    let mode = if args
+        .get("parse_only")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+    {
+        SsrMode::ParseOnly
+    } else if args
+        .get("search_only")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+    {
+        SsrMode::Search
+    } else {
+        SsrMode::Apply
+    };
+
+    let result = analyzer.ssr(rule, &scope, mode).await?;
+
+    Ok(ToolResult {
+        content: vec![
+            json!({
+                "type": "text",
+                "text": result
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ],
+    })
+}